@@ -0,0 +1,100 @@
+//! Compares a large round robin, which dominates the run time of tournaments
+//! with big group stages, as the group stage plays it, with the points of
+//! every round robin and tiebreak in one buffer indexed by position, to a
+//! baseline that counts the points of each round robin in a new HashMap. Run
+//! with `cargo bench --bench group_stage`.
+
+use std::collections::HashMap;
+use std::time::Duration;
+use std::time::Instant;
+
+use rand::seq::SliceRandom;
+use rand::Rng;
+use tournament_sim::component::Component;
+use tournament_sim::model::MatchModel;
+use tournament_sim::rng::stream_rng;
+use tournament_sim::Team;
+
+const NUM_TEAMS: usize = 32;
+const NUM_RUNS: usize = 2000;
+const NUM_GAMES_PER_SERIES: usize = 3;
+const MAX_TIEBREAK_DEPTH: usize = 100;
+
+/// Plays the round robin like the group stage with its default tiebreak, i.e.
+/// ties are replayed and broken at random below `MAX_TIEBREAK_DEPTH`.
+fn hash_map_group_stage(teams: &mut [Team], model: &MatchModel, rng: &mut impl Rng, depth: usize) {
+    let mut points: HashMap<usize, i64> = (0..teams.len()).map(|i| (i, 0)).collect();
+    for i in 0..teams.len() {
+        for j in i + 1..teams.len() {
+            let p = model.match_probability(&teams[i], &teams[j], rng);
+            for _ in 0..NUM_GAMES_PER_SERIES {
+                let winner = if model.wins_game(p, rng) { i } else { j };
+                *points.get_mut(&winner).unwrap() += 1;
+            }
+        }
+    }
+    let mut order: Vec<usize> = (0..teams.len()).collect();
+    order.sort_by_key(|i| -points[i]);
+    let sorted: Vec<Team> = order.iter().map(|i| teams[*i]).collect();
+    teams.copy_from_slice(&sorted);
+    let points: Vec<i64> = order.iter().map(|i| points[i]).collect();
+    let mut start = 0;
+    while start < teams.len() {
+        let end = start
+            + points[start..]
+                .iter()
+                .take_while(|p| **p == points[start])
+                .count();
+        let tied = &mut teams[start..end];
+        if tied.len() > 1 {
+            if depth >= MAX_TIEBREAK_DEPTH {
+                tied.shuffle(rng);
+            } else {
+                hash_map_group_stage(tied, model, rng, depth + 1);
+            }
+        }
+        start = end;
+    }
+}
+
+/// The time of `NUM_RUNS` runs of `run` and the final order of every run.
+fn time(mut run: impl FnMut(&mut [Team], u64)) -> (Duration, Vec<Vec<Team>>) {
+    let mut orders = Vec::with_capacity(NUM_RUNS);
+    let start = Instant::now();
+    for seed in 0..NUM_RUNS as u64 {
+        let mut teams: Vec<_> = (0..NUM_TEAMS)
+            .map(|index| Team::with_strength(index, index == 0))
+            .collect();
+        run(&mut teams, seed);
+        orders.push(teams);
+    }
+    (start.elapsed(), orders)
+}
+
+fn main() {
+    let group = serde_yaml::from_str(&format!(
+        "!GroupStage {{num_games_per_series: {}, max_tiebreak_depth: {}}}",
+        NUM_GAMES_PER_SERIES, MAX_TIEBREAK_DEPTH
+    ))
+    .unwrap();
+    let component = Component::new(group, vec![(); NUM_TEAMS]);
+    let model = MatchModel::default();
+    let (buffered, buffered_orders) = time(|teams, seed| {
+        component.run(teams, &model, &mut stream_rng(seed, 0));
+    });
+    let (hash_map, hash_map_orders) = time(|teams, seed| {
+        hash_map_group_stage(teams, &model, &mut stream_rng(seed, 0), 0);
+    });
+    assert_eq!(buffered_orders, hash_map_orders);
+    println!("{} runs of a {} team group stage", NUM_RUNS, NUM_TEAMS);
+    println!(
+        "points buffer: {:.3?} ({:.3?} per run)",
+        buffered,
+        buffered / NUM_RUNS as u32
+    );
+    println!(
+        "HashMap:       {:.3?} ({:.3?} per run)",
+        hash_map,
+        hash_map / NUM_RUNS as u32
+    );
+}
@@ -0,0 +1,59 @@
+//! Compares simulating every run on its own, with a clone of the runner and
+//! new statistics per run that are merged pairwise, to [`simulate`] over all
+//! runs, which reuses one runner per batch and adds every run to one set of
+//! statistics in place. Run with
+//! `cargo bench --bench reduction`.
+
+use std::time::Duration;
+use std::time::Instant;
+
+use rayon::iter::IntoParallelIterator;
+use rayon::iter::ParallelIterator;
+use tournament_sim::parse_tournaments;
+use tournament_sim::report::SimulationStats;
+use tournament_sim::runner::Runner;
+use tournament_sim::simulation::get_teams;
+use tournament_sim::simulation::simulate;
+
+const NUM_RUNS: usize = 1000000;
+
+const TOURNAMENT: &str = "
+components:
+  semi1:
+    type: !BestOf1
+    teams: [0, 3]
+  semi2:
+    type: !BestOf1
+    teams: [1, 2]
+  final:
+    type: !BestOf3
+    teams: [[winner, semi1], [winner, semi2]]
+scoring:
+  [winner, final]: 1.0
+";
+
+fn time(f: impl FnOnce()) -> Duration {
+    let start = Instant::now();
+    f();
+    start.elapsed()
+}
+
+fn main() {
+    let runner = Runner::new(parse_tournaments(TOURNAMENT).unwrap().remove(0)).unwrap();
+    let get_teams = |rng: &mut _| get_teams(4, rng);
+    let mut per_run = SimulationStats::default();
+    let cloned = time(|| {
+        per_run = (0..NUM_RUNS)
+            .into_par_iter()
+            .map(|run| simulate(&runner, 4, run..run + 1, Some(0), &get_teams))
+            .reduce(SimulationStats::default, SimulationStats::merge);
+    });
+    let mut batched_stats = SimulationStats::default();
+    let batched = time(|| {
+        batched_stats = simulate(&runner, 4, 0..NUM_RUNS, Some(0), &get_teams);
+    });
+    assert_eq!(per_run.advantage.count(), batched_stats.advantage.count());
+    println!("{} runs", NUM_RUNS);
+    println!("clone per run: {:.3?}", cloned);
+    println!("batched fold:  {:.3?}", batched);
+}
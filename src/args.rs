@@ -0,0 +1,313 @@
+use std::fs;
+use std::path::Path;
+use std::time::Duration;
+use std::time::Instant;
+
+const TOURNAMENT_EXTENSIONS: [&str; 3] = ["yml", "yaml", "json"];
+
+/// A calibration mode that checks the measurement instead of the format.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Control {
+    /// The measured team is chosen at random but has no edge, so the
+    /// advantage should be compatible with zero.
+    NoStrongTeam,
+    /// The strong team is always the top seed.
+    FixedTopSeed,
+}
+
+/// Command line options. Everything that is not a flag is a tournament
+/// file or a directory which is searched recursively for tournament files.
+#[derive(Debug, Default)]
+pub struct Args {
+    pub files: Vec<String>,
+    /// Print a description of each component instead of simulating.
+    pub describe: bool,
+    /// Print each tournament as YAML with every field given explicitly
+    /// instead of simulating.
+    pub normalize: bool,
+    /// Print the differences between the components and scoring of the
+    /// tournaments in these two files instead of simulating.
+    pub structural_diff: Option<(String, String)>,
+    /// Write the component graph of each tournament to this file in the
+    /// Graphviz DOT format instead of simulating.
+    pub dot: Option<String>,
+    /// Additionally measure the advantage of a top-seeded strong team with
+    /// perfect seeding and with this probability of swapping adjacent seeds.
+    pub seeding_error: Option<f64>,
+    /// Also simulate the first tournament in this file with the same random
+    /// numbers and report the advantage as a multiple of its advantage.
+    pub baseline: Option<String>,
+    /// Take the ratings of the teams from this CSV file instead of the
+    /// tournament files.
+    pub ratings: Option<String>,
+    /// The number of runs, overriding the number given in the tournament file.
+    pub num_runs: Option<usize>,
+    /// Instead of a fixed number of runs, simulate until the half width of
+    /// the confidence interval of the advantage is below this value.
+    pub precision: Option<f64>,
+    /// Stop every simulation that is still running at this time, which is
+    /// given on the command line as a duration such as `10s`, and report
+    /// the advantage from the runs completed until then.
+    pub deadline: Option<Instant>,
+    /// Free the placements of each component during a run once nothing
+    /// refers to them anymore.
+    pub low_memory: bool,
+    /// Skip the check that every team can win the tournament.
+    pub no_sanity: bool,
+    /// Derive the seed of run `i` from this seed and `i`, so that results
+    /// are reproducible and different files are simulated with correlated
+    /// randomness.
+    pub base_seed: Option<u64>,
+    /// Print the expected payout to the strong team, treating the scores as
+    /// prize money.
+    pub payout: bool,
+    /// Repeat the whole simulation this many times with independent seeds
+    /// and report the spread of the advantage between the repetitions.
+    pub repeat: usize,
+    /// Also simulate a strong team that wins every game, which bounds the
+    /// advantage the format can give.
+    pub max_advantage: bool,
+    /// For tournaments with ratings, seed the teams by their rating plus
+    /// normally distributed noise with each of these standard deviations
+    /// and report the title probability of the highest rated team.
+    pub seeding_noise: Vec<f64>,
+    /// Pull every win probability `p` towards a coin flip, to
+    /// `0.5 + (p - 0.5) * (1 - chaos)`.
+    pub chaos: Option<f64>,
+    /// Let the strong team win each game with probability `0.5 + edge`
+    /// instead of `0.5 + STRONG_TEAM_ADVANTAGE`. Zero or negative edges
+    /// measure how the format treats an equal or a weaker team. The ratings
+    /// of rated teams are kept, with the edge on top of the strong team's.
+    pub edge: Option<f64>,
+    /// Write the results of the `--seeding-noise` and `--seeding-error`
+    /// sweeps to this file as columns for gnuplot.
+    pub plot_data: Option<String>,
+    /// Run a calibration mode instead of the regular measurement.
+    pub control: Option<Control>,
+    /// Print how often the strong team finishes in each position of the
+    /// component with this name.
+    pub positions: Option<String>,
+    /// Also print the advantage within the runs in which the strong team
+    /// played in the component with this name.
+    pub given: Option<String>,
+    /// Print how much each component contributes to the advantage.
+    pub explain: bool,
+    /// Print the components ranked by how sensitive the advantage is to the
+    /// edge of the strong team in them.
+    pub sensitivity: bool,
+    /// Print how often each seed wins the title if every game is a coin
+    /// flip, which reveals structural biases towards some seeds.
+    pub seed_titles: bool,
+    /// Print how often the lower seed won a match in each component.
+    pub upsets: bool,
+    /// Print the JSON Schema of tournament files and exit.
+    pub emit_schema: bool,
+    /// Insert a row per simulated tournament into this SQLite database.
+    pub sqlite: Option<String>,
+    /// Test whether each format amplifies the edge more than the baseline,
+    /// with a paired test over runs with the same random numbers.
+    pub paired_test: bool,
+    /// The number of strong teams, which are planted at random seeds and
+    /// are even against each other.
+    pub strong_teams: usize,
+}
+
+impl Args {
+    pub fn parse(mut args: impl Iterator<Item = String>) -> Self {
+        let mut parsed = Self {
+            repeat: 1,
+            strong_teams: 1,
+            ..Self::default()
+        };
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--describe" => parsed.describe = true,
+                "--normalize" => parsed.normalize = true,
+                "--no-sanity" => parsed.no_sanity = true,
+                "--low-memory" => parsed.low_memory = true,
+                "--upsets" => parsed.upsets = true,
+                "--seed-titles" => parsed.seed_titles = true,
+                "--paired-test" => parsed.paired_test = true,
+                "--payout" => parsed.payout = true,
+                "--max-advantage" => parsed.max_advantage = true,
+                "--explain" => parsed.explain = true,
+                "--sensitivity" => parsed.sensitivity = true,
+                "--repeat" => {
+                    parsed.repeat = match value(&arg, args.next()).parse() {
+                        Ok(repeat) if repeat > 0 => repeat,
+                        _ => fail("--repeat expects a positive integer"),
+                    }
+                }
+                "--strong-teams" => {
+                    parsed.strong_teams = match value(&arg, args.next()).parse() {
+                        Ok(strong_teams) if strong_teams > 0 => strong_teams,
+                        _ => fail("--strong-teams expects a positive integer"),
+                    }
+                }
+                "--base-seed" => {
+                    parsed.base_seed = Some(
+                        value(&arg, args.next())
+                            .parse()
+                            .unwrap_or_else(|_| fail("--base-seed expects a non-negative integer")),
+                    )
+                }
+                "--emit-schema" if cfg!(feature = "schema") => parsed.emit_schema = true,
+                "--emit-schema" => fail("--emit-schema requires building with the schema feature"),
+                "--sqlite" if cfg!(feature = "sqlite") => {
+                    parsed.sqlite = Some(value(&arg, args.next()))
+                }
+                "--sqlite" => fail("--sqlite requires building with the sqlite feature"),
+                "--plot-data" => parsed.plot_data = Some(value(&arg, args.next())),
+                "--positions" => parsed.positions = Some(value(&arg, args.next())),
+                "--given" => parsed.given = Some(value(&arg, args.next())),
+                "--dot" => parsed.dot = Some(value(&arg, args.next())),
+                "--ratings" => parsed.ratings = Some(value(&arg, args.next())),
+                "--baseline" => parsed.baseline = Some(value(&arg, args.next())),
+                "--structural-diff" => {
+                    let old = value(&arg, args.next());
+                    parsed.structural_diff = Some((old, value(&arg, args.next())))
+                }
+                "--seeding-error" => {
+                    parsed.seeding_error = Some(parse_probability(&arg, args.next()))
+                }
+                "--seeding-noise" => {
+                    parsed.seeding_noise = value(&arg, args.next())
+                        .split(',')
+                        .map(|noise| match noise.trim().parse::<f64>() {
+                            Ok(noise) if noise >= 0.0 => noise,
+                            _ => {
+                                fail("--seeding-noise expects comma separated non-negative numbers")
+                            }
+                        })
+                        .collect()
+                }
+                "--chaos" => parsed.chaos = Some(parse_probability(&arg, args.next())),
+                "--edge" => {
+                    parsed.edge = match value(&arg, args.next()).parse::<f64>() {
+                        Ok(edge) if (-0.5..=0.5).contains(&edge) => Some(edge),
+                        _ => fail("--edge expects a number between -0.5 and 0.5"),
+                    }
+                }
+                "--control" => {
+                    parsed.control = Some(match value(&arg, args.next()).as_str() {
+                        "none" => Control::NoStrongTeam,
+                        "fixed0" => Control::FixedTopSeed,
+                        _ => fail("--control expects none or fixed0"),
+                    })
+                }
+                "--num-runs" => {
+                    parsed.num_runs = match value(&arg, args.next()).parse() {
+                        Ok(num_runs) if num_runs > 0 => Some(num_runs),
+                        _ => fail("--num-runs expects a positive integer"),
+                    }
+                }
+                "--precision" => parsed.precision = Some(parse_positive(&arg, args.next())),
+                "--timeout" => {
+                    let timeout = parse_duration(&value(&arg, args.next())).unwrap_or_else(|| {
+                        fail("--timeout expects a duration such as 10s, 500ms or 2m")
+                    });
+                    parsed.deadline = Some(Instant::now() + timeout)
+                }
+                flag if flag.starts_with("--") => fail(&format!("Unknown option: {}", flag)),
+                _ => parsed.files.extend(find_tournament_files(Path::new(&arg))),
+            }
+        }
+        if parsed.paired_test && parsed.baseline.is_none() {
+            fail("--paired-test requires --baseline");
+        }
+        parsed
+    }
+}
+
+fn find_tournament_files(path: &Path) -> Vec<String> {
+    if !path.is_dir() {
+        return vec![path.to_string_lossy().into_owned()];
+    }
+    let mut entries: Vec<_> = fs::read_dir(path)
+        .unwrap_or_else(|e| fail(&format!("Cannot read directory {}: {}", path.display(), e)))
+        .map(|entry| entry.unwrap().path())
+        .collect();
+    entries.sort();
+    entries
+        .into_iter()
+        .filter(|entry| {
+            entry.is_dir()
+                || entry
+                    .extension()
+                    .is_some_and(|ext| TOURNAMENT_EXTENSIONS.iter().any(|e| ext == *e))
+        })
+        .flat_map(|entry| find_tournament_files(&entry))
+        .collect()
+}
+
+fn value(flag: &str, value: Option<String>) -> String {
+    value.unwrap_or_else(|| fail(&format!("Missing value for {}", flag)))
+}
+
+fn parse_probability(flag: &str, arg: Option<String>) -> f64 {
+    match value(flag, arg).parse::<f64>() {
+        Ok(p) if (0.0..=1.0).contains(&p) => p,
+        _ => fail(&format!("{} expects a probability between 0 and 1", flag)),
+    }
+}
+
+fn parse_positive(flag: &str, arg: Option<String>) -> f64 {
+    match value(flag, arg).parse::<f64>() {
+        Ok(x) if x > 0.0 => x,
+        _ => fail(&format!("{} expects a positive number", flag)),
+    }
+}
+
+/// A positive number of seconds, with an optional unit of `ms`, `s`, `m`
+/// or `h`.
+fn parse_duration(arg: &str) -> Option<Duration> {
+    let (number, seconds_per_unit) = [("ms", 0.001), ("s", 1.0), ("m", 60.0), ("h", 3600.0)]
+        .into_iter()
+        .find_map(|(unit, seconds)| Some((arg.strip_suffix(unit)?, seconds)))
+        .unwrap_or((arg, 1.0));
+    match number.trim().parse::<f64>() {
+        Ok(number) if number > 0.0 && number.is_finite() => {
+            Some(Duration::from_secs_f64(number * seconds_per_unit))
+        }
+        _ => None,
+    }
+}
+
+fn fail(message: &str) -> ! {
+    eprintln!("{}", message);
+    std::process::exit(1);
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::time::Duration;
+
+    use super::find_tournament_files;
+    use super::parse_duration;
+
+    #[test]
+    fn directories_are_searched_recursively() {
+        let dir = std::env::temp_dir().join(format!("tournament_files_{}", std::process::id()));
+        fs::create_dir_all(dir.join("sub")).unwrap();
+        for file in ["b.yml", "a.json", "notes.txt", "sub/c.yaml"] {
+            fs::write(dir.join(file), "").unwrap();
+        }
+        let found: Vec<_> = find_tournament_files(&dir)
+            .into_iter()
+            .map(|file| file.strip_prefix(dir.to_str().unwrap()).unwrap().to_owned())
+            .collect();
+        fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(found, vec!["/a.json", "/b.yml", "/sub/c.yaml"]);
+    }
+
+    #[test]
+    fn durations() {
+        assert_eq!(parse_duration("10s"), Some(Duration::from_secs(10)));
+        assert_eq!(parse_duration("500ms"), Some(Duration::from_millis(500)));
+        assert_eq!(parse_duration("2m"), Some(Duration::from_secs(120)));
+        assert_eq!(parse_duration("1.5"), Some(Duration::from_millis(1500)));
+        assert_eq!(parse_duration("0s"), None);
+        assert_eq!(parse_duration("ten seconds"), None);
+    }
+}
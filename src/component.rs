@@ -1,21 +1,122 @@
+use std::cmp::Ordering;
+use std::cmp::Reverse;
+use std::fmt;
+use std::ops::Range;
+
 use hashbrown::HashMap;
 use itertools::Itertools;
-use rand::rngs::ThreadRng;
+use rand::seq::SliceRandom;
 use rand::Rng;
+use rand_distr::Distribution;
+use rand_distr::Poisson;
 use serde::Deserialize;
+use serde::Serialize;
 
+use crate::math::best_of_n_win_probability;
+use crate::math::best_of_n_win_probability_with_handicap;
 use crate::math::binomial_distribution;
+use crate::math::expected_num_games;
+use crate::model::MatchModel;
+use crate::model::MatchupOverride;
 use crate::Team;
 
 type TeamIndex = usize;
 
-#[derive(Deserialize, Debug, Clone)]
+/// What a component reports about a run besides the order of its teams.
+#[derive(Debug, Default)]
+pub struct Outcome {
+    /// The depth of the deepest tiebreak that was needed.
+    pub tiebreak_depth: usize,
+    /// Ranges of positions whose teams are genuinely tied. The runner hands
+    /// them on to downstream components in random order.
+    pub ties: Vec<Range<usize>>,
+    /// The number of matches (series or pairings within a group) that were decided.
+    pub matches: usize,
+    /// The number of decided matches won by the lower seed, i.e. the team
+    /// that comes later in the input of the component.
+    pub upsets: usize,
+}
+
+/// Is called with the two teams of each decided match, in the order in
+/// which they were paired, and the winner.
+pub type MatchObserver<'a> = dyn FnMut(Team, Team, Team) + 'a;
+
+/// Counts the matches of a run of a component and the upsets among them.
+struct MatchLog<'a> {
+    seeds: HashMap<TeamIndex, usize>,
+    matches: usize,
+    upsets: usize,
+    observer: Option<&'a mut MatchObserver<'a>>,
+}
+
+impl<'a> MatchLog<'a> {
+    fn new(input: &[Team]) -> Self {
+        Self {
+            seeds: input
+                .iter()
+                .enumerate()
+                .map(|(seed, team)| (team.index, seed))
+                .collect(),
+            matches: 0,
+            upsets: 0,
+            observer: None,
+        }
+    }
+
+    fn with_observer(self, observer: Option<&'a mut MatchObserver<'a>>) -> Self {
+        Self { observer, ..self }
+    }
+
+    fn record(&mut self, [team_a, team_b]: [Team; 2], winner: &Team) {
+        let loser = if *winner == team_a { team_b } else { team_a };
+        self.matches += 1;
+        if self.seeds[&winner.index] > self.seeds[&loser.index] {
+            self.upsets += 1;
+        }
+        if let Some(observer) = self.observer.as_mut() {
+            observer(team_a, team_b, *winner);
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct Component<P> {
     pub r#type: ComponentType,
     pub teams: Vec<P>,
+    /// Seed the incoming teams by the position they reached in their previous
+    /// component instead of by the order in which they are listed, so that
+    /// for example group winners are seeded above runners-up.
+    #[serde(default)]
+    pub reseed: bool,
+    /// Win probabilities of specific pairs of teams in this component that
+    /// take precedence over the ratings and win probabilities of the
+    /// tournament.
+    #[serde(default)]
+    pub matchups: Vec<MatchupOverride>,
+    /// Once per run of this component, a shift towards the underdog is
+    /// drawn uniformly from `[-width, width]` and applied to every match
+    /// in it, like conditions that help the underdogs everywhere, so that
+    /// the upsets within the component are correlated.
+    #[serde(default)]
+    pub shared_upset_shift: f64,
+    /// Lowers the win probability in every game of this component of the
+    /// teams that took a loser placement (see [`is_loser_placement`]) on
+    /// their way to it by this amount, e.g. for the fatigue of teams that
+    /// come from a losers bracket.
+    #[serde(default)]
+    pub loser_route_penalty: f64,
 }
 
-#[derive(Deserialize, Debug, Clone, Copy)]
+/// Whether a team in this placement lost its way out of the component,
+/// such as `loser` or `round_1_loser_2`, as opposed to advancing as a
+/// winner or by its position.
+pub fn is_loser_placement(placement: &str) -> bool {
+    placement.split('_').any(|word| word == "loser")
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub enum ComponentType {
     // For convenience
     BestOf1,
@@ -23,162 +124,3056 @@ pub enum ComponentType {
     BestOf5,
     BestOf7,
     BestOfN(usize),
+    /// A best-of series in which the first team starts with `handicap` wins.
+    HandicapBestOfN {
+        num_games: usize,
+        handicap: usize,
+    },
+    /// A best-of series in which each game is drawn with probability
+    /// `draw_prob`. Drawn games are replayed and do not count.
+    ReplayDrawsBestOfN {
+        num_games: usize,
+        draw_prob: f64,
+    },
+    /// A single game that is drawn with probability `draw_prob`. A draw
+    /// is decided by a shootout that either team wins with probability
+    /// 0.5 or, without a shootout, stands and is reported as a tie.
+    SingleGame {
+        draw_prob: f64,
+        #[serde(default)]
+        shootout: bool,
+    },
+    TwoLegTie(TwoLegTie),
+    /// Passes the teams on in the order in which they come in, without
+    /// playing any matches. Useful for routing and as a test fixture.
+    Placebo,
     GroupStage(GroupStage),
+    Groups(Groups),
+    SingleElimination(SingleElimination),
+    PagePlayoff(PagePlayoff),
+    Gauntlet(Gauntlet),
+    DoubleElimination(DoubleElimination),
+    LastChanceQualifier(LastChanceQualifier),
+    RoundRobinCut(RoundRobinCut),
+    RoundRobinSingleTable(SingleTable),
+}
+
+impl fmt::Display for ComponentType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ComponentType::BestOf1 => write!(f, "BestOf1"),
+            ComponentType::BestOf3 => write!(f, "BestOf3"),
+            ComponentType::BestOf5 => write!(f, "BestOf5"),
+            ComponentType::BestOf7 => write!(f, "BestOf7"),
+            ComponentType::BestOfN(n) => write!(f, "BestOf{}", n),
+            ComponentType::HandicapBestOfN {
+                num_games,
+                handicap,
+            } => write!(f, "BestOf{}(handicap {})", num_games, handicap),
+            ComponentType::ReplayDrawsBestOfN {
+                num_games,
+                draw_prob,
+            } => write!(f, "BestOf{}(replayed draws {})", num_games, draw_prob),
+            ComponentType::SingleGame {
+                draw_prob,
+                shootout,
+            } => write!(
+                f,
+                "SingleGame(draws {}{})",
+                draw_prob,
+                if *shootout { ", shootout" } else { "" }
+            ),
+            ComponentType::Placebo => write!(f, "Placebo"),
+            ComponentType::TwoLegTie(tie) => write!(
+                f,
+                "TwoLegTie({} goals/game{})",
+                tie.goals_per_game,
+                if tie.away_goals { ", away goals" } else { "" }
+            ),
+            ComponentType::GroupStage(group) => {
+                write!(f, "GroupStage({} games/series", group.num_games_per_series)?;
+                if group.draw_prob > 0.0 {
+                    write!(f, ", draws {}", group.draw_prob)?;
+                }
+                if group.points != Points::default() {
+                    write!(f, ", points {}", group.points)?;
+                }
+                if group.keep_ties {
+                    write!(f, ", ties kept")?;
+                } else if group.tiebreak == Tiebreak::PreSeed {
+                    write!(f, ", higher seed wins ties")?;
+                } else if group.tiebreak == Tiebreak::HeadToHead {
+                    write!(f, ", ties broken head to head")?;
+                }
+                write!(f, ")")
+            }
+            ComponentType::Groups(groups) => write!(
+                f,
+                "Groups({} x {}, {:?})",
+                groups.count,
+                ComponentType::GroupStage(groups.template),
+                groups.split
+            ),
+            ComponentType::SingleElimination(bracket) => {
+                if bracket.series_by_round.is_empty() {
+                    write!(
+                        f,
+                        "SingleElimination(Bo{} series",
+                        bracket.num_games_per_series
+                    )?;
+                } else {
+                    write!(
+                        f,
+                        "SingleElimination({} by round",
+                        bracket.series_by_round.iter().join(", ")
+                    )?;
+                }
+                if bracket.reseed_each_round {
+                    write!(f, ", reseeded each round")?;
+                }
+                write!(f, ")")
+            }
+            ComponentType::PagePlayoff(playoff) => {
+                write!(f, "PagePlayoff(Bo{} series)", playoff.num_games_per_series)
+            }
+            ComponentType::Gauntlet(gauntlet) => {
+                write!(f, "Gauntlet(Bo{} series)", gauntlet.num_games_per_series)
+            }
+            ComponentType::DoubleElimination(bracket) => write!(
+                f,
+                "DoubleElimination(Bo{} series{})",
+                bracket.num_games_per_series,
+                if bracket.bracket_reset {
+                    ", bracket reset"
+                } else {
+                    ""
+                }
+            ),
+            ComponentType::LastChanceQualifier(qualifier) => write!(
+                f,
+                "LastChanceQualifier(Bo{} series, {} slots)",
+                qualifier.num_games_per_series, qualifier.slots
+            ),
+            ComponentType::RoundRobinCut(round_robin) => write!(
+                f,
+                "RoundRobinCut({} games/series, top {} to Bo{} bracket)",
+                round_robin.games, round_robin.cut, round_robin.bracket_series
+            ),
+            ComponentType::RoundRobinSingleTable(table) => {
+                write!(
+                    f,
+                    "RoundRobinSingleTable({} games/series",
+                    table.num_games_per_series
+                )?;
+                if table.draw_prob > 0.0 {
+                    write!(f, ", draws {}", table.draw_prob)?;
+                }
+                if table.points != Points::default() {
+                    write!(f, ", points {}", table.points)?;
+                }
+                if table.final_tiebreak == FinalTiebreak::Random {
+                    write!(f, ", random final tiebreak")?;
+                }
+                write!(f, ")")
+            }
+        }
+    }
+}
+
+impl<P: fmt::Display> fmt::Display for Component<P> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} [{}]", self.r#type, self.teams.iter().join(", "))
+    }
+}
+
+/// The numbers of teams a component type can take.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RequiredInputs {
+    Exactly(usize),
+    AtLeast(usize),
+    /// A power of two of at least two teams.
+    PowerOfTwo,
+}
+
+impl RequiredInputs {
+    pub fn accepts(&self, num_teams: usize) -> bool {
+        match *self {
+            RequiredInputs::Exactly(n) => num_teams == n,
+            RequiredInputs::AtLeast(n) => num_teams >= n,
+            RequiredInputs::PowerOfTwo => num_teams >= 2 && num_teams.is_power_of_two(),
+        }
+    }
+}
+
+impl fmt::Display for RequiredInputs {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RequiredInputs::Exactly(n) => write!(f, "exactly {} teams", n),
+            RequiredInputs::AtLeast(n) => write!(f, "at least {} teams", n),
+            RequiredInputs::PowerOfTwo => write!(f, "a power of two of at least 2 teams"),
+        }
+    }
+}
+
+impl ComponentType {
+    /// The numbers of teams the component can be played with.
+    pub fn required_inputs(&self) -> RequiredInputs {
+        match *self {
+            ComponentType::Placebo => RequiredInputs::AtLeast(1),
+            ComponentType::GroupStage(_)
+            | ComponentType::Gauntlet(_)
+            | ComponentType::RoundRobinCut(_)
+            | ComponentType::RoundRobinSingleTable(_) => RequiredInputs::AtLeast(2),
+            ComponentType::Groups(groups) => RequiredInputs::AtLeast(2 * groups.count),
+            ComponentType::SingleElimination(_) => RequiredInputs::AtLeast(2),
+            ComponentType::DoubleElimination(_) | ComponentType::LastChanceQualifier(_) => {
+                RequiredInputs::PowerOfTwo
+            }
+            ComponentType::PagePlayoff(_) => RequiredInputs::Exactly(4),
+            _ => RequiredInputs::Exactly(2),
+        }
+    }
+
+    /// The series played by a component consisting of a single series.
+    fn series(&self) -> Option<BestOfN> {
+        match *self {
+            ComponentType::BestOf1 => Some(BestOfN::new(1)),
+            ComponentType::BestOf3 => Some(BestOfN::new(3)),
+            ComponentType::BestOf5 => Some(BestOfN::new(5)),
+            ComponentType::BestOf7 => Some(BestOfN::new(7)),
+            ComponentType::BestOfN(n) => Some(BestOfN::new(n)),
+            ComponentType::HandicapBestOfN {
+                num_games,
+                handicap,
+            } => Some(BestOfN {
+                handicap,
+                ..BestOfN::new(num_games)
+            }),
+            _ => None,
+        }
+    }
+
+    /// The number of games and the handicap of every kind of best-of
+    /// series that the component plays.
+    pub(crate) fn best_of_n_series(&self) -> Vec<(usize, usize)> {
+        match self {
+            ComponentType::SingleElimination(bracket) if bracket.series_by_round.is_empty() => {
+                vec![(bracket.num_games_per_series, 0)]
+            }
+            ComponentType::SingleElimination(bracket) => bracket
+                .series_by_round
+                .iter()
+                .flat_map(|series| series.best_of_n_series())
+                .collect(),
+            ComponentType::PagePlayoff(playoff) => vec![(playoff.num_games_per_series, 0)],
+            ComponentType::Gauntlet(gauntlet) => vec![(gauntlet.num_games_per_series, 0)],
+            ComponentType::DoubleElimination(bracket) => vec![(bracket.num_games_per_series, 0)],
+            ComponentType::LastChanceQualifier(qualifier) => {
+                vec![(qualifier.num_games_per_series, 0)]
+            }
+            ComponentType::RoundRobinCut(round_robin) => vec![(round_robin.bracket_series, 0)],
+            _ => self
+                .series()
+                .map(|series| (series.num_games, series.handicap))
+                .into_iter()
+                .collect(),
+        }
+    }
+
+    /// Checks the parameters, returning a description of the problem if they are invalid.
+    pub fn validate(&self) -> Result<(), String> {
+        match *self {
+            ComponentType::HandicapBestOfN {
+                num_games,
+                handicap,
+            } if handicap >= num_games.div_ceil(2) => Err(format!(
+                "Handicap of {} must be less than the {} wins needed in {}",
+                handicap,
+                num_games.div_ceil(2),
+                self
+            )),
+            ComponentType::ReplayDrawsBestOfN { draw_prob, .. }
+                if !(0.0..1.0).contains(&draw_prob) =>
+            {
+                Err(format!("Draw probability must be in [0, 1) in {}", self))
+            }
+            ComponentType::SingleGame { draw_prob, .. } if !(0.0..=1.0).contains(&draw_prob) => {
+                Err(format!("Draw probability must be in [0, 1] in {}", self))
+            }
+            ComponentType::TwoLegTie(tie)
+                if tie.goals_per_game.is_nan() || tie.goals_per_game <= 0.0 =>
+            {
+                Err(format!("Goals per game must be positive in {}", self))
+            }
+            ComponentType::GroupStage(group) if group.num_games_per_series == 0 => Err(format!(
+                "A group stage needs at least one game per series in {}",
+                self
+            )),
+            ComponentType::GroupStage(group) if !(0.0..=1.0).contains(&group.draw_prob) => {
+                Err(format!("Draw probability must be in [0, 1] in {}", self))
+            }
+            ComponentType::RoundRobinSingleTable(table)
+                if !(0.0..=1.0).contains(&table.draw_prob) =>
+            {
+                Err(format!("Draw probability must be in [0, 1] in {}", self))
+            }
+            ComponentType::LastChanceQualifier(qualifier)
+                if !(1..=2).contains(&qualifier.slots) =>
+            {
+                Err(format!(
+                    "A last chance qualifier has one or two slots, got {}",
+                    self
+                ))
+            }
+            ComponentType::Groups(groups) if groups.count == 0 => {
+                Err(format!("Number of groups must be positive in {}", self))
+            }
+            ComponentType::Groups(groups) => ComponentType::GroupStage(groups.template).validate(),
+            ComponentType::SingleElimination(ref bracket)
+                if !bracket.series_by_round.is_empty() =>
+            {
+                bracket.series_by_round.iter().try_for_each(|series| {
+                    series.validate()?;
+                    match series {
+                        _ if series.required_inputs() != RequiredInputs::Exactly(2) => {
+                            Err(format!(
+                                "Bracket rounds must be played between two teams, got {}",
+                                series
+                            ))
+                        }
+                        ComponentType::SingleGame {
+                            draw_prob,
+                            shootout: false,
+                        } if *draw_prob > 0.0 => Err(format!(
+                            "Bracket rounds must always have a winner, got {}",
+                            series
+                        )),
+                        _ => Ok(()),
+                    }
+                })
+            }
+            _ => match self.num_games_per_series() {
+                Some(num_games) if num_games == 0 || num_games % 2 == 0 => Err(format!(
+                    "Series must consist of an odd number of games, got {} in {}",
+                    num_games, self
+                )),
+                _ => Ok(()),
+            },
+        }
+    }
+
+    /// The length of the series played in this component, if all of its
+    /// matches are best-of series.
+    fn num_games_per_series(&self) -> Option<usize> {
+        match *self {
+            ComponentType::ReplayDrawsBestOfN { num_games, .. } => Some(num_games),
+            ComponentType::SingleElimination(ref bracket)
+                if !bracket.series_by_round.is_empty() =>
+            {
+                None
+            }
+            ComponentType::SingleElimination(ref bracket) => Some(bracket.num_games_per_series),
+            ComponentType::PagePlayoff(playoff) => Some(playoff.num_games_per_series),
+            ComponentType::Gauntlet(gauntlet) => Some(gauntlet.num_games_per_series),
+            ComponentType::DoubleElimination(bracket) => Some(bracket.num_games_per_series),
+            ComponentType::LastChanceQualifier(qualifier) => Some(qualifier.num_games_per_series),
+            ComponentType::RoundRobinCut(round_robin) => Some(round_robin.bracket_series),
+            ComponentType::GroupStage(_)
+            | ComponentType::Groups(_)
+            | ComponentType::RoundRobinSingleTable(_) => None,
+            _ => self.series().map(|series| series.num_games),
+        }
+    }
+
+    /// The expected number of games in a component consisting of a single
+    /// series between the favorite and an underdog at the base edge.
+    pub fn expected_num_games(&self) -> Option<f64> {
+        let teams = favorite_and_underdogs(2);
+        let p = teams[0].probability_to_win_against(&teams[1]);
+        match *self {
+            ComponentType::ReplayDrawsBestOfN {
+                num_games,
+                draw_prob,
+            } => {
+                let wins_needed = num_games.div_ceil(2);
+                Some(expected_num_games(p, wins_needed, wins_needed) / (1.0 - draw_prob))
+            }
+            ComponentType::SingleGame { .. } => Some(1.0),
+            ComponentType::TwoLegTie(_) => Some(2.0),
+            _ => self.series().map(|series| {
+                let wins_needed = series.num_games.div_ceil(2);
+                expected_num_games(p, wins_needed - series.handicap, wins_needed)
+            }),
+        }
+    }
+}
+
+impl ComponentType {
+    /// Plays the matches of this component type and logs them in `log`.
+    fn play(
+        &self,
+        input: &mut [Team],
+        model: &MatchModel,
+        rng: &mut impl Rng,
+        log: &mut MatchLog,
+    ) -> Outcome {
+        let mut outcome = Outcome::default();
+        match *self {
+            ComponentType::BestOf1
+            | ComponentType::BestOf3
+            | ComponentType::BestOf5
+            | ComponentType::BestOf7
+            | ComponentType::BestOfN(_)
+            | ComponentType::HandicapBestOfN { .. } => {
+                let pairing = [input[0], input[1]];
+                self.series().unwrap().run(input, model, rng);
+                log.record(pairing, &input[0]);
+            }
+            ComponentType::ReplayDrawsBestOfN {
+                num_games,
+                draw_prob,
+            } => {
+                let pairing = [input[0], input[1]];
+                ReplayDrawsBestOfN {
+                    num_games,
+                    draw_prob,
+                }
+                .run(input, model, rng);
+                log.record(pairing, &input[0]);
+            }
+            ComponentType::SingleGame {
+                draw_prob,
+                shootout,
+            } => {
+                assert_eq!(input.len(), 2);
+                let pairing = [input[0], input[1]];
+                let p = model.match_probability(&input[0], &input[1], rng);
+                let draw = rng.gen_bool(draw_prob);
+                if draw && !shootout {
+                    // A draw that stands has neither a winner nor an upset.
+                    outcome.ties.push(0..2);
+                } else {
+                    let first_wins = if draw {
+                        rng.gen_bool(0.5)
+                    } else {
+                        model.wins_game(p, rng)
+                    };
+                    if !first_wins {
+                        input.swap(0, 1);
+                    }
+                    log.record(pairing, &input[0]);
+                }
+            }
+            ComponentType::Placebo => {}
+            ComponentType::TwoLegTie(tie) => {
+                let pairing = [input[0], input[1]];
+                tie.run(input, model, rng);
+                log.record(pairing, &input[0]);
+            }
+            ComponentType::GroupStage(group) => outcome = group.run(input, model, rng, log),
+            ComponentType::Groups(groups) => outcome = groups.run(input, model, rng, log),
+            ComponentType::SingleElimination(ref bracket) => bracket.run(input, model, rng, log),
+            ComponentType::PagePlayoff(playoff) => playoff.run(input, model, rng, log),
+            ComponentType::Gauntlet(gauntlet) => gauntlet.run(input, model, rng, log),
+            ComponentType::DoubleElimination(bracket) => bracket.run(input, model, rng, log),
+            ComponentType::LastChanceQualifier(qualifier) => qualifier.run(input, model, rng, log),
+            ComponentType::RoundRobinCut(round_robin) => {
+                outcome = round_robin.run(input, model, rng, log)
+            }
+            ComponentType::RoundRobinSingleTable(table) => table.run(input, model, rng, log),
+        }
+        outcome
+    }
 }
 
-#[derive(Deserialize, Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy)]
 struct BestOfN {
     num_games: usize,
+    /// The number of wins the first team starts with.
+    handicap: usize,
 }
 
 impl BestOfN {
-    fn run(&self, input: &mut [Team], rng: &mut ThreadRng) {
+    fn new(num_games: usize) -> Self {
+        Self {
+            num_games,
+            handicap: 0,
+        }
+    }
+
+    /// The probability that the first team wins the series if it wins
+    /// each game with probability `p`.
+    fn win_probability(&self, p: f64) -> f64 {
+        best_of_n_win_probability_with_handicap(p, self.num_games, self.handicap)
+    }
+
+    fn run(&self, input: &mut [Team], model: &MatchModel, rng: &mut impl Rng) {
+        assert_eq!(input.len(), 2);
+        let p = model.match_probability(&input[0], &input[1], rng);
+        let first_wins =
+            model.check_probability(model.series_win_probability(p, self.num_games, self.handicap));
+        // A uniform number in [0, 1) is below `first_wins` with exactly
+        // that probability, so a certain win can never be lost.
+        if rng.gen_range(0.0..1.0) >= first_wins {
+            input.swap(0, 1);
+        }
+    }
+}
+
+/// A best-of series played game by game, in which drawn games are replayed.
+struct ReplayDrawsBestOfN {
+    num_games: usize,
+    draw_prob: f64,
+}
+
+impl ReplayDrawsBestOfN {
+    fn run(&self, input: &mut [Team], model: &MatchModel, rng: &mut impl Rng) {
         assert_eq!(input.len(), 2);
-        let p = input[0].probability_to_win_against(&input[1]);
-        let total: f64 = (0..=(self.num_games - 1) / 2)
-            .map(|k| binomial_distribution(p, self.num_games, k))
-            .sum();
-        if rng.gen_range(0.0..=1.0) > total {
+        let p = model.match_probability(&input[0], &input[1], rng);
+        let wins_needed = self.num_games.div_ceil(2);
+        let (mut wins, mut losses) = (0, 0);
+        while wins < wins_needed && losses < wins_needed {
+            if rng.gen_bool(self.draw_prob) {
+                continue;
+            }
+            if model.wins_game(p, rng) {
+                wins += 1;
+            } else {
+                losses += 1;
+            }
+        }
+        if losses == wins_needed {
             input.swap(0, 1);
         }
     }
 }
 
-impl<P: std::fmt::Debug> Component<P> {
-    pub fn run(&self, input: &mut [Team], rng: &mut ThreadRng) {
+impl<P> Component<P> {
+    /// A component of the given type without matchups, reseeding, shifts or
+    /// penalties.
+    pub fn new(r#type: ComponentType, teams: Vec<P>) -> Self {
+        Self {
+            r#type,
+            teams,
+            reseed: false,
+            matchups: vec![],
+            shared_upset_shift: 0.0,
+            loser_route_penalty: 0.0,
+        }
+    }
+
+    /// The probability that a strong team wins this component against
+    /// otherwise equal opponents at the base edge. Computed exactly where a
+    /// closed form exists and estimated from `num_runs` simulations otherwise.
+    /// Returns whether the result is exact.
+    pub fn favorite_win_probability(&self, num_runs: usize, rng: &mut impl Rng) -> (f64, bool) {
+        let teams = favorite_and_underdogs(self.teams.len().max(2));
+        let p = teams[0].probability_to_win_against(&teams[1]);
         match self.r#type {
-            ComponentType::BestOf1 => BestOfN { num_games: 1 }.run(input, rng),
-            ComponentType::BestOf3 => BestOfN { num_games: 3 }.run(input, rng),
-            ComponentType::BestOf5 => BestOfN { num_games: 5 }.run(input, rng),
-            ComponentType::BestOf7 => BestOfN { num_games: 7 }.run(input, rng),
-            ComponentType::BestOfN(n) => BestOfN { num_games: n }.run(input, rng),
-            ComponentType::GroupStage(group) => group.run(input, rng),
+            ComponentType::BestOf1
+            | ComponentType::BestOf3
+            | ComponentType::BestOf5
+            | ComponentType::BestOf7
+            | ComponentType::BestOfN(_)
+            | ComponentType::HandicapBestOfN { .. } => {
+                (self.r#type.series().unwrap().win_probability(p), true)
+            }
+            // Replaying draws does not change who wins the series.
+            ComponentType::ReplayDrawsBestOfN { num_games, .. } => {
+                (best_of_n_win_probability(p, num_games), true)
+            }
+            // The favorite comes in first and stays there.
+            ComponentType::Placebo => (1.0, true),
+            // A draw goes either way with equal probability, whether it is
+            // decided by the shootout or a tie that is broken at random.
+            ComponentType::SingleGame { draw_prob, .. } => {
+                ((1.0 - draw_prob) * p + draw_prob * 0.5, true)
+            }
+            // The favorite skips the first round if there are byes.
+            ComponentType::SingleElimination(ref bracket)
+                if !bracket.series_by_round.is_empty() =>
+            {
+                bracket
+                    .series_by_round
+                    .iter()
+                    .skip(!self.teams.len().is_power_of_two() as usize)
+                    .fold((1.0, true), |(p, exact), series| {
+                        let round = Component::new(series.clone(), vec![(); 2]);
+                        let (p_round, exact_round) = round.favorite_win_probability(num_runs, rng);
+                        (p * p_round, exact && exact_round)
+                    })
+            }
+            ComponentType::SingleElimination(ref bracket) => (
+                best_of_n_win_probability(p, bracket.num_games_per_series)
+                    .powi(self.teams.len().ilog2() as i32),
+                true,
+            ),
+            ComponentType::PagePlayoff(playoff) => {
+                let q = best_of_n_win_probability(p, playoff.num_games_per_series);
+                // Either win the first qualifier and the final or lose it
+                // and win the second qualifier and the final.
+                (q * q + (1.0 - q) * q * q, true)
+            }
+            // The favorite starts as the holder and has to defend against
+            // every challenger.
+            ComponentType::Gauntlet(gauntlet) => (
+                best_of_n_win_probability(p, gauntlet.num_games_per_series)
+                    .powi(self.teams.len() as i32 - 1),
+                true,
+            ),
+            // The favorite qualifies by winning every round until only the
+            // qualifiers remain.
+            ComponentType::LastChanceQualifier(qualifier) => (
+                best_of_n_win_probability(p, qualifier.num_games_per_series)
+                    .powi(qualifier.num_rounds(self.teams.len()) as i32),
+                true,
+            ),
+            ComponentType::GroupStage(group) => {
+                match group.first_place_probabilities(&probability_matrix(&teams)) {
+                    Some(probabilities) => (probabilities[&0], true),
+                    None => (self.simulate_favorite_wins(&teams, num_runs, rng), false),
+                }
+            }
+            ComponentType::Groups(_)
+            | ComponentType::DoubleElimination(_)
+            | ComponentType::RoundRobinCut(_)
+            | ComponentType::RoundRobinSingleTable(_)
+            | ComponentType::TwoLegTie(_) => {
+                (self.simulate_favorite_wins(&teams, num_runs, rng), false)
+            }
         }
     }
 
-    pub fn get_placement_index_from_placement_name(&self, placement: &str) -> usize {
+    /// The fraction of `num_runs` runs in which the first of `teams` finishes first.
+    fn simulate_favorite_wins(&self, teams: &[Team], num_runs: usize, rng: &mut impl Rng) -> f64 {
+        let model = MatchModel::default();
+        let num_wins = (0..num_runs)
+            .filter(|_| {
+                let mut teams = teams.to_vec();
+                self.run(&mut teams, &model, rng);
+                teams[0].is_strong()
+            })
+            .count();
+        num_wins as f64 / num_runs as f64
+    }
+
+    /// Orders the input teams by their result in this component.
+    pub fn run(&self, input: &mut [Team], model: &MatchModel, rng: &mut impl Rng) -> Outcome {
+        self.run_observed(input, model, rng, None)
+    }
+
+    /// Like [`Component::run`], but calls `observer` on every decided match.
+    pub fn run_observed<'a>(
+        &self,
+        input: &mut [Team],
+        model: &MatchModel,
+        rng: &mut impl Rng,
+        observer: Option<&'a mut MatchObserver<'a>>,
+    ) -> Outcome {
+        let scoped;
+        let model = if self.matchups.is_empty() && self.shared_upset_shift == 0.0 {
+            model
+        } else {
+            let width = self.shared_upset_shift;
+            let shift = if width > 0.0 {
+                rng.gen_range(-width..=width)
+            } else {
+                0.0
+            };
+            scoped = model
+                .clone()
+                .with_overrides(self.matchups.clone())
+                .with_underdog_shift(shift);
+            &scoped
+        };
+        let mut log = MatchLog::new(input).with_observer(observer);
+        let mut outcome = self.r#type.play(input, model, rng, &mut log);
+        outcome.matches = log.matches;
+        outcome.upsets = log.upsets;
+        outcome
+    }
+
+    /// The position in the result of this component of the team in the
+    /// placement with the given name, if the component has such a placement.
+    pub fn get_placement_index_from_placement_name(&self, placement: &str) -> Option<usize> {
         use ComponentType::*;
-        match self.r#type {
-            BestOf1 | BestOf3 | BestOf5 | BestOf7 | BestOfN(_) => match placement {
-                "winner" => 0,
-                "loser" => 1,
-                _ => panic!(
-                    "Wrong placement name: {} in component: {:?}",
-                    placement, self
-                ),
+        let index = match self.r#type {
+            BestOf1
+            | BestOf3
+            | BestOf5
+            | BestOf7
+            | BestOfN(_)
+            | HandicapBestOfN { .. }
+            | ReplayDrawsBestOfN { .. }
+            | SingleGame { .. }
+            | TwoLegTie(_) => match placement {
+                "winner" => Some(0),
+                "loser" => Some(1),
+                _ => None,
             },
             GroupStage(group) => group.get_placement_index_from_placement_name(placement),
+            Groups(groups) => {
+                groups.get_placement_index_from_placement_name(self.teams.len(), placement)
+            }
+            SingleElimination(ref bracket) => {
+                bracket.get_placement_index_from_placement_name(self.teams.len(), placement)
+            }
+            PagePlayoff(_) => match placement {
+                "winner" | "1st" => Some(0),
+                "finalist" | "2nd" => Some(1),
+                "3rd" => Some(2),
+                "4th" => Some(3),
+                _ => None,
+            },
+            Gauntlet(_) => gauntlet_placement_index(self.teams.len(), placement),
+            DoubleElimination(_) => double_elimination_placement_index(self.teams.len(), placement),
+            LastChanceQualifier(qualifier) => {
+                qualifier.get_placement_index_from_placement_name(self.teams.len(), placement)
+            }
+            Placebo => match placement
+                .strip_prefix("position_")
+                .and_then(|position| position.parse::<usize>().ok())
+            {
+                Some(position) if position >= 1 => Some(position - 1),
+                _ => None,
+            },
+            RoundRobinCut(round_robin) => {
+                round_robin.get_placement_index_from_placement_name(self.teams.len(), placement)
+            }
+            RoundRobinSingleTable(_) => group_placement_index(placement),
+        };
+        index.filter(|index| *index < self.teams.len())
+    }
+}
+
+impl<P> Component<P> {
+    /// Whether the components agree in everything but their teams.
+    pub fn same_but_teams<Q>(&self, other: &Component<Q>) -> bool {
+        let Component {
+            r#type,
+            teams: _,
+            reseed,
+            matchups,
+            shared_upset_shift,
+            loser_route_penalty,
+        } = self;
+        *r#type == other.r#type
+            && *reseed == other.reseed
+            && *matchups == other.matchups
+            && *shared_upset_shift == other.shared_upset_shift
+            && *loser_route_penalty == other.loser_route_penalty
+    }
+
+    /// Checks the parameters of the type and whether they fit the number of teams.
+    pub fn validate(&self) -> Result<(), String> {
+        self.r#type.validate()?;
+        let required = self.r#type.required_inputs();
+        if !required.accepts(self.teams.len()) {
+            return Err(format!(
+                "{} requires {}, got {}",
+                self.r#type,
+                required,
+                self.teams.len()
+            ));
+        }
+        for matchup in self.matchups.iter() {
+            if matchup.team == matchup.opponent || !(0.0..=1.0).contains(&matchup.win_probability) {
+                return Err(format!(
+                    "Matchup of team {} against team {} must be between two different teams with a probability in [0, 1]",
+                    matchup.team, matchup.opponent
+                ));
+            }
+        }
+        if !(0.0..=0.5).contains(&self.shared_upset_shift) {
+            return Err(format!(
+                "Shared upset shift must be in [0, 0.5], got {}",
+                self.shared_upset_shift
+            ));
+        }
+        if !(0.0..=0.5).contains(&self.loser_route_penalty) {
+            return Err(format!(
+                "Loser route penalty must be in [0, 0.5], got {}",
+                self.loser_route_penalty
+            ));
+        }
+        match self.r#type {
+            ComponentType::Groups(groups) if !self.teams.len().is_multiple_of(groups.count) => {
+                Err(format!(
+                    "{} teams cannot be split into {} groups of equal size",
+                    self.teams.len(),
+                    groups.count
+                ))
+            }
+            ComponentType::RoundRobinCut(round_robin)
+                if round_robin.cut < 2
+                    || !round_robin.cut.is_power_of_two()
+                    || round_robin.cut > self.teams.len() =>
+            {
+                Err(format!(
+                    "The cut must be a power of two between 2 and the {} teams, got {}",
+                    self.teams.len(),
+                    round_robin.cut
+                ))
+            }
+            ComponentType::LastChanceQualifier(qualifier)
+                if self.teams.len() < 2 * qualifier.slots =>
+            {
+                Err(format!(
+                    "{} teams cannot play for {} slots",
+                    self.teams.len(),
+                    qualifier.slots
+                ))
+            }
+            ComponentType::SingleElimination(ref bracket)
+                if !bracket.series_by_round.is_empty()
+                    && bracket.series_by_round.len() != num_bracket_rounds(self.teams.len()) =>
+            {
+                Err(format!(
+                    "A bracket of {} teams has {} rounds, got series for {}",
+                    self.teams.len(),
+                    num_bracket_rounds(self.teams.len()),
+                    bracket.series_by_round.len()
+                ))
+            }
+            _ => Ok(()),
         }
     }
 }
 
-#[derive(Deserialize, Debug, Clone, Copy)]
-pub struct GroupStage {
+/// The probability of each team to win a game against each other team at
+/// the base edge.
+fn probability_matrix(teams: &[Team]) -> Vec<Vec<f64>> {
+    teams
+        .iter()
+        .map(|team| {
+            teams
+                .iter()
+                .map(|other| team.probability_to_win_against(other))
+                .collect()
+        })
+        .collect()
+}
+
+/// A strong team at index 0 followed by `num_teams - 1` regular teams.
+fn favorite_and_underdogs(num_teams: usize) -> Vec<Team> {
+    (0..num_teams)
+        .map(|index| Team::with_strength(index, index == 0))
+        .collect()
+}
+
+/// Two games between two teams, in which the first team plays the first
+/// game away and the second game at home. Each team scores a Poisson
+/// distributed number of goals per game, with means that split
+/// `goals_per_game` in proportion to the win probabilities. The team with
+/// more goals on aggregate advances. A level aggregate is decided by the
+/// goals scored away, if `away_goals` is set, and then by penalties that
+/// either team wins with probability 0.5.
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct TwoLegTie {
+    goals_per_game: f64,
+    #[serde(default)]
+    away_goals: bool,
+}
+
+impl TwoLegTie {
+    fn run(&self, input: &mut [Team], model: &MatchModel, rng: &mut impl Rng) {
+        assert_eq!(input.len(), 2);
+        let p = model.match_probability(&input[0], &input[1], rng);
+        let mut goals = |mean: f64| match Poisson::new(mean) {
+            Ok(poisson) => poisson.sample(rng) as usize,
+            // A team that cannot win does not score.
+            Err(_) => 0,
+        };
+        let first = [
+            goals(self.goals_per_game * p),
+            goals(self.goals_per_game * p),
+        ];
+        let second = [
+            goals(self.goals_per_game * (1.0 - p)),
+            goals(self.goals_per_game * (1.0 - p)),
+        ];
+        if !two_leg_winner(first, second, self.away_goals, || rng.gen_bool(0.5)) {
+            input.swap(0, 1);
+        }
+    }
+}
+
+/// Whether the first team advances, given the goals of each team in the
+/// first and second game. The first team plays the first game away.
+/// `penalties` decides whether the first team wins a shootout.
+fn two_leg_winner(
+    first: [usize; 2],
+    second: [usize; 2],
+    away_goals: bool,
+    penalties: impl FnOnce() -> bool,
+) -> bool {
+    let aggregate = (first[0] + first[1]).cmp(&(second[0] + second[1]));
+    let away = if away_goals {
+        first[0].cmp(&second[1])
+    } else {
+        Ordering::Equal
+    };
+    match aggregate.then(away) {
+        Ordering::Greater => true,
+        Ordering::Less => false,
+        Ordering::Equal => penalties(),
+    }
+}
+
+/// A knockout bracket over seeded teams, where seed 1 plays seed n, seed 2
+/// plays seed n-1 and so on, such that the top two seeds can only meet in the
+/// final. If the number of teams is not a power of two, the top seeds get a
+/// bye into the second round.
+///
+/// The resulting order is the winner, the finalist and then the losers of each
+/// round from the last round back to the first one, each in bracket order.
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct SingleElimination {
+    #[serde(default)]
     num_games_per_series: usize,
+    /// The two-team component type that decides the matches of each round,
+    /// starting from the first round. Takes precedence over
+    /// `num_games_per_series` if not empty.
+    #[serde(default)]
+    series_by_round: Vec<ComponentType>,
+    /// Instead of following a fixed bracket, pair the best remaining seed
+    /// with the worst remaining seed, the second best with the second worst
+    /// and so on before every round. The losers of each round are then in
+    /// the order of the better seed of their match.
+    #[serde(default)]
+    reseed_each_round: bool,
 }
 
-impl GroupStage {
-    fn run(&self, input: &mut [Team], rng: &mut ThreadRng) {
-        let mut num_games_won: HashMap<TeamIndex, i32> =
-            input.iter().map(|team| (team.index, 0)).collect();
-        for (i, team1) in input.iter().enumerate() {
-            for team2 in input[i + 1..].iter() {
-                for _ in 0..self.num_games_per_series {
-                    if team1.wins_against(&team2, rng) {
-                        *num_games_won.get_mut(&team1.index).unwrap() += 1;
-                    } else {
-                        *num_games_won.get_mut(&team2.index).unwrap() += 1;
-                    }
-                }
-            }
+impl SingleElimination {
+    fn new(num_games_per_series: usize) -> Self {
+        Self {
+            num_games_per_series,
+            series_by_round: vec![],
+            reseed_each_round: false,
         }
-        input.sort_by_key(|team| -num_games_won[&team.index]);
-        self.tiebreak(&num_games_won, input, rng);
     }
 
-    fn tiebreak(
+    fn run(&self, input: &mut [Team], model: &MatchModel, rng: &mut impl Rng, log: &mut MatchLog) {
+        self.run_until(input, 1, model, rng, log)
+    }
+
+    /// Plays rounds until `survivors` teams remain, who come first in
+    /// bracket order, followed by the losers of each round as in a full
+    /// bracket.
+    fn run_until(
         &self,
-        num_games_won: &HashMap<TeamIndex, i32>,
         input: &mut [Team],
-        rng: &mut ThreadRng,
+        survivors: usize,
+        model: &MatchModel,
+        rng: &mut impl Rng,
+        log: &mut MatchLog,
     ) {
-        let ties = identify_tied_teams(input, num_games_won);
-        if ties.len() > 0 {
-            for tie in ties {
-                GroupStage {
-                    num_games_per_series: self.num_games_per_series,
+        assert!(
+            input.len() >= 2,
+            "Single elimination bracket requires at least 2 teams, got {}",
+            input.len()
+        );
+        // Seeds beyond the number of teams are byes.
+        let mut remaining: Vec<Option<Team>> = bracket_order(input.len().next_power_of_two())
+            .into_iter()
+            .map(|seed| input.get(seed).copied())
+            .collect();
+        let best_of_n = BestOfN::new(self.num_games_per_series);
+        let mut losers_by_round = vec![];
+        while remaining.len() > survivors {
+            if self.reseed_each_round {
+                remaining = reseeded_pairs(input, &remaining);
+            }
+            let series = self.series_by_round.get(losers_by_round.len());
+            let mut winners = vec![];
+            let mut losers = vec![];
+            for pair in remaining.chunks(2) {
+                let mut pair: Vec<Team> = pair.iter().flatten().copied().collect();
+                if pair.len() == 2 {
+                    match series {
+                        Some(series) => {
+                            series.play(&mut pair, model, rng, log);
+                        }
+                        None => {
+                            let pairing = [pair[0], pair[1]];
+                            best_of_n.run(&mut pair, model, rng);
+                            log.record(pairing, &pair[0]);
+                        }
+                    }
+                    losers.push(pair[1]);
                 }
-                .run(&mut input[tie.start_index..=tie.end_index], rng);
+                winners.push(Some(pair[0]));
             }
+            remaining = winners;
+            losers_by_round.push(losers);
+        }
+        let order = remaining
+            .into_iter()
+            .flatten()
+            .chain(losers_by_round.into_iter().rev().flatten());
+        for (slot, team) in input.iter_mut().zip(order) {
+            *slot = team;
         }
     }
 
-    fn get_placement_index_from_placement_name(&self, placement: &str) -> usize {
-        match placement {
-            "1st" => 0,
-            "2nd" => 1,
-            "3rd" => 2,
-            "4th" => 3,
-            "5th" => 4,
-            "6th" => 5,
-            "7th" => 6,
-            "8th" => 7,
-            _ => panic!("Unknown group placement: {}", placement),
+    /// Accepts `winner`, `finalist`, `semifinal_loser_<i>` or
+    /// `semifinalist_<i>`, `quarterfinal_loser_<i>` and
+    /// `round_<r>_loser_<i>`, where rounds are counted from 1 for the first
+    /// round and `i` counts the matches of a round that were played in
+    /// bracket order, starting from 1.
+    fn get_placement_index_from_placement_name(
+        &self,
+        num_teams: usize,
+        placement: &str,
+    ) -> Option<usize> {
+        let num_rounds = num_bracket_rounds(num_teams);
+        let loser = |round, match_| bracket_loser_index(num_teams, round, match_);
+        if placement == "winner" {
+            Some(0)
+        } else if placement == "finalist" {
+            Some(1)
+        } else if let Some(match_) = placement
+            .strip_prefix("semifinal_loser_")
+            .or_else(|| placement.strip_prefix("semifinalist_"))
+        {
+            loser(num_rounds.checked_sub(1)?, match_)
+        } else if let Some(match_) = placement.strip_prefix("quarterfinal_loser_") {
+            loser(num_rounds.saturating_sub(2), match_)
+        } else {
+            round_loser_index(num_teams, num_rounds, placement)
         }
     }
 }
 
-#[derive(Debug, PartialEq, Eq)]
-struct TiedTeams {
-    teams: Vec<TeamIndex>,
-    start_index: usize,
-    end_index: usize,
+/// The number of rounds of a bracket of `num_teams` teams.
+fn num_bracket_rounds(num_teams: usize) -> usize {
+    num_teams.next_power_of_two().trailing_zeros() as usize
 }
 
-fn identify_tied_teams<'a>(
-    teams: &'a [Team],
-    num_games_won: &'a HashMap<TeamIndex, i32>,
-) -> Vec<TiedTeams> {
-    debug_assert!(sorted(teams, num_games_won));
-    teams
+/// The position of the loser of the `match_`-th match, counting from 1, in
+/// round `round` of a bracket of `num_teams` teams. Only the first round
+/// has fewer matches than in a full bracket, since the byes go to the top
+/// seeds.
+fn bracket_loser_index(num_teams: usize, round: usize, match_: &str) -> Option<usize> {
+    if round == 0 {
+        return None;
+    }
+    let first = num_teams.next_power_of_two() >> round;
+    let num_losers = if round == 1 { num_teams - first } else { first };
+    match match_.parse::<usize>() {
+        Ok(i) if (1..=num_losers).contains(&i) => Some(first + i - 1),
+        _ => None,
+    }
+}
+
+/// Accepts `round_<r>_loser_<i>` for the first `num_rounds` rounds of a
+/// bracket of `num_teams` teams.
+fn round_loser_index(num_teams: usize, num_rounds: usize, placement: &str) -> Option<usize> {
+    let (round, match_) = placement
+        .strip_prefix("round_")
+        .and_then(|rest| rest.split_once("_loser_"))?;
+    match round.parse::<usize>() {
+        Ok(round) if round <= num_rounds => bracket_loser_index(num_teams, round, match_),
+        _ => None,
+    }
+}
+
+/// The `remaining` teams ordered into pairs of the best and the worst seed,
+/// the second best and the second worst and so on, where the seeds are the
+/// positions in `input` and byes come last.
+fn reseeded_pairs(input: &[Team], remaining: &[Option<Team>]) -> Vec<Option<Team>> {
+    let seed = |team: &Option<Team>| match team {
+        Some(team) => input.iter().position(|other| other == team).unwrap(),
+        None => usize::MAX,
+    };
+    let by_seed: Vec<_> = remaining.iter().copied().sorted_by_key(seed).collect();
+    let half = by_seed.len() / 2;
+    by_seed[..half]
         .iter()
-        .enumerate()
-        .group_by(|(_, team)| num_games_won[&team.index])
-        .into_iter()
-        .map(|(_, group)| group.collect::<Vec<_>>())
-        .filter(|group| group.len() > 1)
-        .map(|group| TiedTeams {
-            start_index: *group.iter().map(|(index, _)| index).min().unwrap(),
-            end_index: *group.iter().map(|(index, _)| index).max().unwrap(),
-            teams: group.iter().map(|(_, team)| team.index).collect(),
-        })
+        .zip(by_seed[half..].iter().rev())
+        .flat_map(|(better, worse)| [*better, *worse])
         .collect()
 }
 
-fn sorted(teams: &[Team], num_games_won: &HashMap<TeamIndex, i32>) -> bool {
-    teams
-        .windows(2)
-        .all(|ts| num_games_won[&ts[0].index] >= num_games_won[&ts[1].index])
+/// A Page playoff between four seeded teams: seeds 1 and 2 play the first
+/// qualifier, whose winner goes to the final. Seeds 3 and 4 play the
+/// eliminator, whose loser finishes 4th. The loser of the first qualifier
+/// and the winner of the eliminator play the second qualifier for the
+/// remaining spot in the final.
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct PagePlayoff {
+    num_games_per_series: usize,
 }
 
-#[cfg(test)]
-mod tests {
-    use super::TiedTeams;
-    use crate::Team;
+impl PagePlayoff {
+    fn run(&self, input: &mut [Team], model: &MatchModel, rng: &mut impl Rng, log: &mut MatchLog) {
+        let series = BestOfN::new(self.num_games_per_series);
+        page_playoff(input, |pair| {
+            let pairing = [pair[0], pair[1]];
+            series.run(pair, model, rng);
+            log.record(pairing, &pair[0]);
+        });
+    }
+}
 
-    #[test]
-    fn identify_tied_teams() {
-        let make_teams = |indices: &[usize]| {
-            indices
-                .into_iter()
-                .map(|index| Team {
-                    index: *index,
-                    strong: false,
-                })
+/// `play` orders a pair of teams into winner and loser.
+fn page_playoff(input: &mut [Team], mut play: impl FnMut(&mut [Team])) {
+    assert_eq!(input.len(), 4, "Page playoff requires four teams");
+    let mut qualifier1 = [input[0], input[1]];
+    play(&mut qualifier1);
+    let mut eliminator = [input[2], input[3]];
+    play(&mut eliminator);
+    let mut qualifier2 = [qualifier1[1], eliminator[0]];
+    play(&mut qualifier2);
+    let mut final_ = [qualifier1[0], qualifier2[0]];
+    play(&mut final_);
+    input.copy_from_slice(&[final_[0], final_[1], qualifier2[1], eliminator[1]]);
+}
+
+/// The first team starts as the holder and defends against every other team
+/// in the order in which they are listed. The winner of each series becomes
+/// the holder, and the final holder wins. The remaining teams are ordered by
+/// reverse elimination order, so the last team to be eliminated is 2nd.
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct Gauntlet {
+    num_games_per_series: usize,
+}
+
+impl Gauntlet {
+    fn run(&self, input: &mut [Team], model: &MatchModel, rng: &mut impl Rng, log: &mut MatchLog) {
+        let series = BestOfN::new(self.num_games_per_series);
+        gauntlet(input, |pair| {
+            let pairing = [pair[0], pair[1]];
+            series.run(pair, model, rng);
+            log.record(pairing, &pair[0]);
+        });
+    }
+}
+
+/// `play` orders a pair of holder and challenger into winner and loser.
+fn gauntlet(input: &mut [Team], mut play: impl FnMut(&mut [Team])) {
+    assert!(!input.is_empty(), "Gauntlet requires at least one team");
+    let mut holder = input[0];
+    let mut eliminated = vec![];
+    for challenger in input[1..].iter() {
+        let mut pair = [holder, *challenger];
+        play(&mut pair);
+        holder = pair[0];
+        eliminated.push(pair[1]);
+    }
+    input[0] = holder;
+    for (slot, team) in input[1..].iter_mut().zip(eliminated.into_iter().rev()) {
+        *slot = team;
+    }
+}
+
+/// Accepts `winner` and `eliminated_<k>` for the team that was eliminated
+/// `k`-th, counting from 1.
+fn gauntlet_placement_index(num_teams: usize, placement: &str) -> Option<usize> {
+    if placement == "winner" {
+        return Some(0);
+    }
+    match placement
+        .strip_prefix("eliminated_")
+        .and_then(|k| k.parse::<usize>().ok())
+    {
+        Some(k) if (1..num_teams).contains(&k) => Some(num_teams - k),
+        _ => None,
+    }
+}
+
+/// A bracket over a power-of-two number of seeded teams, in which a team is
+/// only eliminated after its second loss. The upper bracket is seeded as in
+/// [`SingleElimination`], and the losers of each of its rounds drop into the
+/// lower bracket. The champions of both brackets meet in the grand final.
+///
+/// The resulting order is the winner, the grand finalist and then the losers
+/// of each lower bracket round from the last round back to the first one.
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct DoubleElimination {
+    num_games_per_series: usize,
+    /// Play a second grand final if the champion of the lower bracket wins
+    /// the first one, so that the champion of the upper bracket also needs
+    /// to lose twice.
+    #[serde(default)]
+    bracket_reset: bool,
+}
+
+impl DoubleElimination {
+    fn run(&self, input: &mut [Team], model: &MatchModel, rng: &mut impl Rng, log: &mut MatchLog) {
+        let series = BestOfN::new(self.num_games_per_series);
+        double_elimination(input, self.bracket_reset, |pair| {
+            let pairing = [pair[0], pair[1]];
+            series.run(pair, model, rng);
+            log.record(pairing, &pair[0]);
+        });
+    }
+}
+
+/// `play` orders a pair of teams into winner and loser.
+fn double_elimination(input: &mut [Team], bracket_reset: bool, mut play: impl FnMut(&mut [Team])) {
+    assert!(
+        input.len() >= 2 && input.len().is_power_of_two(),
+        "Double elimination bracket requires a power of two of teams, got {}",
+        input.len()
+    );
+    let mut upper: Vec<Team> = bracket_order(input.len())
+        .into_iter()
+        .map(|seed| input[seed])
+        .collect();
+    let mut lower: Vec<Team> = vec![];
+    let mut eliminated_by_round = vec![];
+    while upper.len() > 1 {
+        let (winners, dropped) = play_pairs(upper.chunks(2), &mut play);
+        upper = winners;
+        if lower.is_empty() {
+            lower = dropped;
+        } else {
+            // The dropped teams meet the lower bracket in reverse order,
+            // which avoids immediate rematches.
+            let pairs: Vec<_> = dropped.into_iter().rev().zip(lower).collect();
+            let (survivors, eliminated) =
+                play_pairs(pairs.iter().map(|(a, b)| [*a, *b]), &mut play);
+            lower = survivors;
+            eliminated_by_round.push(eliminated);
+        }
+        if lower.len() > 1 {
+            let (survivors, eliminated) = play_pairs(lower.chunks(2), &mut play);
+            lower = survivors;
+            eliminated_by_round.push(eliminated);
+        }
+    }
+    let mut grand_final = [upper[0], lower[0]];
+    play(&mut grand_final);
+    if bracket_reset && grand_final[0] == lower[0] {
+        play(&mut grand_final);
+    }
+    let order = grand_final
+        .into_iter()
+        .chain(eliminated_by_round.into_iter().rev().flatten());
+    for (slot, team) in input.iter_mut().zip(order) {
+        *slot = team;
+    }
+}
+
+/// Plays each pair and returns the winners and the losers, each in the order
+/// of the pairs.
+fn play_pairs<P: AsRef<[Team]>>(
+    pairs: impl IntoIterator<Item = P>,
+    play: &mut impl FnMut(&mut [Team]),
+) -> (Vec<Team>, Vec<Team>) {
+    pairs
+        .into_iter()
+        .map(|pair| {
+            let mut pair = [pair.as_ref()[0], pair.as_ref()[1]];
+            play(&mut pair);
+            (pair[0], pair[1])
+        })
+        .unzip()
+}
+
+/// Accepts `winner` or `1st`, `grand_finalist` or `2nd`, `3rd`, `4th` and
+/// `lower_round_<r>_loser_<i>`, where the rounds of the lower bracket are
+/// counted from 1 and `i` counts the matches of a round from 1.
+fn double_elimination_placement_index(num_teams: usize, placement: &str) -> Option<usize> {
+    match placement {
+        "winner" | "1st" => return Some(0),
+        "grand_finalist" | "2nd" => return Some(1),
+        "3rd" => return Some(2),
+        "4th" => return Some(3),
+        _ => {}
+    }
+    let (round, match_) = placement
+        .strip_prefix("lower_round_")
+        .and_then(|rest| rest.split_once("_loser_"))?;
+    let (round, match_) = (round.parse::<usize>().ok()?, match_.parse::<usize>().ok()?);
+    // The losers of the first upper round play each other. The losers of
+    // every later upper round drop in against the lower bracket, followed
+    // by a round within the lower bracket except after the upper final.
+    let num_rounds = 2 * (num_teams.trailing_zeros() as usize).saturating_sub(1);
+    let num_losers = |round: usize| num_teams >> (2 + (round - 1) / 2);
+    if !(1..=num_rounds).contains(&round) || !(1..=num_losers(round)).contains(&match_) {
+        return None;
+    }
+    let first = 2 + (round + 1..=num_rounds).map(num_losers).sum::<usize>();
+    Some(first + match_ - 1)
+}
+
+/// A qualifier for the last `slots` places of a later event, between the
+/// teams that did not qualify directly: a single elimination bracket that
+/// stops once `slots` teams remain, such that for two slots the top two
+/// seeds cannot meet. The qualifiers come first in bracket order, followed
+/// by the losers of each round as in [`SingleElimination`].
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct LastChanceQualifier {
+    num_games_per_series: usize,
+    #[serde(default = "default_slots")]
+    slots: usize,
+}
+
+fn default_slots() -> usize {
+    1
+}
+
+impl LastChanceQualifier {
+    fn run(&self, input: &mut [Team], model: &MatchModel, rng: &mut impl Rng, log: &mut MatchLog) {
+        SingleElimination::new(self.num_games_per_series)
+            .run_until(input, self.slots, model, rng, log)
+    }
+
+    /// The number of rounds a qualifier needs to win.
+    fn num_rounds(&self, num_teams: usize) -> usize {
+        (num_teams / self.slots).trailing_zeros() as usize
+    }
+
+    /// Accepts `qualifier_<i>` for the `i`-th slot, counting from 1, and
+    /// `round_<r>_loser_<i>` as in [`SingleElimination`].
+    fn get_placement_index_from_placement_name(
+        &self,
+        num_teams: usize,
+        placement: &str,
+    ) -> Option<usize> {
+        match placement
+            .strip_prefix("qualifier_")
+            .map(|slot| slot.parse::<usize>())
+        {
+            Some(Ok(slot)) if (1..=self.slots).contains(&slot) => Some(slot - 1),
+            Some(_) => None,
+            None => round_loser_index(num_teams, self.num_rounds(num_teams), placement),
+        }
+    }
+}
+
+/// The seeds in the order in which they are placed into the bracket, such that
+/// neighbouring pairs play each other in the first round.
+fn bracket_order(num_teams: usize) -> Vec<usize> {
+    let mut order = vec![0];
+    while order.len() < num_teams {
+        let size = order.len() * 2;
+        order = order
+            .into_iter()
+            .flat_map(|seed| [seed, size - 1 - seed])
+            .collect();
+    }
+    order
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct GroupStage {
+    num_games_per_series: usize,
+    /// Ties that remain after this many nested replays are broken at random.
+    #[serde(default = "default_max_tiebreak_depth")]
+    max_tiebreak_depth: usize,
+    /// Report teams with the same number of games won as tied instead of
+    /// breaking the tie.
+    #[serde(default)]
+    keep_ties: bool,
+    #[serde(default)]
+    tiebreak: Tiebreak,
+    /// The probability that a single game ends in a draw.
+    #[serde(default)]
+    draw_prob: f64,
+    /// The points awarded per game, by which the teams are ranked.
+    #[serde(default)]
+    points: Points,
+}
+
+/// Points for the result of a single game in a group stage.
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(default)]
+pub struct Points {
+    pub win: i32,
+    pub draw: i32,
+    pub loss: i32,
+}
+
+impl Default for Points {
+    /// One point per game won, i.e. teams are ranked by the number of games won.
+    fn default() -> Self {
+        Self {
+            win: 1,
+            draw: 0,
+            loss: 0,
+        }
+    }
+}
+
+impl fmt::Display for Points {
+    /// The points for a win, a draw and a loss, e.g. `3-1-0`.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}-{}-{}", self.win, self.draw, self.loss)
+    }
+}
+
+/// How a group stage breaks ties between teams with the same number of games won.
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, Default, PartialEq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub enum Tiebreak {
+    /// The tied teams play another round robin among themselves.
+    #[default]
+    Replay,
+    /// The higher seed, i.e. the team that comes first in the input, advances.
+    PreSeed,
+    /// The tied teams are ranked by the points they got against each other
+    /// in the group. Teams that are still tied play another round robin.
+    HeadToHead,
+}
+
+/// The points that each team of a group got against each other team.
+struct HeadToHeadTable {
+    /// The teams in the order in which they played the group.
+    teams: Vec<TeamIndex>,
+    /// The points of `teams[i]` against `teams[j]` at `i * teams.len() + j`.
+    points: Vec<i64>,
+}
+
+impl HeadToHeadTable {
+    /// Sorts each of `ties` by the points of the tied teams against each
+    /// other and returns the teams that are still tied.
+    fn break_ties(&self, input: &mut [Team], ties: Vec<TiedTeams>) -> Vec<TiedTeams> {
+        let mut remaining = vec![];
+        for tie in ties {
+            let tied = &mut input[tie.start_index..=tie.end_index];
+            let mut tied_points = self.points_among(tied);
+            sort_by_points(tied, &mut tied_points);
+            remaining.extend(identify_tied_teams(tied, &tied_points).into_iter().map(
+                |still_tied| TiedTeams {
+                    teams: still_tied.teams,
+                    start_index: tie.start_index + still_tied.start_index,
+                    end_index: tie.start_index + still_tied.end_index,
+                },
+            ));
+        }
+        remaining
+    }
+
+    /// The points of each of `tied` in the games among them.
+    fn points_among(&self, tied: &[Team]) -> Vec<i64> {
+        let num_teams = self.teams.len();
+        let positions: Vec<_> = tied
+            .iter()
+            .map(|team| {
+                self.teams
+                    .iter()
+                    .position(|index| *index == team.index)
+                    .unwrap()
+            })
+            .collect();
+        positions
+            .iter()
+            .map(|i| {
+                positions
+                    .iter()
+                    .map(|j| self.points[i * num_teams + j])
+                    .sum()
+            })
+            .collect()
+    }
+}
+
+fn default_max_tiebreak_depth() -> usize {
+    100
+}
+
+impl GroupStage {
+    fn run(
+        &self,
+        input: &mut [Team],
+        model: &MatchModel,
+        rng: &mut impl Rng,
+        log: &mut MatchLog,
+    ) -> Outcome {
+        let mut points = Vec::with_capacity(input.len());
+        if self.keep_ties {
+            self.play(input, model, rng, log, &mut points);
+            return Outcome {
+                tiebreak_depth: 0,
+                ties: identify_tied_teams(input, &points)
+                    .into_iter()
+                    .map(|tie| tie.start_index..tie.end_index + 1)
+                    .collect(),
+                ..Default::default()
+            };
+        }
+        Outcome {
+            tiebreak_depth: self.run_at_depth(input, model, rng, log, &mut points, 0),
+            ..Default::default()
+        }
+    }
+
+    /// Returns the depth of the deepest tiebreak that was needed. `points`
+    /// is scratch space shared by all tiebreaks of the group.
+    fn run_at_depth(
+        &self,
+        input: &mut [Team],
+        model: &MatchModel,
+        rng: &mut impl Rng,
+        log: &mut MatchLog,
+        points: &mut Vec<i64>,
+        depth: usize,
+    ) -> usize {
+        let head_to_head = self.play(input, model, rng, log, points);
+        if self.tiebreak == Tiebreak::PreSeed {
+            // Sorting by games won is stable, so ties are already in seeding order.
+            return depth;
+        }
+        let mut ties = identify_tied_teams(input, points);
+        if let Some(table) = head_to_head {
+            ties = table.break_ties(input, ties);
+        }
+        let mut deepest = depth;
+        for tie in ties {
+            let tied = &mut input[tie.start_index..=tie.end_index];
+            if depth >= self.max_tiebreak_depth {
+                tied.shuffle(rng);
+            } else {
+                deepest = deepest.max(self.run_at_depth(tied, model, rng, log, points, depth + 1));
+            }
+        }
+        deepest
+    }
+
+    /// Plays the round robin and sorts the teams by their points. Afterwards,
+    /// `points` holds the points of the team at each position of `input`.
+    /// The results of the games are only kept for a head to head tiebreak.
+    fn play(
+        &self,
+        input: &mut [Team],
+        model: &MatchModel,
+        rng: &mut impl Rng,
+        log: &mut MatchLog,
+        points: &mut Vec<i64>,
+    ) -> Option<HeadToHeadTable> {
+        let num_teams = input.len();
+        points.clear();
+        points.resize(num_teams, 0);
+        let mut head_to_head = (self.tiebreak == Tiebreak::HeadToHead).then(|| HeadToHeadTable {
+            teams: input.iter().map(|team| team.index).collect(),
+            points: vec![0; num_teams * num_teams],
+        });
+        let rules = RoundRobinRules {
+            num_games_per_series: self.num_games_per_series,
+            draw_prob: self.draw_prob,
+            points: self.points,
+        };
+        play_round_robin(input, rules, model, rng, log, |i, j, points_i, points_j| {
+            points[i] += points_i;
+            points[j] += points_j;
+            if let Some(table) = head_to_head.as_mut() {
+                table.points[i * num_teams + j] += points_i;
+                table.points[j * num_teams + i] += points_j;
+            }
+        });
+        sort_by_points(input, points);
+        head_to_head
+    }
+
+    fn get_placement_index_from_placement_name(&self, placement: &str) -> Option<usize> {
+        group_placement_index(placement)
+    }
+}
+
+fn group_placement_index(placement: &str) -> Option<usize> {
+    match placement {
+        "1st" => Some(0),
+        "2nd" => Some(1),
+        "3rd" => Some(2),
+        "4th" => Some(3),
+        "5th" => Some(4),
+        "6th" => Some(5),
+        "7th" => Some(6),
+        "8th" => Some(7),
+        _ => None,
+    }
+}
+
+/// How the series of a round robin are played and scored.
+#[derive(Clone, Copy)]
+struct RoundRobinRules {
+    num_games_per_series: usize,
+    draw_prob: f64,
+    points: Points,
+}
+
+/// Plays a series between every pair of teams and calls `award` with the
+/// positions of both teams in `input` and the points each of them got.
+fn play_round_robin(
+    input: &[Team],
+    rules: RoundRobinRules,
+    model: &MatchModel,
+    rng: &mut impl Rng,
+    log: &mut MatchLog,
+    mut award: impl FnMut(usize, usize, i64, i64),
+) {
+    for i in 0..input.len() {
+        for j in i + 1..input.len() {
+            let (team1, team2) = (&input[i], &input[j]);
+            let p = model.match_probability(team1, team2, rng);
+            let mut balance = 0;
+            for _ in 0..rules.num_games_per_series {
+                // Only draw if needed, so that groups without draws use
+                // the same random numbers as before draws existed.
+                let (points1, points2) = if rules.draw_prob > 0.0 && rng.gen_bool(rules.draw_prob) {
+                    (rules.points.draw, rules.points.draw)
+                } else if model.wins_game(p, rng) {
+                    balance += 1;
+                    (rules.points.win, rules.points.loss)
+                } else {
+                    balance -= 1;
+                    (rules.points.loss, rules.points.win)
+                };
+                award(i, j, i64::from(points1), i64::from(points2));
+            }
+            match balance.cmp(&0) {
+                Ordering::Greater => log.record([*team1, *team2], team1),
+                Ordering::Less => log.record([*team1, *team2], team2),
+                Ordering::Equal => {}
+            }
+        }
+    }
+}
+
+/// A single round robin league table. Unlike a [`GroupStage`], ties are
+/// never replayed: teams are ranked by points, then by the points they
+/// got in the games among the teams with the same points, then by the
+/// final tiebreak.
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct SingleTable {
+    num_games_per_series: usize,
+    /// The probability that a single game ends in a draw.
+    #[serde(default)]
+    draw_prob: f64,
+    #[serde(default)]
+    points: Points,
+    #[serde(default)]
+    final_tiebreak: FinalTiebreak,
+}
+
+/// How a league table orders teams that are tied on points and head to head.
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, Default, PartialEq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub enum FinalTiebreak {
+    /// The higher seed, i.e. the team that comes first in the input.
+    #[default]
+    PreSeed,
+    /// A random order, like drawing lots.
+    Random,
+}
+
+impl SingleTable {
+    fn run(&self, input: &mut [Team], model: &MatchModel, rng: &mut impl Rng, log: &mut MatchLog) {
+        let num_teams = input.len();
+        let mut points = vec![0; num_teams];
+        // The points of the team at position `i` against the team at `j` at `i * num_teams + j`.
+        let mut head_to_head = vec![0; num_teams * num_teams];
+        let rules = RoundRobinRules {
+            num_games_per_series: self.num_games_per_series,
+            draw_prob: self.draw_prob,
+            points: self.points,
+        };
+        play_round_robin(input, rules, model, rng, log, |i, j, points_i, points_j| {
+            points[i] += points_i;
+            points[j] += points_j;
+            head_to_head[i * num_teams + j] += points_i;
+            head_to_head[j * num_teams + i] += points_j;
+        });
+        let head_to_head_points: Vec<i64> = (0..num_teams)
+            .map(|i| {
+                (0..num_teams)
+                    .filter(|j| points[*j] == points[i])
+                    .map(|j| head_to_head[i * num_teams + j])
+                    .sum()
+            })
+            .collect();
+        let mut order: Vec<_> = (0..num_teams).collect();
+        if self.final_tiebreak == FinalTiebreak::Random {
+            order.shuffle(rng);
+        }
+        // Stable, so that the final tiebreak decides the remaining ties.
+        order.sort_by_key(|i| Reverse((points[*i], head_to_head_points[*i])));
+        let table: Vec<_> = order.iter().map(|i| input[*i]).collect();
+        input.copy_from_slice(&table);
+    }
+}
+
+/// Group stages with more possible results of all of their series are not
+/// enumerated by [`GroupStage::first_place_probabilities`].
+const MAX_EXACT_GROUP_OUTCOMES: f64 = (1 << 20) as f64;
+
+impl GroupStage {
+    /// The exact probability of each team, by its position in the group,
+    /// to finish first if team `i` wins a game against team `j` with
+    /// probability `p[i][j]`. The results of all series are enumerated,
+    /// so this is `None` if there are more than [`MAX_EXACT_GROUP_OUTCOMES`]
+    /// of them or more teams than fit into the bit masks of their sets. It
+    /// is also `None` for head to head tiebreaks, which depend on more than
+    /// the points of each team.
+    pub fn first_place_probabilities(&self, p: &[Vec<f64>]) -> Option<HashMap<TeamIndex, f64>> {
+        if self.tiebreak == Tiebreak::HeadToHead && !self.keep_ties {
+            return None;
+        }
+        let num_teams = p.len();
+        let num_pairs = num_teams * num_teams.saturating_sub(1) / 2;
+        let num_series_results = self.series_results(0.5).len() as f64;
+        if num_teams == 0
+            || num_teams >= u32::BITS as usize
+            || num_series_results.powi(num_pairs as i32) > MAX_EXACT_GROUP_OUTCOMES
+        {
+            return None;
+        }
+        let mut exact = ExactGroupStage {
+            group: self,
+            p,
+            leaders: HashMap::new(),
+            first_place: HashMap::new(),
+        };
+        let everyone = (1u32 << num_teams) - 1;
+        Some(
+            exact
+                .first_place(everyone, 0)
+                .into_iter()
+                .enumerate()
+                .collect(),
+        )
+    }
+
+    /// The possible results of a series in which the first team wins a
+    /// game with probability `p`.
+    fn series_results(&self, p: f64) -> Vec<SeriesResult> {
+        let num_games = self.num_games_per_series;
+        let max_draws = if self.draw_prob > 0.0 { num_games } else { 0 };
+        let points = |result: i32, num_games: usize| i64::from(result) * num_games as i64;
+        (0..=max_draws)
+            .flat_map(|draws| {
+                (0..=num_games - draws).map(move |wins| {
+                    let losses = num_games - draws - wins;
+                    // The distribution counts the games that do not end in a draw and
+                    // the games that are not won, respectively.
+                    let probability =
+                        binomial_distribution(self.draw_prob, num_games, num_games - draws)
+                            * binomial_distribution(p, num_games - draws, losses);
+                    let draw_points = points(self.points.draw, draws);
+                    SeriesResult {
+                        probability,
+                        points: [
+                            points(self.points.win, wins)
+                                + draw_points
+                                + points(self.points.loss, losses),
+                            points(self.points.win, losses)
+                                + draw_points
+                                + points(self.points.loss, wins),
+                        ],
+                    }
+                })
+            })
+            .collect()
+    }
+}
+
+/// A possible result of a series in a group stage.
+struct SeriesResult {
+    probability: f64,
+    /// The points of the first and the second team.
+    points: [i64; 2],
+}
+
+/// The series of a round robin: the positions of both teams in the group
+/// and the possible results.
+type RoundRobinSeries = (usize, usize, Vec<SeriesResult>);
+
+/// The state of [`GroupStage::first_place_probabilities`]. Sets of teams
+/// are bit masks of their positions in the group.
+struct ExactGroupStage<'a> {
+    group: &'a GroupStage,
+    p: &'a [Vec<f64>],
+    /// For each set of teams that plays a round robin, the probability of
+    /// each set of teams to share the most points.
+    leaders: HashMap<u32, Vec<(u32, f64)>>,
+    /// The first place probabilities of each set of teams that plays a
+    /// round robin at each tiebreak depth.
+    first_place: HashMap<(u32, usize), Vec<f64>>,
+}
+
+impl ExactGroupStage<'_> {
+    fn first_place(&mut self, teams: u32, depth: usize) -> Vec<f64> {
+        if let Some(probabilities) = self.first_place.get(&(teams, depth)) {
+            return probabilities.clone();
+        }
+        let mut probabilities = vec![0.0; self.p.len()];
+        for (leaders, probability) in self.leaders(teams) {
+            let positions: Vec<_> = (0..self.p.len())
+                .filter(|position| leaders & (1 << position) != 0)
+                .collect();
+            if positions.len() == 1 || self.group.tiebreak == Tiebreak::PreSeed {
+                // Sorting is stable, so the higher seed comes first.
+                probabilities[positions[0]] += probability;
+            } else if self.group.keep_ties || depth >= self.group.max_tiebreak_depth {
+                for position in positions.iter() {
+                    probabilities[*position] += probability / positions.len() as f64;
+                }
+            } else {
+                for (position, p) in self.first_place(leaders, depth + 1).into_iter().enumerate() {
+                    probabilities[position] += probability * p;
+                }
+            }
+        }
+        self.first_place
+            .insert((teams, depth), probabilities.clone());
+        probabilities
+    }
+
+    fn leaders(&mut self, teams: u32) -> Vec<(u32, f64)> {
+        if let Some(leaders) = self.leaders.get(&teams) {
+            return leaders.clone();
+        }
+        let positions: Vec<_> = (0..self.p.len())
+            .filter(|position| teams & (1 << position) != 0)
+            .collect();
+        let series: Vec<_> = positions
+            .iter()
+            .tuple_combinations()
+            .map(|(a, b)| (*a, *b, self.group.series_results(self.p[*a][*b])))
+            .collect();
+        let mut leaders = HashMap::new();
+        let mut points = vec![0; self.p.len()];
+        enumerate_series(&series, &positions, &mut points, 1.0, &mut leaders);
+        let leaders: Vec<_> = leaders.into_iter().collect();
+        self.leaders.insert(teams, leaders.clone());
+        leaders
+    }
+}
+
+/// Adds the probability of every combination of results of `series` to
+/// the set of teams that ends up with the most points.
+fn enumerate_series(
+    series: &[RoundRobinSeries],
+    positions: &[usize],
+    points: &mut [i64],
+    probability: f64,
+    leaders: &mut HashMap<u32, f64>,
+) {
+    let Some(((a, b, results), rest)) = series.split_first() else {
+        let most = positions
+            .iter()
+            .map(|position| points[*position])
+            .max()
+            .unwrap();
+        let mask = positions
+            .iter()
+            .filter(|position| points[**position] == most)
+            .fold(0, |mask, position| mask | (1 << position));
+        *leaders.entry(mask).or_default() += probability;
+        return;
+    };
+    for result in results.iter() {
+        if result.probability == 0.0 {
+            continue;
+        }
+        points[*a] += result.points[0];
+        points[*b] += result.points[1];
+        enumerate_series(
+            rest,
+            positions,
+            points,
+            probability * result.probability,
+            leaders,
+        );
+        points[*a] -= result.points[0];
+        points[*b] -= result.points[1];
+    }
+}
+
+/// Several group stages with the same rules, run independently on equally
+/// sized parts of the incoming teams. The result contains the standings of
+/// the first group, followed by those of the second group and so on.
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct Groups {
+    count: usize,
+    template: GroupStage,
+    #[serde(default)]
+    split: Split,
+}
+
+/// How the seeded incoming teams are distributed over the groups.
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, Default, PartialEq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub enum Split {
+    /// Seeds are dealt to the groups in rounds, in alternating direction,
+    /// so that every group gets a similar mix of seeds.
+    #[default]
+    Snake,
+    /// The first seeds form the first group, the next seeds the second one and so on.
+    Blocks,
+}
+
+impl Groups {
+    fn run(
+        &self,
+        input: &mut [Team],
+        model: &MatchModel,
+        rng: &mut impl Rng,
+        log: &mut MatchLog,
+    ) -> Outcome {
+        let mut outcome = Outcome::default();
+        let mut standings = Vec::with_capacity(input.len());
+        for seeds in split_into_groups(input.len(), self.count, self.split) {
+            let mut teams: Vec<_> = seeds.iter().map(|seed| input[*seed]).collect();
+            let group = self.template.run(&mut teams, model, rng, log);
+            let offset = standings.len();
+            outcome.tiebreak_depth = outcome.tiebreak_depth.max(group.tiebreak_depth);
+            outcome.ties.extend(
+                group
+                    .ties
+                    .into_iter()
+                    .map(|tie| tie.start + offset..tie.end + offset),
+            );
+            standings.extend(teams);
+        }
+        input.copy_from_slice(&standings);
+        outcome
+    }
+
+    /// Accepts `group_<g>_<placement>`, where groups are counted from 1 and
+    /// `placement` is a group stage placement such as `1st`.
+    fn get_placement_index_from_placement_name(
+        &self,
+        num_teams: usize,
+        placement: &str,
+    ) -> Option<usize> {
+        let group_size = num_teams / self.count;
+        let (group, rest) = placement
+            .strip_prefix("group_")
+            .and_then(|rest| rest.split_once('_'))?;
+        match group.parse::<usize>() {
+            Ok(group) if (1..=self.count).contains(&group) => {
+                let position = self
+                    .template
+                    .get_placement_index_from_placement_name(rest)?;
+                (position < group_size).then_some((group - 1) * group_size + position)
+            }
+            _ => None,
+        }
+    }
+}
+
+/// A round robin between all teams, after which the top `cut` teams play a
+/// single elimination bracket, seeded by their group standing. The result
+/// is the order of the bracket followed by the group standings of the teams
+/// that missed the cut.
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct RoundRobinCut {
+    /// The length of the series in the round robin.
+    games: usize,
+    cut: usize,
+    /// The length of the series in the bracket.
+    bracket_series: usize,
+}
+
+impl RoundRobinCut {
+    fn group(&self) -> GroupStage {
+        GroupStage {
+            num_games_per_series: self.games,
+            max_tiebreak_depth: default_max_tiebreak_depth(),
+            keep_ties: false,
+            tiebreak: Tiebreak::default(),
+            draw_prob: 0.0,
+            points: Points::default(),
+        }
+    }
+
+    fn run(
+        &self,
+        input: &mut [Team],
+        model: &MatchModel,
+        rng: &mut impl Rng,
+        log: &mut MatchLog,
+    ) -> Outcome {
+        let outcome = self.group().run(input, model, rng, log);
+        let bracket = SingleElimination::new(self.bracket_series);
+        bracket.run(&mut input[..self.cut], model, rng, log);
+        outcome
+    }
+
+    /// Accepts the bracket placements for the teams that made the cut and
+    /// `group_<placement>`, such as `group_5th`, for the teams that missed it.
+    fn get_placement_index_from_placement_name(
+        &self,
+        num_teams: usize,
+        placement: &str,
+    ) -> Option<usize> {
+        match placement.strip_prefix("group_") {
+            Some(rest) => self
+                .group()
+                .get_placement_index_from_placement_name(rest)
+                .filter(|position| (self.cut..num_teams).contains(position)),
+            None => SingleElimination::new(self.bracket_series)
+                .get_placement_index_from_placement_name(self.cut, placement),
+        }
+    }
+}
+
+/// The seeds of the teams in each group, each in seeding order.
+pub(crate) fn split_into_groups(num_teams: usize, count: usize, split: Split) -> Vec<Vec<usize>> {
+    let mut groups = vec![vec![]; count];
+    for seed in 0..num_teams {
+        let group = match split {
+            Split::Blocks => seed / (num_teams / count),
+            Split::Snake => {
+                let (round, position) = (seed / count, seed % count);
+                if round % 2 == 0 {
+                    position
+                } else {
+                    count - 1 - position
+                }
+            }
+        };
+        groups[group].push(seed);
+    }
+    groups
+}
+
+#[derive(Debug, PartialEq, Eq)]
+struct TiedTeams {
+    teams: Vec<TeamIndex>,
+    start_index: usize,
+    end_index: usize,
+}
+
+fn identify_tied_teams(teams: &[Team], points: &[i64]) -> Vec<TiedTeams> {
+    debug_assert!(points.windows(2).all(|ps| ps[0] >= ps[1]));
+    teams
+        .iter()
+        .zip(points)
+        .enumerate()
+        .group_by(|(_, (_, points))| **points)
+        .into_iter()
+        .map(|(_, group)| group.collect::<Vec<_>>())
+        .filter(|group| group.len() > 1)
+        .map(|group| TiedTeams {
+            start_index: group.first().unwrap().0,
+            end_index: group.last().unwrap().0,
+            teams: group.iter().map(|(_, (team, _))| team.index).collect(),
+        })
+        .collect()
+}
+
+/// Sorts the teams by their points in descending order, moving the points
+/// along. Stable, so that tied teams stay in seeding order. Insertion sort
+/// needs no allocation and its quadratic worst case is no worse than that
+/// of playing the round robin in the first place.
+fn sort_by_points(teams: &mut [Team], points: &mut [i64]) {
+    for i in 1..teams.len() {
+        let mut j = i;
+        while j > 0 && points[j - 1] < points[j] {
+            teams.swap(j - 1, j);
+            points.swap(j - 1, j);
+            j -= 1;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use super::Component;
+    use super::ComponentType;
+    use super::DoubleElimination;
+    use super::Gauntlet;
+    use super::GroupStage;
+    use super::MatchLog;
+    use super::MatchModel;
+    use super::Points;
+    use super::SingleElimination;
+    use super::SingleTable;
+    use super::Split;
+    use super::Tiebreak;
+    use super::TiedTeams;
+    use crate::math::best_of_n_win_probability;
+    use crate::math::best_of_n_win_probability_with_handicap;
+    use crate::rng::stream_rng;
+    use crate::teams;
+    use crate::Team;
+
+    #[test]
+    fn favorite_win_probability() {
+        let mut rng = rand::thread_rng();
+        let component = |r#type| Component::new(r#type, vec![(); 4]);
+        let bo3 = ComponentType::BestOf3;
+        let (p, exact) = component(bo3).favorite_win_probability(0, &mut rng);
+        assert!(exact && (p - best_of_n_win_probability(0.6, 3)).abs() < 1e-12);
+        let bracket = ComponentType::SingleElimination(SingleElimination::new(1));
+        let (p, exact) = component(bracket).favorite_win_probability(0, &mut rng);
+        assert!(exact && (p - 0.36).abs() < 1e-12);
+        let gauntlet = ComponentType::Gauntlet(Gauntlet {
+            num_games_per_series: 1,
+        });
+        let (p, exact) = component(gauntlet).favorite_win_probability(0, &mut rng);
+        assert!(exact && (p - 0.216).abs() < 1e-12);
+    }
+
+    #[test]
+    fn series_by_round() {
+        let yaml = "!SingleElimination \
+            {series_by_round: [!BestOf1, !HandicapBestOfN {num_games: 7, handicap: 1}]}";
+        let r#type: ComponentType = serde_yaml::from_str(yaml).unwrap();
+        let component = |num_teams| Component::new(r#type.clone(), vec![(); num_teams]);
+        let bracket = component(4);
+        assert_eq!(
+            bracket.r#type.to_string(),
+            "SingleElimination(BestOf1, BestOf7(handicap 1) by round)"
+        );
+        assert!(bracket.validate().is_ok());
+        assert!(component(8).validate().is_err());
+        let (p, exact) = bracket.favorite_win_probability(0, &mut rand::thread_rng());
+        let expected = 0.6 * best_of_n_win_probability_with_handicap(0.6, 7, 1);
+        assert!(exact && (p - expected).abs() < 1e-12);
+        let mut teams = super::favorite_and_underdogs(4);
+        let outcome = bracket.run(&mut teams, &MatchModel::default(), &mut rand::thread_rng());
+        assert_eq!(outcome.matches, 3);
+        let mut indices: Vec<_> = teams.iter().map(|team| team.index).collect();
+        indices.sort();
+        assert_eq!(indices, vec![0, 1, 2, 3]);
+    }
+
+    /// The order of four teams after `play`, which has to play `num_series`
+    /// series. The series with a set bit in `upsets` are won by the second
+    /// team.
+    fn scripted(
+        play: impl FnOnce(&mut [Team], &mut dyn FnMut(&mut [Team])),
+        num_series: u32,
+        upsets: u32,
+    ) -> Vec<usize> {
+        let mut teams = teams(4, &[]);
+        let mut played = 0;
+        play(&mut teams, &mut |pair| {
+            if upsets & (1 << played) != 0 {
+                pair.swap(0, 1);
+            }
+            played += 1;
+        });
+        assert_eq!(played, num_series);
+        teams.iter().map(|team| team.index).collect()
+    }
+
+    /// Plays a gauntlet where the challenger wins the `i`-th series if
+    /// the `i`-th bit of `upsets` is set.
+    fn scripted_gauntlet(upsets: u32) -> Vec<usize> {
+        scripted(|teams, play| super::gauntlet(teams, play), 3, upsets)
+    }
+
+    #[test]
+    fn gauntlet_paths() {
+        assert_eq!(scripted_gauntlet(0b000), vec![0, 3, 2, 1]);
+        assert_eq!(scripted_gauntlet(0b111), vec![3, 2, 1, 0]);
+        assert_eq!(scripted_gauntlet(0b010), vec![2, 3, 0, 1]);
+        assert_eq!(super::gauntlet_placement_index(4, "winner"), Some(0));
+        // In the last case, team 1 is eliminated first and team 3 last.
+        assert_eq!(super::gauntlet_placement_index(4, "eliminated_1"), Some(3));
+        assert_eq!(super::gauntlet_placement_index(4, "eliminated_3"), Some(1));
+    }
+
+    #[test]
+    fn gauntlet_rejects_out_of_range_placements() {
+        assert_eq!(super::gauntlet_placement_index(4, "eliminated_4"), None);
+    }
+
+    /// Plays a Page playoff where the `i`-th series is an upset if
+    /// the `i`-th bit of `upsets` is set.
+    fn scripted_page_playoff(upsets: u32) -> Vec<usize> {
+        scripted(|teams, play| super::page_playoff(teams, play), 4, upsets)
+    }
+
+    #[test]
+    fn page_playoff_paths() {
+        // Bit 0: first qualifier, 1: eliminator, 2: second qualifier, 3: final.
+        assert_eq!(scripted_page_playoff(0b0000), vec![0, 1, 2, 3]);
+        assert_eq!(scripted_page_playoff(0b1000), vec![1, 0, 2, 3]);
+        assert_eq!(scripted_page_playoff(0b0100), vec![0, 2, 1, 3]);
+        assert_eq!(scripted_page_playoff(0b0010), vec![0, 1, 3, 2]);
+        assert_eq!(scripted_page_playoff(0b0110), vec![0, 3, 1, 2]);
+        assert_eq!(scripted_page_playoff(0b0001), vec![1, 0, 2, 3]);
+        assert_eq!(scripted_page_playoff(0b0101), vec![1, 2, 0, 3]);
+        assert_eq!(scripted_page_playoff(0b1111), vec![3, 1, 0, 2]);
+        for upsets in 0..16 {
+            let mut order = scripted_page_playoff(upsets);
+            order.sort();
+            assert_eq!(order, vec![0, 1, 2, 3]);
+        }
+    }
+
+    #[test]
+    fn tiebreak_depth_is_capped() {
+        // Without any games every replay ends in a full tie again, and so
+        // does every head to head comparison.
+        for tiebreak in [Tiebreak::Replay, Tiebreak::HeadToHead] {
+            let group = GroupStage {
+                num_games_per_series: 0,
+                max_tiebreak_depth: 5,
+                keep_ties: false,
+                tiebreak,
+                draw_prob: 0.0,
+                points: Points::default(),
+            };
+            let mut teams: Vec<_> = teams(4, &[]);
+            let mut log = MatchLog::new(&teams);
+            let outcome = group.run(
+                &mut teams,
+                &Default::default(),
+                &mut rand::thread_rng(),
+                &mut log,
+            );
+            assert_eq!(outcome.tiebreak_depth, 5);
+            assert!(outcome.ties.is_empty());
+            let mut indices: Vec<_> = teams.iter().map(|team| team.index).collect();
+            indices.sort();
+            assert_eq!(indices, vec![0, 1, 2, 3]);
+        }
+    }
+
+    #[test]
+    fn higher_seed_wins_ties() {
+        let make_group = |tiebreak| GroupStage {
+            num_games_per_series: 0,
+            max_tiebreak_depth: 0,
+            keep_ties: false,
+            tiebreak,
+            draw_prob: 0.0,
+            points: Points::default(),
+        };
+        let first_place = |group: GroupStage, seed| {
+            let mut teams = vec![Team::new(7), Team::new(3)];
+            let mut log = MatchLog::new(&teams);
+            group.run(
+                &mut teams,
+                &Default::default(),
+                &mut stream_rng(seed, 0),
+                &mut log,
+            );
+            teams[0].index
+        };
+        let pre_seed: HashSet<_> = (0..50)
+            .map(|seed| first_place(make_group(Tiebreak::PreSeed), seed))
+            .collect();
+        assert_eq!(pre_seed, [7].into_iter().collect());
+        let replay: HashSet<_> = (0..50)
+            .map(|seed| first_place(make_group(Tiebreak::Replay), seed))
+            .collect();
+        assert_eq!(replay.len(), 2);
+    }
+
+    #[test]
+    fn upsets_are_counted() {
+        let component = Component::new(ComponentType::BestOf3, vec![(); 2]);
+        for seed in 0..50 {
+            let mut teams = super::favorite_and_underdogs(2);
+            teams.reverse();
+            let outcome = component.run(&mut teams, &Default::default(), &mut stream_rng(seed, 0));
+            assert_eq!(outcome.matches, 1);
+            assert_eq!(outcome.upsets, teams[0].is_strong() as usize);
+        }
+    }
+
+    #[test]
+    fn draw_points_are_awarded() {
+        let group = GroupStage {
+            num_games_per_series: 1,
+            max_tiebreak_depth: 0,
+            keep_ties: true,
+            tiebreak: Tiebreak::Replay,
+            draw_prob: 0.5,
+            points: Points {
+                win: 3,
+                draw: 1,
+                loss: 0,
+            },
+        };
+        let mut results = HashSet::new();
+        for seed in 0..100 {
+            let mut teams = super::favorite_and_underdogs(2);
+            let mut log = MatchLog::new(&teams);
+            let mut points = vec![];
+            group.play(
+                &mut teams,
+                &Default::default(),
+                &mut stream_rng(seed, 0),
+                &mut log,
+                &mut points,
+            );
+            results.insert((points[0], points[1]));
+            // Draws are not decided matches.
+            assert_eq!(log.matches, (points[0] == 3) as usize);
+        }
+        assert_eq!(results, [(3, 0), (1, 1)].into_iter().collect());
+    }
+
+    #[test]
+    fn exact_first_place_probabilities_match_simulation() {
+        let teams = super::favorite_and_underdogs(4);
+        let p = super::probability_matrix(&teams);
+        for (draw_prob, points) in [
+            (0.0, Points::default()),
+            (
+                0.3,
+                Points {
+                    win: 3,
+                    draw: 1,
+                    loss: 0,
+                },
+            ),
+        ] {
+            let group = GroupStage {
+                num_games_per_series: 2,
+                max_tiebreak_depth: 2,
+                keep_ties: false,
+                tiebreak: Tiebreak::Replay,
+                draw_prob,
+                points,
+            };
+            let exact = group.first_place_probabilities(&p).unwrap();
+            assert!((exact.values().sum::<f64>() - 1.0).abs() < 1e-12);
+            let component = Component::new(ComponentType::GroupStage(group), vec![(); 4]);
+            let simulated = component.simulate_favorite_wins(&teams, 200000, &mut stream_rng(0, 0));
+            assert!(
+                (exact[&0] - simulated).abs() < 0.005,
+                "{} vs {}",
+                exact[&0],
+                simulated
+            );
+        }
+    }
+
+    #[test]
+    fn exact_first_place_probabilities_of_simple_groups() {
+        let group = |num_games_per_series, tiebreak| GroupStage {
+            num_games_per_series,
+            max_tiebreak_depth: 100,
+            keep_ties: false,
+            tiebreak,
+            draw_prob: 0.0,
+            points: Points::default(),
+        };
+        let equal = vec![vec![0.5; 3]; 3];
+        let exact = group(1, Tiebreak::Replay)
+            .first_place_probabilities(&equal)
+            .unwrap();
+        for position in 0..3 {
+            assert!((exact[&position] - 1.0 / 3.0).abs() < 1e-9);
+        }
+        let exact = group(0, Tiebreak::PreSeed)
+            .first_place_probabilities(&equal)
+            .unwrap();
+        assert_eq!(exact[&0], 1.0);
+        let large = vec![vec![0.5; 8]; 8];
+        assert!(group(3, Tiebreak::Replay)
+            .first_place_probabilities(&large)
+            .is_none());
+        // Without games there is a single result, however many teams there are.
+        let huge = vec![vec![0.5; 34]; 34];
+        assert!(group(0, Tiebreak::PreSeed)
+            .first_place_probabilities(&huge)
+            .is_none());
+    }
+
+    #[test]
+    fn split_into_groups() {
+        assert_eq!(
+            super::split_into_groups(8, 2, Split::Blocks),
+            vec![vec![0, 1, 2, 3], vec![4, 5, 6, 7]]
+        );
+        assert_eq!(
+            super::split_into_groups(9, 3, Split::Snake),
+            vec![vec![0, 5, 6], vec![1, 4, 7], vec![2, 3, 8]]
+        );
+    }
+
+    #[test]
+    fn groups() {
+        let component: Component<usize> = serde_yaml::from_str(
+            "
+type: !Groups {count: 2, template: {num_games_per_series: 1}}
+teams: [0, 1, 2, 3, 4, 5]
+",
+        )
+        .unwrap();
+        assert!(component.validate().is_ok());
+        assert_eq!(
+            component.get_placement_index_from_placement_name("group_1_1st"),
+            Some(0)
+        );
+        assert_eq!(
+            component.get_placement_index_from_placement_name("group_2_3rd"),
+            Some(5)
+        );
+        let mut teams: Vec<_> = teams(6, &[]);
+        component.run(&mut teams, &Default::default(), &mut stream_rng(0, 0));
+        let group = |teams: &[Team]| {
+            let mut indices: Vec<_> = teams.iter().map(|team| team.index).collect();
+            indices.sort();
+            indices
+        };
+        assert_eq!(group(&teams[..3]), vec![0, 3, 4]);
+        assert_eq!(group(&teams[3..]), vec![1, 2, 5]);
+        let uneven = Component {
+            teams: vec![0; 5],
+            ..component
+        };
+        assert!(uneven.validate().is_err());
+    }
+
+    #[test]
+    fn groups_reject_placements_beyond_the_group_size() {
+        let component: Component<usize> = serde_yaml::from_str(
+            "
+type: !Groups {count: 2, template: {num_games_per_series: 1}}
+teams: [0, 1, 2, 3]
+",
+        )
+        .unwrap();
+        assert_eq!(
+            component.get_placement_index_from_placement_name("group_1_3rd"),
+            None
+        );
+    }
+
+    #[test]
+    fn round_robin_cut() {
+        let component: Component<usize> = serde_yaml::from_str(
+            "
+type: !RoundRobinCut {games: 1, cut: 4, bracket_series: 3}
+teams: [0, 1, 2, 3, 4, 5]
+",
+        )
+        .unwrap();
+        assert!(component.validate().is_ok());
+        assert_eq!(
+            component.get_placement_index_from_placement_name("winner"),
+            Some(0)
+        );
+        assert_eq!(
+            component.get_placement_index_from_placement_name("semifinal_loser_2"),
+            Some(3)
+        );
+        assert_eq!(
+            component.get_placement_index_from_placement_name("group_6th"),
+            Some(5)
+        );
+        let mut teams: Vec<_> = teams(6, &[5]);
+        let model = MatchModel::default().with_strong_team_advantage(0.5);
+        let outcome = component.run(&mut teams, &model, &mut stream_rng(0, 0));
+        // The strong team tops the round robin and wins every bracket match.
+        assert_eq!(teams[0].index, 5);
+        // 15 round robin pairings, possibly some tiebreaks and 3 bracket series.
+        assert!(outcome.matches >= 18);
+        let all: HashSet<_> = teams.iter().map(|team| team.index).collect();
+        assert_eq!(all.len(), 6);
+        for cut in [3, 8] {
+            let invalid = Component {
+                r#type: ComponentType::RoundRobinCut(super::RoundRobinCut {
+                    games: 1,
+                    cut,
+                    bracket_series: 3,
+                }),
+                ..component.clone()
+            };
+            assert!(invalid.validate().is_err());
+        }
+    }
+
+    #[test]
+    fn round_robin_cut_rejects_group_placements_inside_the_cut() {
+        let component: Component<usize> = serde_yaml::from_str(
+            "
+type: !RoundRobinCut {games: 1, cut: 4, bracket_series: 3}
+teams: [0, 1, 2, 3, 4, 5]
+",
+        )
+        .unwrap();
+        assert_eq!(
+            component.get_placement_index_from_placement_name("group_4th"),
+            None
+        );
+    }
+
+    #[test]
+    fn head_to_head_breaks_ties() {
+        let team = Team::new;
+        let break_ties = |standings: &mut Vec<Team>, points: Vec<i64>, table| {
+            let ties = super::identify_tied_teams(standings, &points);
+            super::HeadToHeadTable {
+                teams: vec![0, 1, 2, 3],
+                points: table,
+            }
+            .break_ties(standings, ties)
+        };
+        // Teams 1 and 3 are tied on two points and team 3 beat team 1.
+        // Teams 0 and 2 are tied on one point and team 0 beat team 2.
+        let mut standings = vec![team(1), team(3), team(2), team(0)];
+        let table = vec![0, 0, 1, 0, 1, 0, 1, 0, 0, 0, 0, 1, 1, 1, 0, 0];
+        assert_eq!(break_ties(&mut standings, vec![2, 2, 1, 1], table), vec![]);
+        assert_eq!(standings, vec![team(3), team(1), team(0), team(2)]);
+        // Teams 1, 2 and 3 beat each other in a cycle, so they are still
+        // tied head to head and are left to the replay.
+        let mut standings = vec![team(1), team(2), team(3), team(0)];
+        let table = vec![0, 0, 0, 0, 1, 0, 1, 0, 1, 0, 0, 1, 1, 1, 0, 0];
+        assert_eq!(
+            break_ties(&mut standings, vec![2, 2, 2, 0], table),
+            vec![TiedTeams {
+                teams: vec![1, 2, 3],
+                start_index: 0,
+                end_index: 2,
+            }]
+        );
+    }
+
+    #[test]
+    fn head_to_head_tiebreaks_are_shown() {
+        let group: GroupStage = serde_yaml::from_str(
+            "{num_games_per_series: 1, points: {win: 3, draw: 1, loss: 0}, tiebreak: HeadToHead}",
+        )
+        .unwrap();
+        assert_eq!(
+            ComponentType::GroupStage(group).to_string(),
+            "GroupStage(1 games/series, points 3-1-0, ties broken head to head)"
+        );
+    }
+
+    #[test]
+    fn kept_ties_are_reported() {
+        let group = GroupStage {
+            num_games_per_series: 0,
+            max_tiebreak_depth: 5,
+            keep_ties: true,
+            tiebreak: Tiebreak::Replay,
+            draw_prob: 0.0,
+            points: Points::default(),
+        };
+        let mut teams: Vec<_> = teams(4, &[]);
+        let mut log = MatchLog::new(&teams);
+        let outcome = group.run(
+            &mut teams,
+            &Default::default(),
+            &mut rand::thread_rng(),
+            &mut log,
+        );
+        assert_eq!(outcome.tiebreak_depth, 0);
+        assert_eq!(outcome.ties, vec![0..4]);
+    }
+
+    #[test]
+    fn display() {
+        let group = ComponentType::GroupStage(GroupStage {
+            num_games_per_series: 3,
+            max_tiebreak_depth: 1,
+            keep_ties: false,
+            tiebreak: Tiebreak::Replay,
+            draw_prob: 0.0,
+            points: Points::default(),
+        });
+        let component = Component::new(group, vec![1, 2]);
+        assert_eq!(component.to_string(), "GroupStage(3 games/series) [1, 2]");
+        let football = ComponentType::GroupStage(GroupStage {
+            num_games_per_series: 1,
+            max_tiebreak_depth: 1,
+            keep_ties: false,
+            tiebreak: Tiebreak::Replay,
+            draw_prob: 0.25,
+            points: Points {
+                win: 3,
+                draw: 1,
+                loss: 0,
+            },
+        });
+        assert_eq!(
+            football.to_string(),
+            "GroupStage(1 games/series, draws 0.25, points 3-1-0)"
+        );
+        assert_eq!(ComponentType::BestOfN(9).to_string(), "BestOf9");
+    }
+
+    #[test]
+    fn handicap_must_be_less_than_wins_needed() {
+        let handicap = |handicap| ComponentType::HandicapBestOfN {
+            num_games: 3,
+            handicap,
+        };
+        assert!(handicap(1).validate().is_ok());
+        assert!(handicap(2).validate().is_err());
+    }
+
+    #[test]
+    fn series_must_have_an_odd_number_of_games() {
+        assert!(ComponentType::BestOfN(0).validate().is_err());
+        assert!(ComponentType::BestOfN(4).validate().is_err());
+        assert!(ComponentType::BestOfN(5).validate().is_ok());
+        let bracket: ComponentType =
+            serde_yaml::from_str("!SingleElimination {num_games_per_series: 0}").unwrap();
+        assert!(bracket.validate().is_err());
+        for group in [
+            "!GroupStage {num_games_per_series: 0}",
+            "!Groups {count: 2, template: {num_games_per_series: 0}}",
+        ] {
+            let group: ComponentType = serde_yaml::from_str(group).unwrap();
+            assert!(group.validate().is_err());
+        }
+    }
+
+    fn series_win_frequency(r#type: ComponentType) -> f64 {
+        let component = Component::new(r#type, vec![(); 2]);
+        let mut rng = stream_rng(0, 0);
+        let num_runs = 20000;
+        let num_wins = (0..num_runs)
+            .filter(|_| {
+                let mut teams = super::favorite_and_underdogs(2);
+                component.run(&mut teams, &Default::default(), &mut rng);
+                teams[0].is_strong()
+            })
+            .count();
+        num_wins as f64 / num_runs as f64
+    }
+
+    #[test]
+    fn replayed_draws_do_not_change_the_winner_distribution() {
+        let expected = best_of_n_win_probability(0.6, 5);
+        for draw_prob in [0.0, 0.4] {
+            let frequency = series_win_frequency(ComponentType::ReplayDrawsBestOfN {
+                num_games: 5,
+                draw_prob,
+            });
+            assert!((frequency - expected).abs() < 0.015);
+        }
+        assert!((series_win_frequency(ComponentType::BestOf5) - expected).abs() < 0.015);
+    }
+
+    #[test]
+    fn replayed_draws_add_games() {
+        let with_draws = ComponentType::ReplayDrawsBestOfN {
+            num_games: 3,
+            draw_prob: 0.5,
+        };
+        let without = ComponentType::BestOf3.expected_num_games().unwrap();
+        assert!((with_draws.expected_num_games().unwrap() - 2.0 * without).abs() < 1e-12);
+    }
+
+    #[test]
+    fn single_game_draws() {
+        let component = |draw_prob, shootout| {
+            Component::new(
+                ComponentType::SingleGame {
+                    draw_prob,
+                    shootout,
+                },
+                vec![(); 2],
+            )
+        };
+        let mut teams = super::favorite_and_underdogs(2);
+        let outcome =
+            component(1.0, false).run(&mut teams, &Default::default(), &mut stream_rng(0, 0));
+        assert_eq!(outcome.ties, vec![0..2]);
+        assert!((series_win_frequency(component(1.0, true).r#type) - 0.5).abs() < 0.015);
+        let (p, exact) = component(0.4, true).favorite_win_probability(0, &mut rand::thread_rng());
+        assert!(exact);
+        assert!((p - (0.6 * 0.6 + 0.4 * 0.5)).abs() < 1e-12);
+        assert!((series_win_frequency(component(0.4, true).r#type) - p).abs() < 0.015);
+        assert!(component(1.5, true).validate().is_err());
+    }
+
+    #[test]
+    fn two_leg_ties_are_decided_on_aggregate() {
+        let no_penalties = || panic!("No penalties expected");
+        assert!(super::two_leg_winner([2, 1], [0, 2], false, no_penalties));
+        assert!(!super::two_leg_winner([0, 1], [1, 1], true, no_penalties));
+        // 1-1 away and 0-0 at home: level on aggregate, but the first team
+        // scored an away goal and the second team did not.
+        assert!(super::two_leg_winner([1, 0], [1, 0], true, no_penalties));
+        // 0-0 away and 1-1 at home: the second team scored away.
+        assert!(!super::two_leg_winner([0, 1], [0, 1], true, no_penalties));
+        // Without away goals, both go to penalties.
+        assert!(super::two_leg_winner([1, 0], [1, 0], false, || true));
+        assert!(!super::two_leg_winner([0, 1], [0, 1], false, || false));
+        // Level on away goals as well.
+        assert!(super::two_leg_winner([1, 1], [1, 1], true, || true));
+    }
+
+    #[test]
+    fn two_leg_tie_favors_the_favorite() {
+        let tie: ComponentType =
+            serde_yaml::from_str("!TwoLegTie {goals_per_game: 2.5, away_goals: true}").unwrap();
+        assert!(tie.validate().is_ok());
+        let frequency = series_win_frequency(tie);
+        assert!(frequency > 0.6 && frequency < 0.8);
+        let scoreless: ComponentType =
+            serde_yaml::from_str("!TwoLegTie {goals_per_game: 0.0}").unwrap();
+        assert!(scoreless.validate().is_err());
+    }
+
+    #[test]
+    fn precomputed_series_win_probabilities_match_the_computed_ones() {
+        let bracket: ComponentType =
+            serde_yaml::from_str("!SingleElimination {series_by_round: [!BestOf1, !HandicapBestOfN {num_games: 7, handicap: 1}]}")
+                .unwrap();
+        let series = bracket.best_of_n_series();
+        assert_eq!(series, vec![(1, 0), (7, 1)]);
+        let model =
+            MatchModel::default().with_series_precomputed(&series, &[crate::BASE_RATING; 8]);
+        let teams = super::favorite_and_underdogs(2);
+        for p in [0.5, teams[0].probability_to_win_against(&teams[1]), 0.3] {
+            for (num_games, handicap) in series.iter() {
+                assert_eq!(
+                    model.series_win_probability(p, *num_games, *handicap),
+                    best_of_n_win_probability_with_handicap(p, *num_games, *handicap)
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn series_decision_at_the_boundaries() {
+        use rand::rngs::mock::StepRng;
+        let play = |advantage, rng: &mut StepRng| {
+            let mut teams = super::favorite_and_underdogs(2);
+            let model = MatchModel::default().with_strong_team_advantage(advantage);
+            super::BestOfN::new(3).run(&mut teams, &model, rng);
+            teams[0].is_strong()
+        };
+        // The largest number the generator can produce does not make a
+        // certain winner lose, and the smallest one does not save a certain loser.
+        assert!(play(0.5, &mut StepRng::new(u64::MAX, 0)));
+        assert!(!play(-0.5, &mut StepRng::new(0, 0)));
+        // At an even series, the favorite wins exactly below one half.
+        assert!(play(0.0, &mut StepRng::new(u64::MAX / 2 - (1 << 11), 0)));
+        assert!(!play(0.0, &mut StepRng::new(u64::MAX / 2 + (1 << 11), 0)));
+    }
+
+    #[test]
+    fn placebo_passes_teams_on_unchanged() {
+        let component: Component<usize> =
+            serde_yaml::from_str("{type: !Placebo, teams: [0, 1, 2]}").unwrap();
+        let mut teams = super::favorite_and_underdogs(3);
+        teams.reverse();
+        let before = teams.clone();
+        let outcome = component.run(&mut teams, &Default::default(), &mut stream_rng(0, 0));
+        assert_eq!(teams, before);
+        assert_eq!(outcome.matches, 0);
+        assert_eq!(
+            component.get_placement_index_from_placement_name("position_3"),
+            Some(2)
+        );
+        assert_eq!(
+            component.favorite_win_probability(0, &mut rand::thread_rng()),
+            (1.0, true)
+        );
+    }
+
+    #[test]
+    fn placebo_rejects_positions_beyond_its_teams() {
+        let component: Component<usize> =
+            serde_yaml::from_str("{type: !Placebo, teams: [0, 1, 2]}").unwrap();
+        assert_eq!(
+            component.get_placement_index_from_placement_name("position_4"),
+            None
+        );
+    }
+
+    #[test]
+    fn bracket_order() {
+        assert_eq!(super::bracket_order(2), vec![0, 1]);
+        assert_eq!(super::bracket_order(4), vec![0, 3, 1, 2]);
+        assert_eq!(super::bracket_order(8), vec![0, 7, 3, 4, 1, 6, 2, 5]);
+    }
+
+    #[test]
+    fn single_elimination_placement_names() {
+        let bracket = SingleElimination::new(1);
+        let index = |name| bracket.get_placement_index_from_placement_name(8, name);
+        assert_eq!(index("winner"), Some(0));
+        assert_eq!(index("finalist"), Some(1));
+        assert_eq!(index("round_3_loser_1"), Some(1));
+        assert_eq!(index("semifinal_loser_1"), Some(2));
+        assert_eq!(index("semifinal_loser_2"), Some(3));
+        assert_eq!(index("quarterfinal_loser_1"), Some(4));
+        assert_eq!(index("quarterfinal_loser_4"), Some(7));
+        assert_eq!(index("round_1_loser_4"), Some(7));
+    }
+
+    #[test]
+    fn single_elimination_rejects_out_of_range_loser() {
+        assert_eq!(
+            SingleElimination::new(1)
+                .get_placement_index_from_placement_name(8, "semifinal_loser_3"),
+            None
+        );
+    }
+
+    #[test]
+    fn reseeding_bracket_pairs_best_with_worst_remaining_seed() {
+        // The better seed always wins, except that the last seed beats the first.
+        let win_probabilities: Vec<Vec<f64>> = (0..8)
+            .map(|i| {
+                (0..8)
+                    .map(|j| match (i, j) {
+                        (7, 0) => 1.0,
+                        (0, 7) => 0.0,
+                        _ if i < j => 1.0,
+                        _ if i > j => 0.0,
+                        _ => 0.5,
+                    })
+                    .collect()
+            })
+            .collect();
+        let model = MatchModel::default().with_win_probabilities(win_probabilities);
+        let bracket = SingleElimination {
+            reseed_each_round: true,
+            ..SingleElimination::new(1)
+        };
+        let mut teams: Vec<_> = teams(8, &[]);
+        let mut pairings = vec![];
+        let mut observer = |a: Team, b: Team, _| pairings.push((a.index, b.index));
+        let mut log = MatchLog::new(&teams).with_observer(Some(&mut observer));
+        bracket.run(&mut teams, &model, &mut rand::thread_rng(), &mut log);
+        drop(log);
+        assert_eq!(
+            pairings,
+            vec![(0, 7), (1, 6), (2, 5), (3, 4), (1, 7), (2, 3), (1, 2)]
+        );
+        let order: Vec<_> = teams.iter().map(|team| team.index).collect();
+        assert_eq!(order, vec![1, 2, 7, 3, 0, 6, 5, 4]);
+    }
+
+    #[test]
+    fn top_seeds_get_byes_if_the_bracket_is_not_full() {
+        let component: Component<usize> = serde_yaml::from_str(
+            "
+type: !SingleElimination {num_games_per_series: 1}
+teams: [0, 1, 2, 3, 4, 5]
+",
+        )
+        .unwrap();
+        assert!(component.validate().is_ok());
+        let index = |name| component.get_placement_index_from_placement_name(name);
+        assert_eq!(index("semifinalist_1"), Some(2));
+        assert_eq!(index("round_1_loser_2"), Some(5));
+        assert_eq!(index("round_1_loser_3"), None);
+        // The better seed always wins.
+        let win_probabilities: Vec<Vec<f64>> = (0..6)
+            .map(|i| (0..6).map(|j| if i < j { 1.0 } else { 0.0 }).collect())
+            .collect();
+        let model = MatchModel::default().with_win_probabilities(win_probabilities);
+        let mut teams: Vec<_> = teams(6, &[]);
+        let mut pairings = vec![];
+        let mut observer = |a: Team, b: Team, _| pairings.push((a.index, b.index));
+        let mut log = MatchLog::new(&teams).with_observer(Some(&mut observer));
+        SingleElimination::new(1).run(&mut teams, &model, &mut rand::thread_rng(), &mut log);
+        drop(log);
+        assert_eq!(pairings, vec![(3, 4), (2, 5), (0, 3), (1, 2), (0, 1)]);
+        let order: Vec<_> = teams.iter().map(|team| team.index).collect();
+        assert_eq!(order, vec![0, 1, 3, 2, 4, 5]);
+    }
+
+    #[test]
+    fn double_elimination_placement_names() {
+        let index = |name| super::double_elimination_placement_index(8, name);
+        assert_eq!(index("winner"), Some(0));
+        assert_eq!(index("grand_finalist"), Some(1));
+        assert_eq!(index("3rd"), Some(2));
+        assert_eq!(index("lower_round_4_loser_1"), Some(2));
+        assert_eq!(index("lower_round_3_loser_1"), Some(3));
+        assert_eq!(index("lower_round_2_loser_2"), Some(5));
+        assert_eq!(index("lower_round_1_loser_2"), Some(7));
+        assert_eq!(index("lower_round_1_loser_3"), None);
+        assert_eq!(index("lower_round_5_loser_1"), None);
+    }
+
+    #[test]
+    fn double_elimination_eliminates_after_two_losses() {
+        // The better seed always wins.
+        let win_probabilities: Vec<Vec<f64>> = (0..8)
+            .map(|i| (0..8).map(|j| if i < j { 1.0 } else { 0.0 }).collect())
+            .collect();
+        let model = MatchModel::default().with_win_probabilities(win_probabilities);
+        let mut teams: Vec<_> = teams(8, &[]);
+        let mut pairings = vec![];
+        let mut observer = |a: Team, b: Team, _| pairings.push((a.index, b.index));
+        let mut log = MatchLog::new(&teams).with_observer(Some(&mut observer));
+        let bracket = DoubleElimination {
+            num_games_per_series: 1,
+            bracket_reset: false,
+        };
+        bracket.run(&mut teams, &model, &mut rand::thread_rng(), &mut log);
+        drop(log);
+        assert_eq!(
+            pairings,
+            vec![
+                (0, 7),
+                (3, 4),
+                (1, 6),
+                (2, 5),
+                (7, 4),
+                (6, 5),
+                (0, 3),
+                (1, 2),
+                (2, 4),
+                (3, 5),
+                (2, 3),
+                (0, 1),
+                (1, 2),
+                (0, 1)
+            ]
+        );
+        let order: Vec<_> = teams.iter().map(|team| team.index).collect();
+        assert_eq!(order, vec![0, 1, 2, 3, 4, 5, 7, 6]);
+    }
+
+    #[test]
+    fn bracket_reset_gives_the_upper_champion_a_second_chance() {
+        let run = |bracket_reset| {
+            let mut teams: Vec<_> = teams(4, &[]);
+            // The better seed wins every match except the first grand final.
+            let mut num_matches = 0;
+            super::double_elimination(&mut teams, bracket_reset, |pair| {
+                num_matches += 1;
+                if (pair[0].index > pair[1].index) != (num_matches == 6) {
+                    pair.swap(0, 1);
+                }
+            });
+            let order: Vec<_> = teams.iter().map(|team| team.index).collect();
+            (order, num_matches)
+        };
+        assert_eq!(run(false), (vec![1, 0, 2, 3], 6));
+        assert_eq!(run(true), (vec![0, 1, 2, 3], 7));
+    }
+
+    #[test]
+    fn last_chance_qualifier_stops_at_the_slots() {
+        let component: Component<usize> = serde_yaml::from_str(
+            "
+type: !LastChanceQualifier {num_games_per_series: 1, slots: 2}
+teams: [0, 1, 2, 3, 4, 5, 6, 7]
+",
+        )
+        .unwrap();
+        assert!(component.validate().is_ok());
+        let index = |name| component.get_placement_index_from_placement_name(name);
+        assert_eq!(index("qualifier_1"), Some(0));
+        assert_eq!(index("qualifier_2"), Some(1));
+        assert_eq!(index("round_2_loser_1"), Some(2));
+        assert_eq!(index("round_1_loser_4"), Some(7));
+        assert_eq!(index("qualifier_3"), None);
+        assert_eq!(index("round_3_loser_1"), None);
+        // The better seed always wins.
+        let win_probabilities: Vec<Vec<f64>> = (0..8)
+            .map(|i| (0..8).map(|j| if i < j { 1.0 } else { 0.0 }).collect())
+            .collect();
+        let model = MatchModel::default().with_win_probabilities(win_probabilities);
+        let mut teams: Vec<_> = teams(8, &[]);
+        let outcome = component.run(&mut teams, &model, &mut stream_rng(0, 0));
+        assert_eq!(outcome.matches, 6);
+        let order: Vec<_> = teams.iter().map(|team| team.index).collect();
+        assert_eq!(order, vec![0, 1, 3, 2, 7, 4, 6, 5]);
+        let (p, exact) = component.favorite_win_probability(0, &mut rand::thread_rng());
+        assert!(exact && (p - 0.36).abs() < 1e-12);
+        for (slots, num_teams) in [(0, 8), (3, 8), (2, 2)] {
+            let invalid = Component {
+                r#type: ComponentType::LastChanceQualifier(super::LastChanceQualifier {
+                    num_games_per_series: 1,
+                    slots,
+                }),
+                teams: vec![0; num_teams],
+                ..component.clone()
+            };
+            assert!(invalid.validate().is_err());
+        }
+    }
+
+    #[test]
+    fn shared_upset_shift_correlates_the_matches_of_a_component() {
+        // Winning all three rounds has probability E[(0.6 - s)^3] for a
+        // shared shift s, which exceeds 0.6^3 by 3 * 0.6 * Var(s) = 0.054.
+        let title_probability = |shared_upset_shift| {
+            let bracket = ComponentType::SingleElimination(SingleElimination::new(1));
+            let component = Component {
+                shared_upset_shift,
+                ..Component::new(bracket, vec![(); 8])
+            };
+            let num_runs = 20000;
+            let num_titles = (0..num_runs)
+                .filter(|seed| {
+                    let mut teams = super::favorite_and_underdogs(8);
+                    let mut rng = stream_rng(*seed, 0);
+                    component.run(&mut teams, &MatchModel::default(), &mut rng);
+                    teams[0].is_strong()
+                })
+                .count();
+            num_titles as f64 / num_runs as f64
+        };
+        assert!((title_probability(0.0) - 0.216).abs() < 0.015);
+        assert!((title_probability(0.3) - 0.27).abs() < 0.015);
+    }
+
+    #[test]
+    fn single_table_breaks_ties_by_head_to_head() {
+        // 0 and 1 both win two games, but 1 beats 0. 2 and 3 both win
+        // one game, but 3 beats 2.
+        let beats = [(0, 2), (0, 3), (1, 0), (1, 3), (2, 1), (3, 2)];
+        let win_probabilities: Vec<Vec<f64>> = (0..4)
+            .map(|i| {
+                (0..4)
+                    .map(
+                        |j| match (beats.contains(&(i, j)), beats.contains(&(j, i))) {
+                            (true, _) => 1.0,
+                            (_, true) => 0.0,
+                            _ => 0.5,
+                        },
+                    )
+                    .collect()
+            })
+            .collect();
+        let model = MatchModel::default().with_win_probabilities(win_probabilities);
+        let table: SingleTable = serde_yaml::from_str("num_games_per_series: 1").unwrap();
+        let mut teams: Vec<_> = teams(4, &[]);
+        let mut log = MatchLog::new(&teams);
+        table.run(&mut teams, &model, &mut rand::thread_rng(), &mut log);
+        let order: Vec<_> = teams.iter().map(|team| team.index).collect();
+        assert_eq!(order, vec![1, 0, 3, 2]);
+    }
+
+    #[test]
+    fn single_elimination_produces_full_order() {
+        let bracket = SingleElimination::new(3);
+        let mut teams: Vec<_> = teams(8, &[0]);
+        let mut log = MatchLog::new(&teams);
+        bracket.run(
+            &mut teams,
+            &Default::default(),
+            &mut rand::thread_rng(),
+            &mut log,
+        );
+        let mut indices: Vec<_> = teams.iter().map(|team| team.index).collect();
+        indices.sort();
+        assert_eq!(indices, (0..8).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn identify_tied_teams() {
+        let make_teams = |indices: &[usize]| {
+            indices
+                .iter()
+                .map(|index| Team::new(*index))
                 .collect::<Vec<_>>()
         };
         let teams = make_teams(&[10, 11, 12, 13]);
-        let num_games_won = [(10, 2), (11, 2), (12, 2), (13, 2)].into_iter().collect();
+        let num_games_won = [2, 2, 2, 2];
         assert_eq!(
             super::identify_tied_teams(&teams, &num_games_won),
             vec![TiedTeams {
@@ -188,7 +3183,7 @@ mod tests {
             },]
         );
         let teams = make_teams(&[10, 12, 13, 11]);
-        let num_games_won = [(10, 3), (12, 2), (13, 2), (11, 1)].into_iter().collect();
+        let num_games_won = [3, 2, 2, 1];
         assert_eq!(
             super::identify_tied_teams(&teams, &num_games_won),
             vec![TiedTeams {
@@ -198,7 +3193,7 @@ mod tests {
             },]
         );
         let teams = make_teams(&[10, 12, 13, 11]);
-        let num_games_won = [(10, 3), (12, 3), (13, 1), (11, 1)].into_iter().collect();
+        let num_games_won = [3, 3, 1, 1];
         assert_eq!(
             super::identify_tied_teams(&teams, &num_games_won),
             vec![
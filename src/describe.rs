@@ -0,0 +1,128 @@
+use itertools::Itertools;
+
+use crate::rng::stream_rng;
+use crate::rng::TEAMS_STREAM;
+use crate::TeamIdentifier;
+use crate::Tournament;
+
+/// Number of simulations used to estimate the probabilities of components
+/// for which no closed form is available.
+const NUM_DESCRIBE_RUNS: usize = 100000;
+
+/// Prints, for each component, the probability that the favorite comes out
+/// on top when it plays against otherwise equal teams at the base edge.
+pub fn describe(tournament: &Tournament) {
+    let mut rng = stream_rng(0, TEAMS_STREAM);
+    for (name, component) in tournament.components.iter() {
+        let (p, exact) = component.favorite_win_probability(NUM_DESCRIBE_RUNS, &mut rng);
+        let expected_num_games = component
+            .r#type
+            .expected_num_games()
+            .map(|num_games| format!(", {num_games:.2} games expected"))
+            .unwrap_or_default();
+        println!(
+            "  {name} ({}, {} teams): favorite finishes first with probability {p:.3}{}{}",
+            component.r#type,
+            component.teams.len(),
+            if exact { "" } else { " (estimated)" },
+            expected_num_games
+        );
+    }
+    for names in equivalent_components(tournament) {
+        println!("  identical components: {}", names.join(", "));
+    }
+}
+
+/// Groups of at least two components that agree in everything but their
+/// teams and receive the same teams or the same placements of equivalent
+/// components, so that they only differ by their name. Placements are
+/// compared by position, so that for example `winner` and `1st` of a group
+/// stage are the same.
+pub fn equivalent_components(tournament: &Tournament) -> Vec<Vec<&str>> {
+    let names: Vec<_> = tournament.component_names().collect();
+    // The class of each component is the index of the first component it is equivalent to.
+    let mut classes: Vec<usize> = vec![];
+    let mut wirings = vec![];
+    for (index, component) in tournament.components.values().enumerate() {
+        let wiring: Vec<_> = component
+            .teams
+            .iter()
+            .map(|team| match team {
+                TeamIdentifier::Team(num) => (None, *num),
+                TeamIdentifier::FromPreviousComponent(..) => {
+                    let (source, position) = tournament
+                        .resolve_team(team)
+                        .expect("Tournaments are validated when they are parsed");
+                    (Some(classes[source - 1]), position)
+                }
+            })
+            .collect();
+        let class = tournament
+            .components
+            .values()
+            .take(index)
+            .zip(wirings.iter())
+            .position(|(other, other_wiring)| {
+                other.same_but_teams(component) && *other_wiring == wiring
+            })
+            .map(|other| classes[other])
+            .unwrap_or(index);
+        classes.push(class);
+        wirings.push(wiring);
+    }
+    (0..names.len())
+        .into_group_map_by(|index| classes[*index])
+        .into_values()
+        .filter(|group| group.len() > 1)
+        .sorted()
+        .map(|group| group.into_iter().map(|index| names[index]).collect())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::equivalent_components;
+
+    #[test]
+    fn renamed_components_are_found() {
+        let tournament = &crate::parse_tournaments(
+            "
+components:
+  group_a:
+    type: !GroupStage {num_games_per_series: 1}
+    teams: [0, 1, 2]
+  group_b:
+    type: !GroupStage {num_games_per_series: 1}
+    teams: [0, 1, 2]
+  group_with_matchups:
+    type: !GroupStage {num_games_per_series: 1}
+    teams: [0, 1, 2]
+    matchups: [{team: 0, opponent: 1, win_probability: 0.9}]
+  group_with_upsets:
+    type: !GroupStage {num_games_per_series: 1}
+    teams: [0, 1, 2]
+    shared_upset_shift: 0.1
+  group_with_penalty:
+    type: !GroupStage {num_games_per_series: 1}
+    teams: [0, 1, 2]
+    loser_route_penalty: 0.1
+  final_a:
+    type: !BestOf3
+    teams: [[1st, group_a], [2nd, group_a]]
+  final_b:
+    type: !BestOf3
+    teams: [[1st, group_b], [2nd, group_b]]
+  other_final:
+    type: !BestOf3
+    teams: [[1st, group_b], [3rd, group_b]]
+scoring:
+  [winner, final_a]: 1.0
+",
+        )
+        .unwrap()[0];
+        assert_eq!(
+            equivalent_components(tournament),
+            vec![vec!["group_a", "group_b"], vec!["final_a", "final_b"]]
+        );
+    }
+}
@@ -0,0 +1,152 @@
+use itertools::Itertools;
+
+use crate::component::Component;
+use crate::TeamIdentifier;
+use crate::Tournament;
+
+/// The differences between the components and the scoring of two
+/// tournaments, one line per difference. Lines start with `+` for
+/// additions, `-` for removals and `~` for changes from `old` to `new`.
+pub fn structural_diff(old: &Tournament, new: &Tournament) -> Vec<String> {
+    let mut lines = vec![];
+    for (name, old_component) in old.components.iter() {
+        match new.components.get(name) {
+            None => lines.push(format!(
+                "- component {}: {}",
+                name,
+                describe_component(old_component)
+            )),
+            Some(new_component) => {
+                for change in component_changes(old_component, new_component) {
+                    lines.push(format!("~ component {}: {}", name, change));
+                }
+            }
+        }
+    }
+    for (name, new_component) in new.components.iter() {
+        if !old.components.contains_key(name) {
+            lines.push(format!(
+                "+ component {}: {}",
+                name,
+                describe_component(new_component)
+            ));
+        }
+    }
+    // The scoring is not ordered, so report it sorted by placement.
+    let placements = old
+        .scoring
+        .keys()
+        .chain(new.scoring.keys())
+        .unique()
+        .sorted_by_key(|placement| placement.to_string());
+    for placement in placements {
+        match (old.scoring.get(placement), new.scoring.get(placement)) {
+            (Some(score), None) => lines.push(format!("- scoring {}: {}", placement, score)),
+            (None, Some(score)) => lines.push(format!("+ scoring {}: {}", placement, score)),
+            (Some(old_score), Some(new_score)) if old_score != new_score => lines.push(format!(
+                "~ scoring {}: {} -> {}",
+                placement, old_score, new_score
+            )),
+            _ => {}
+        }
+    }
+    lines
+}
+
+fn describe_component(component: &Component<TeamIdentifier>) -> String {
+    format!(
+        "{} with {}",
+        component.r#type,
+        describe_teams(&component.teams)
+    )
+}
+
+fn describe_teams(teams: &[TeamIdentifier]) -> String {
+    format!("[{}]", teams.iter().join(", "))
+}
+
+fn component_changes(
+    old: &Component<TeamIdentifier>,
+    new: &Component<TeamIdentifier>,
+) -> Vec<String> {
+    let mut changes = vec![];
+    if old.r#type != new.r#type {
+        changes.push(format!("type {} -> {}", old.r#type, new.r#type));
+    }
+    if old.teams != new.teams {
+        changes.push(format!(
+            "teams {} -> {}",
+            describe_teams(&old.teams),
+            describe_teams(&new.teams)
+        ));
+    }
+    if old.reseed != new.reseed {
+        changes.push(format!("reseed {} -> {}", old.reseed, new.reseed));
+    }
+    if old.matchups != new.matchups {
+        changes.push("matchups changed".into());
+    }
+    if old.shared_upset_shift != new.shared_upset_shift {
+        changes.push(format!(
+            "shared upset shift {} -> {}",
+            old.shared_upset_shift, new.shared_upset_shift
+        ));
+    }
+    if old.loser_route_penalty != new.loser_route_penalty {
+        changes.push(format!(
+            "loser route penalty {} -> {}",
+            old.loser_route_penalty, new.loser_route_penalty
+        ));
+    }
+    changes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::structural_diff;
+
+    #[test]
+    fn changes_are_reported() {
+        let tournaments = crate::parse_tournaments(
+            "
+components:
+  semi:
+    type: !BestOf1
+    teams: [0, 1]
+  final:
+    type: !BestOf3
+    teams: [[winner, semi], 2]
+scoring:
+  [winner, final]: 1.0
+  [loser, final]: 0.5
+---
+components:
+  semi:
+    type: !BestOf1
+    teams: [0, 1]
+  final:
+    type: !BestOf5
+    teams: [[winner, semi], 3]
+  consolation:
+    type: !BestOf1
+    teams: [[loser, semi], 2]
+scoring:
+  [winner, final]: 2.0
+  [winner, consolation]: 0.5
+",
+        )
+        .unwrap();
+        assert_eq!(
+            structural_diff(&tournaments[0], &tournaments[1]),
+            vec![
+                "~ component final: type BestOf3 -> BestOf5",
+                "~ component final: teams [winner of semi, team 2] -> [winner of semi, team 3]",
+                "+ component consolation: BestOf1 with [loser of semi, team 2]",
+                "- scoring loser of final: 0.5",
+                "+ scoring winner of consolation: 0.5",
+                "~ scoring winner of final: 1 -> 2",
+            ]
+        );
+        assert!(structural_diff(&tournaments[0], &tournaments[0]).is_empty());
+    }
+}
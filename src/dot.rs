@@ -0,0 +1,88 @@
+use std::io;
+use std::io::Write;
+
+use crate::TeamIdentifier;
+use crate::Tournament;
+
+fn escape(name: &str) -> String {
+    name.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+fn quote(name: &str) -> String {
+    format!("\"{}\"", escape(name))
+}
+
+/// Writes the component graph of the tournament in the Graphviz DOT
+/// format. Components are boxes, incoming teams are ellipses and each
+/// edge is labeled with the placement it routes.
+pub fn write_dot(tournament: &Tournament, title: &str, out: &mut impl Write) -> io::Result<()> {
+    writeln!(out, "digraph {} {{", quote(title))?;
+    writeln!(out, "  rankdir=LR;")?;
+    for (name, component) in tournament.components.iter() {
+        writeln!(
+            out,
+            "  {} [shape=box, label=\"{}\\n{}\"];",
+            quote(name),
+            escape(name),
+            escape(&component.r#type.to_string())
+        )?;
+    }
+    for num in tournament.get_team_numbers() {
+        writeln!(
+            out,
+            "  {} [shape=ellipse, label={}];",
+            quote(&format!("team {}", num)),
+            quote(&tournament.team_name(num))
+        )?;
+    }
+    for (name, component) in tournament.components.iter() {
+        for team in component.teams.iter() {
+            match team {
+                TeamIdentifier::Team(num) => writeln!(
+                    out,
+                    "  {} -> {};",
+                    quote(&format!("team {}", num)),
+                    quote(name)
+                )?,
+                TeamIdentifier::FromPreviousComponent(placement, source) => writeln!(
+                    out,
+                    "  {} -> {} [label={}];",
+                    quote(source),
+                    quote(name),
+                    quote(placement)
+                )?,
+            }
+        }
+    }
+    writeln!(out, "}}")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::write_dot;
+
+    #[test]
+    fn edges_are_labeled_with_placements() {
+        let tournament = serde_yaml::from_str(
+            "
+components:
+  semi:
+    type: !BestOf3
+    teams: [0, 1]
+  final:
+    type: !BestOf3
+    teams: [[winner, semi], 2]
+scoring:
+  [winner, final]: 1.0
+",
+        )
+        .unwrap();
+        let mut out = vec![];
+        write_dot(&tournament, "test", &mut out).unwrap();
+        let dot = String::from_utf8(out).unwrap();
+        assert!(dot.starts_with("digraph \"test\" {"));
+        assert!(dot.contains("\"team 0\" -> \"semi\";"));
+        assert!(dot.contains("\"team 2\" -> \"final\";"));
+        assert!(dot.contains("\"semi\" -> \"final\" [label=\"winner\"];"));
+    }
+}
@@ -0,0 +1,129 @@
+//! Snake drafts of a ranking into groups: the first team goes to the first
+//! group, the second team to the second group and so on, then the order is
+//! reversed for the next round of picks. A component may take the teams of
+//! one such group instead of listing them by hand:
+//!
+//! ```yaml
+//! components:
+//!   group_b:
+//!     type: !GroupStage {num_games_per_series: 1}
+//!     teams: !SnakeDraft {ranking: [0, 1, 2, 3, 4, 5, 6, 7], groups: 2, group: 1}
+//! ```
+//!
+//! Here, the teams are replaced by `[1, 2, 5, 6]` before the document is
+//! deserialized into a [`crate::Tournament`].
+
+use serde::Deserialize;
+use serde_yaml::Value;
+
+use crate::component::split_into_groups;
+use crate::component::Split;
+use crate::error::TournamentError;
+
+const SNAKE_DRAFT_TAG: &str = "SnakeDraft";
+
+#[derive(Deserialize)]
+struct SnakeDraft {
+    ranking: Vec<Value>,
+    groups: usize,
+    /// The index of the group whose teams are used, starting from 0.
+    group: usize,
+}
+
+/// Distributes the ranked teams over `num_groups` groups by snake draft.
+/// Each group is in the order in which its teams were picked.
+pub fn snake_draft<T: Clone>(ranking: &[T], num_groups: usize) -> Vec<Vec<T>> {
+    split_into_groups(ranking.len(), num_groups, Split::Snake)
+        .into_iter()
+        .map(|seeds| {
+            seeds
+                .into_iter()
+                .map(|seed| ranking[seed].clone())
+                .collect()
+        })
+        .collect()
+}
+
+/// Replaces the teams of every component that are given as a snake draft
+/// by the teams of the drafted group.
+pub fn expand_snake_drafts(mut document: Value) -> Result<Value, TournamentError> {
+    let Some(Value::Mapping(components)) = document.get_mut("components") else {
+        return Ok(document);
+    };
+    for (name, component) in components.iter_mut() {
+        let Some(teams) = component.get_mut("teams") else {
+            continue;
+        };
+        let Value::Tagged(tagged) = teams else {
+            continue;
+        };
+        if tagged.tag != SNAKE_DRAFT_TAG {
+            continue;
+        }
+        let invalid = |reason: String| TournamentError::InvalidComponent {
+            component: name.as_str().unwrap_or_default().to_owned(),
+            reason,
+        };
+        let draft: SnakeDraft = serde_yaml::from_value(tagged.value.clone())
+            .map_err(|e| invalid(format!("invalid snake draft: {}", e)))?;
+        if draft.group >= draft.groups {
+            return Err(invalid(format!(
+                "snake draft group {} does not exist with {} groups",
+                draft.group, draft.groups
+            )));
+        }
+        let group = snake_draft(&draft.ranking, draft.groups).swap_remove(draft.group);
+        *teams = Value::Sequence(group);
+    }
+    Ok(document)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::snake_draft;
+
+    #[test]
+    fn snake_draft_into_three_groups_of_four() {
+        let ranking: Vec<_> = (1..=12).collect();
+        assert_eq!(
+            snake_draft(&ranking, 3),
+            vec![vec![1, 6, 7, 12], vec![2, 5, 8, 11], vec![3, 4, 9, 10]]
+        );
+    }
+
+    #[test]
+    fn teams_are_drafted_while_parsing() {
+        let tournaments = crate::parse_tournaments(
+            "
+components:
+  group_a:
+    type: !GroupStage {num_games_per_series: 1}
+    teams: !SnakeDraft {ranking: [0, 1, 2, 3, 4, 5, 6, 7], groups: 2, group: 0}
+  group_b:
+    type: !GroupStage {num_games_per_series: 1}
+    teams: !SnakeDraft {ranking: [0, 1, 2, 3, 4, 5, 6, 7], groups: 2, group: 1}
+scoring:
+  [1st, group_a]: 1.0
+  [1st, group_b]: 1.0
+",
+        )
+        .unwrap();
+        let teams = |name| {
+            tournaments[0]
+                .component(name)
+                .unwrap()
+                .teams
+                .iter()
+                .map(|team| team.to_string())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            teams("group_a"),
+            vec!["team 0", "team 3", "team 4", "team 7"]
+        );
+        assert_eq!(
+            teams("group_b"),
+            vec!["team 1", "team 2", "team 5", "team 6"]
+        );
+    }
+}
@@ -0,0 +1,91 @@
+use std::fmt;
+
+use crate::ComponentName;
+
+/// A problem with a tournament file or definition.
+#[derive(Debug, Clone, PartialEq)]
+pub enum TournamentError {
+    /// A component has invalid parameters.
+    InvalidComponent {
+        component: ComponentName,
+        reason: String,
+    },
+    /// The noise, ratings or win probabilities are invalid or do not fit the
+    /// teams.
+    InvalidMatchups(String),
+    /// The number of runs is zero.
+    InvalidNumRuns,
+    /// No placement is scored.
+    EmptyScoring,
+    /// The team names do not fit the teams.
+    InvalidTeamNames(String),
+    /// A component refers to a template that is not defined.
+    UnknownTemplate {
+        component: ComponentName,
+        template: String,
+    },
+    /// The templates or a reference to one are malformed.
+    InvalidTemplate(String),
+    /// A ratings file is malformed or does not fit the teams.
+    InvalidRatings(String),
+    /// A file cannot be read.
+    Io { file: String, reason: String },
+    /// A document is not valid YAML or does not describe a tournament.
+    Parse(String),
+    /// A team refers to a component that is not defined.
+    UnknownComponent(ComponentName),
+    /// A team refers to a placement that its component does not have.
+    InvalidPlacement {
+        component: ComponentName,
+        placement: String,
+    },
+    /// A placement is used more than once or before its component has run.
+    InvalidRouting(String),
+    /// Several of the problems above, e.g. all invalid references of a tournament.
+    Multiple(Vec<TournamentError>),
+}
+
+impl fmt::Display for TournamentError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TournamentError::InvalidComponent { component, reason } => {
+                write!(f, "Invalid component {}: {}", component, reason)
+            }
+            TournamentError::InvalidMatchups(reason) => write!(f, "Invalid matchups: {}", reason),
+            TournamentError::InvalidNumRuns => write!(f, "The number of runs must be positive"),
+            TournamentError::EmptyScoring => write!(f, "The scoring must score a placement"),
+            TournamentError::InvalidTeamNames(reason) => {
+                write!(f, "Invalid team names: {}", reason)
+            }
+            TournamentError::UnknownTemplate {
+                component,
+                template,
+            } => write!(
+                f,
+                "Component {} refers to unknown template {}",
+                component, template
+            ),
+            TournamentError::InvalidTemplate(reason) => write!(f, "Invalid template: {}", reason),
+            TournamentError::InvalidRatings(reason) => write!(f, "Invalid ratings: {}", reason),
+            TournamentError::Io { file, reason } => write!(f, "Cannot read {}: {}", file, reason),
+            TournamentError::Parse(reason) => write!(f, "Invalid tournament: {}", reason),
+            TournamentError::UnknownComponent(component) => {
+                write!(f, "Unknown component: {}", component)
+            }
+            TournamentError::InvalidPlacement {
+                component,
+                placement,
+            } => write!(f, "Component {} has no placement {}", component, placement),
+            TournamentError::InvalidRouting(reason) => write!(f, "Invalid routing: {}", reason),
+            TournamentError::Multiple(errors) => {
+                write!(f, "{} problems:", errors.len())?;
+                for error in errors {
+                    write!(f, "\n  {}", error)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl std::error::Error for TournamentError {}
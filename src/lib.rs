@@ -0,0 +1,1315 @@
+pub mod component;
+pub mod describe;
+pub mod diff;
+pub mod dot;
+pub mod draft;
+pub mod error;
+pub mod math;
+pub mod model;
+pub mod plot;
+pub mod ratings;
+pub mod report;
+pub mod rng;
+pub mod runner;
+pub mod simulation;
+#[cfg(feature = "sqlite")]
+pub mod sqlite;
+pub mod stats;
+pub mod template;
+
+use std::collections::HashMap;
+use std::fmt;
+use std::hash::Hash;
+
+use itertools::Itertools;
+use linked_hash_map::LinkedHashMap;
+use ordered_float::OrderedFloat;
+use rand::Rng;
+use serde::Deserialize;
+use serde::Serialize;
+
+use crate::component::Component;
+use crate::error::TournamentError;
+use crate::math::elo_rating_difference;
+use crate::math::elo_win_probability;
+use crate::model::NoiseModel;
+use crate::model::ProbabilityPolicy;
+use crate::model::RatingDistribution;
+use crate::ratings::RatingsTable;
+use crate::runner::Runner;
+
+/// A strong team wins a game against a team with the base rating with
+/// probability `0.5 + STRONG_TEAM_ADVANTAGE`.
+pub const STRONG_TEAM_ADVANTAGE: f64 = 0.1;
+/// The Elo rating of the teams that are not strong.
+pub const BASE_RATING: f64 = 1500.0;
+/// The number of runs per team in the check that every team can win.
+pub const SANITY_CHECK_RUNS: usize = 10000;
+
+pub type Score = f64;
+pub type ComponentName = String;
+pub type PlacementName = String;
+
+#[derive(Deserialize, Serialize, Debug, Hash, PartialEq, Eq, Clone)]
+struct Placement {
+    component: usize,
+    position: usize,
+}
+
+#[derive(Deserialize, Serialize, Debug, Hash, PartialEq, Eq, Clone)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(untagged)]
+pub enum TeamIdentifier {
+    Team(usize),
+    FromPreviousComponent(PlacementName, ComponentName),
+}
+
+impl fmt::Display for TeamIdentifier {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TeamIdentifier::Team(num) => write!(f, "team {}", num),
+            TeamIdentifier::FromPreviousComponent(placement, component) => {
+                write!(f, "{} of {}", placement, component)
+            }
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Team {
+    index: usize,
+    /// The Elo rating, which decides the matches of the team.
+    rating: f64,
+    /// The rating that the team would have if it was not strong, i.e. the
+    /// base rating or the rating of the team in the tournament.
+    own_rating: f64,
+}
+
+impl Team {
+    /// A team with the base rating.
+    pub fn new(index: usize) -> Self {
+        Self::with_strength(index, false)
+    }
+
+    /// A team with the base rating, which a strong team exceeds by the
+    /// difference at which it wins a game with probability
+    /// `0.5 + STRONG_TEAM_ADVANTAGE`.
+    pub fn with_strength(index: usize, strong: bool) -> Self {
+        let offset = if strong {
+            strong_team_rating_difference()
+        } else {
+            0.0
+        };
+        Self {
+            index,
+            rating: BASE_RATING + offset,
+            own_rating: BASE_RATING,
+        }
+    }
+
+    /// Gives the team its own rating, which a strong team keeps exceeding.
+    pub fn with_rating(self, rating: f64) -> Self {
+        Self {
+            rating: rating + self.strong_offset(),
+            own_rating: rating,
+            ..self
+        }
+    }
+
+    /// How far the rating of the team exceeds its own rating.
+    fn strong_offset(&self) -> f64 {
+        self.rating - self.own_rating
+    }
+
+    /// Whether the team is rated above its own rating, which makes it one
+    /// of the strong teams whose advantage is measured.
+    pub fn is_strong(&self) -> bool {
+        self.rating > self.own_rating
+    }
+
+    pub fn probability_to_win_against(&self, other: &Self) -> f64 {
+        elo_win_probability(self.rating, other.rating)
+    }
+}
+
+/// The rating difference between a strong team and a team with the base
+/// rating.
+pub fn strong_team_rating_difference() -> f64 {
+    elo_rating_difference(0.5 + STRONG_TEAM_ADVANTAGE)
+}
+
+/// `n` teams, of which the ones with the given indices are strong.
+#[cfg(test)]
+pub(crate) fn teams(n: usize, strong: &[usize]) -> Vec<Team> {
+    (0..n)
+        .map(|index| Team::with_strength(index, strong.contains(&index)))
+        .collect()
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct Tournament {
+    /// Used to label the tournament in files with multiple documents.
+    #[serde(default)]
+    name: Option<String>,
+    // Linked hash map is used here to preserve order of the components during
+    // deserialization.
+    #[cfg_attr(
+        feature = "schema",
+        schemars(with = "std::collections::BTreeMap<String, Component<TeamIdentifier>>")
+    )]
+    components: LinkedHashMap<ComponentName, Component<TeamIdentifier>>,
+    // The keys are placements, which YAML allows as keys but JSON does not.
+    #[cfg_attr(feature = "schema", schemars(with = "HashMap<String, Score>"))]
+    #[serde(serialize_with = "serialize_scoring")]
+    scoring: HashMap<TeamIdentifier, Score>,
+    #[serde(default)]
+    noise: NoiseModel,
+    #[serde(default)]
+    probability_policy: ProbabilityPolicy,
+    /// Skip the check that every team can win, for formats that are
+    /// intentionally unfair.
+    #[serde(default)]
+    skip_sanity_check: bool,
+    /// Named teams with their Elo ratings, in the order of their team
+    /// numbers. If given, the strong teams are strong on top of these.
+    #[serde(default)]
+    #[cfg_attr(
+        feature = "schema",
+        schemars(with = "std::collections::BTreeMap<String, f64>")
+    )]
+    ratings: LinkedHashMap<String, f64>,
+    /// Draw the Elo ratings of the teams from this distribution in every
+    /// run instead, with team 0 as the best team. Cannot be combined with
+    /// `ratings`.
+    #[serde(default)]
+    rating_distribution: Option<RatingDistribution>,
+    /// The probability that team `i` wins a game against team `j` at
+    /// `[i][j]`. If given, this decides the matches, which allows
+    /// non-transitive matchups that no single strength can represent.
+    #[serde(default)]
+    win_probabilities: Vec<Vec<f64>>,
+    /// The names of the teams by team number, used in the output instead
+    /// of the team numbers.
+    #[serde(default)]
+    team_names: Vec<String>,
+    /// The number of runs to simulate unless it is given on the command line.
+    #[serde(default)]
+    num_runs: Option<usize>,
+    /// The currency in which the scores are paid out, for `--payout`.
+    #[serde(default)]
+    currency: Option<String>,
+}
+
+/// Serializes the scores from the highest to the lowest, so that the
+/// output does not depend on the order of the hash map.
+fn serialize_scoring<S: serde::Serializer>(
+    scoring: &HashMap<TeamIdentifier, Score>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    serializer.collect_map(scoring.iter().sorted_by_key(|(team, score)| {
+        let name = match team {
+            TeamIdentifier::Team(num) => (String::new(), String::new(), *num),
+            TeamIdentifier::FromPreviousComponent(placement, component) => {
+                (component.clone(), placement.clone(), 0)
+            }
+        };
+        (std::cmp::Reverse(OrderedFloat(**score)), name)
+    }))
+}
+
+impl fmt::Display for Tournament {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} teams, {} components ({})",
+            self.num_teams(),
+            self.components.len(),
+            self.components.keys().join(", ")
+        )
+    }
+}
+
+impl Tournament {
+    fn get_team_numbers(&self) -> impl Iterator<Item = usize> + '_ {
+        self.components.iter().flat_map(|(_, component)| {
+            component.teams.iter().filter_map(|team| match team {
+                TeamIdentifier::Team(num) => Some(*num),
+                _ => None,
+            })
+        })
+    }
+
+    pub fn num_teams(&self) -> usize {
+        self.get_team_numbers().count()
+    }
+
+    /// The number of components, not counting the incoming teams.
+    pub fn num_components(&self) -> usize {
+        self.components.len()
+    }
+
+    /// The names of all components in the order in which they are run.
+    pub fn component_names(&self) -> impl Iterator<Item = &str> {
+        self.components.keys().map(|name| name.as_str())
+    }
+
+    /// The component with the given name, if any.
+    pub fn component(&self, name: &str) -> Option<&Component<TeamIdentifier>> {
+        self.components.get(name)
+    }
+
+    /// Checks the parameters of every component.
+    pub fn validate(&self) -> Result<(), TournamentError> {
+        for (name, component) in self.components.iter() {
+            component
+                .validate()
+                .and_then(|()| self.validate_matchup_overrides(component))
+                .map_err(|reason| TournamentError::InvalidComponent {
+                    component: name.clone(),
+                    reason,
+                })?;
+        }
+        self.validate_matchups()
+            .map_err(TournamentError::InvalidMatchups)?;
+        if self.num_runs == Some(0) {
+            return Err(TournamentError::InvalidNumRuns);
+        }
+        if self.scoring.is_empty() {
+            return Err(TournamentError::EmptyScoring);
+        }
+        let mut errors = self.reference_errors();
+        match errors.len() {
+            0 => {}
+            1 => return Err(errors.remove(0)),
+            _ => return Err(TournamentError::Multiple(errors)),
+        }
+        let num_teams = self.num_teams();
+        if !self.team_names.is_empty() && self.team_names.len() != num_teams {
+            return Err(TournamentError::InvalidTeamNames(format!(
+                "Expected {} team names, got {}",
+                num_teams,
+                self.team_names.len()
+            )));
+        }
+        Ok(())
+    }
+
+    /// The problems with every team of a component and every scoring key
+    /// that refers to a component or placement that does not exist, each
+    /// reported once. The problems with the scoring keys are sorted, so
+    /// that they do not depend on the order of the hash map.
+    pub fn reference_errors(&self) -> Vec<TournamentError> {
+        let component_errors = self
+            .components
+            .values()
+            .flat_map(|component| component.teams.iter())
+            .filter_map(|team| self.resolve_team(team).err());
+        let scoring_errors = self
+            .scoring
+            .keys()
+            .filter_map(|team| self.resolve_team(team).err())
+            .sorted_by_key(|error| error.to_string());
+        component_errors
+            .chain(scoring_errors)
+            .unique_by(|error| error.to_string())
+            .collect()
+    }
+
+    /// The index of the component that a team comes from, counting the
+    /// incoming teams as component 0, and its position in the result of
+    /// that component.
+    pub(crate) fn resolve_team(
+        &self,
+        team: &TeamIdentifier,
+    ) -> Result<(usize, usize), TournamentError> {
+        match team {
+            TeamIdentifier::Team(num) => Ok((0, *num)),
+            TeamIdentifier::FromPreviousComponent(placement, name) => {
+                let (index, component) = self
+                    .components
+                    .iter()
+                    .enumerate()
+                    .find(|(_, (other, _))| *other == name)
+                    .map(|(index, (_, component))| (index, component))
+                    .ok_or_else(|| TournamentError::UnknownComponent(name.clone()))?;
+                let position = component
+                    .get_placement_index_from_placement_name(placement)
+                    .ok_or_else(|| TournamentError::InvalidPlacement {
+                        component: name.clone(),
+                        placement: placement.clone(),
+                    })?;
+                Ok((index + 1, position))
+            }
+        }
+    }
+
+    /// Checks that the teams of the matchup overrides of a component can
+    /// play in it: they have to be listed if the component only receives
+    /// teams directly, and exist otherwise.
+    fn validate_matchup_overrides(
+        &self,
+        component: &Component<TeamIdentifier>,
+    ) -> Result<(), String> {
+        let listed: Option<Vec<usize>> = component
+            .teams
+            .iter()
+            .map(|team| match team {
+                TeamIdentifier::Team(num) => Some(*num),
+                TeamIdentifier::FromPreviousComponent(..) => None,
+            })
+            .collect();
+        let num_teams = self.num_teams();
+        for matchup in component.matchups.iter() {
+            for team in [matchup.team, matchup.opponent] {
+                let plays = match &listed {
+                    Some(listed) => listed.contains(&team),
+                    None => team < num_teams,
+                };
+                if !plays {
+                    return Err(format!(
+                        "Matchup refers to team {}, which cannot play in it",
+                        team
+                    ));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn validate_matchups(&self) -> Result<(), String> {
+        let num_teams = self.num_teams();
+        self.noise.validate()?;
+        if let Some(distribution) = self.rating_distribution {
+            if !self.ratings.is_empty() {
+                return Err("Ratings and a rating distribution cannot be combined".into());
+            }
+            distribution.validate()?;
+        }
+        if !self.ratings.is_empty() && self.ratings.len() != num_teams {
+            return Err(format!(
+                "Expected {} ratings, got {}",
+                num_teams,
+                self.ratings.len()
+            ));
+        }
+        if self.win_probabilities.is_empty() {
+            return Ok(());
+        }
+        let p = &self.win_probabilities;
+        if p.len() != num_teams || p.iter().any(|row| row.len() != num_teams) {
+            return Err(format!(
+                "Expected a {}x{} matrix of win probabilities",
+                num_teams, num_teams
+            ));
+        }
+        for (i, j) in (0..num_teams).tuple_combinations() {
+            if !(0.0..=1.0).contains(&p[i][j]) || (p[i][j] + p[j][i] - 1.0).abs() > 1e-9 {
+                return Err(format!(
+                    "Win probabilities of teams {} and {} must be in [0, 1] and add up to 1",
+                    i, j
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// The name of a team for display: its entry in `team_names`, else the
+    /// name of its rating, else its number.
+    pub fn team_name(&self, team: usize) -> String {
+        self.team_names
+            .get(team)
+            .or_else(|| self.ratings.keys().nth(team))
+            .cloned()
+            .unwrap_or_else(|| format!("team {}", team))
+    }
+
+    /// The names of all teams, by team number.
+    pub fn team_names(&self) -> Vec<String> {
+        (0..self.num_teams())
+            .map(|team| self.team_name(team))
+            .collect()
+    }
+
+    /// Replaces the ratings, team names and win probabilities by those of
+    /// a ratings file, which must have a row for every team.
+    pub fn with_ratings_table(self, table: &RatingsTable) -> Result<Self, TournamentError> {
+        table.validate(self.num_teams())?;
+        let tournament = Self {
+            ratings: table
+                .rows
+                .iter()
+                .map(|row| (row.name.clone(), row.rating))
+                .collect(),
+            team_names: table.rows.iter().map(|row| row.name.clone()).collect(),
+            win_probabilities: table.win_probabilities(),
+            ..self
+        };
+        if tournament.ratings.len() != table.rows.len() {
+            return Err(TournamentError::InvalidRatings(
+                "the names of the teams must be distinct".into(),
+            ));
+        }
+        tournament.validate()?;
+        Ok(tournament)
+    }
+
+    /// The Elo ratings of the teams, by team number. Empty if the
+    /// tournament has no ratings.
+    pub fn ratings(&self) -> Vec<f64> {
+        self.ratings.values().copied().collect()
+    }
+
+    pub fn has_ratings(&self) -> bool {
+        !self.ratings.is_empty()
+    }
+
+    /// Whether the teams have ratings of their own, fixed or drawn in every
+    /// run, on top of which the strong teams are strong.
+    pub fn is_rated(&self) -> bool {
+        self.has_ratings() || self.rating_distribution.is_some()
+    }
+
+    /// Whether the matches are decided by ratings or win probabilities
+    /// instead of only by which teams are strong.
+    pub fn has_matchups(&self) -> bool {
+        self.is_rated() || !self.win_probabilities.is_empty()
+    }
+
+    /// The sum of the scores of all placements.
+    pub fn total_score(&self) -> Score {
+        self.scoring.values().sum()
+    }
+
+    pub fn currency(&self) -> Option<&str> {
+        self.currency.as_deref()
+    }
+
+    pub fn num_runs(&self) -> Option<usize> {
+        self.num_runs
+    }
+
+    pub fn skip_sanity_check(&self) -> bool {
+        self.skip_sanity_check
+    }
+
+    /// Whether no placement has a positive score, as in a pure relegation
+    /// format, so that the best a team can do is to avoid all penalties.
+    pub fn has_only_penalties(&self) -> bool {
+        self.scoring.values().all(|score| *score <= 0.0)
+    }
+
+    /// Checks that every team can take a placement with the highest score
+    /// in at least one of `num_runs` runs in which it is the strong team.
+    /// If several placements share the highest score, any of them counts.
+    /// If the tournament has only penalties, checks that every team can
+    /// avoid all penalties instead. The ratings of the teams are ignored,
+    /// since the check is about the format. Returns the teams that cannot,
+    /// or an error if the tournament cannot be simulated, e.g. because a
+    /// placement is used twice.
+    pub fn check_all_teams_can_win(
+        &self,
+        num_runs: usize,
+        rng: &mut impl Rng,
+    ) -> Result<Vec<usize>, TournamentError> {
+        let only_penalties = self.has_only_penalties();
+        let mut tournament = self.clone();
+        tournament.ratings.clear();
+        tournament.rating_distribution = None;
+        if !only_penalties {
+            let best_score = self
+                .scoring
+                .values()
+                .copied()
+                .max_by_key(|score| OrderedFloat(*score))
+                .unwrap();
+            tournament.scoring = self
+                .scoring
+                .iter()
+                .filter(|(_, score)| **score == best_score)
+                .map(|(placement, _)| (placement.clone(), 1.0))
+                .collect();
+        }
+        let num_teams = self.num_teams();
+        let runner = Runner::new(tournament)?;
+        let failing: Vec<_> = (0..num_teams)
+            .filter(|strong_team| {
+                !(0..num_runs).any(|_| {
+                    let teams = (0..num_teams)
+                        .map(|index| Team::with_strength(index, index == *strong_team))
+                        .collect();
+                    let mut runner = runner.clone();
+                    let result = runner.get_score_result(teams, rng.gen());
+                    if only_penalties {
+                        result.strong_team >= 0.0
+                    } else {
+                        result.strong_team > 0.0
+                    }
+                })
+            })
+            .collect();
+        Ok(failing)
+    }
+
+    /// Whether some placements are penalized, e.g. by relegation.
+    pub fn has_penalties(&self) -> bool {
+        self.scoring.values().any(|score| *score < 0.0)
+    }
+}
+
+#[derive(Default, Debug, PartialEq)]
+pub struct ScoreResult {
+    /// The average score of the strong teams.
+    strong_team: Score,
+    /// The average score of the other teams.
+    other_teams: Score,
+    all_teams: Score,
+    /// The rank of the best strong team when all teams are ordered by score,
+    /// starting from 1, with teams of equal score sharing the best rank.
+    strong_team_rank: usize,
+    /// The score of each seed, i.e. of the team in each incoming position.
+    seed_scores: Vec<Score>,
+}
+
+/// The JSON Schema of tournament files.
+#[cfg(feature = "schema")]
+pub fn schema() -> String {
+    serde_json::to_string_pretty(&schemars::schema_for!(Tournament)).unwrap()
+}
+
+/// The tournament as YAML that parses back into the same tournament, with
+/// every field given explicitly and templates and snake drafts expanded.
+pub fn to_yaml(tournament: &Tournament) -> String {
+    serde_yaml::to_string(tournament).expect("Tournaments can always be serialized")
+}
+
+/// Reads all documents (separated by `---`) of a tournament file.
+pub fn read_tournament(fname: &str) -> Result<Vec<Tournament>, TournamentError> {
+    read_labeled_tournaments(fname)?
+        .map(|labeled| labeled.map(|(_, tournament)| tournament))
+        .collect()
+}
+
+pub fn parse_tournaments(contents: &str) -> Result<Vec<Tournament>, TournamentError> {
+    Documents::new(contents.as_bytes())
+        .map(|document| {
+            let (offset, document) = document.map_err(|e| TournamentError::Parse(e.to_string()))?;
+            parse_document(&document, offset)
+        })
+        .collect()
+}
+
+fn io_error(fname: &str, error: std::io::Error) -> TournamentError {
+    TournamentError::Io {
+        file: fname.to_owned(),
+        reason: error.to_string(),
+    }
+}
+
+/// Deserializes a single document, which follows `offset` lines of its
+/// stream, expanding its templates and snake drafts, and validates it.
+fn parse_document(document: &str, offset: usize) -> Result<Tournament, TournamentError> {
+    let parse_error = |e: serde_yaml::Error| TournamentError::Parse(e.to_string());
+    let document = serde_yaml::from_str(document)
+        .map_err(|e| TournamentError::Parse(shift_lines(&e.to_string(), offset)))?;
+    let document = template::expand_templates(document).and_then(draft::expand_snake_drafts)?;
+    let tournament = Tournament::deserialize(document).map_err(parse_error)?;
+    tournament.validate()?;
+    Ok(tournament)
+}
+
+/// The message of a YAML error with the line numbers of its locations moved
+/// down by `offset` lines, from those of the document to those of the stream.
+fn shift_lines(message: &str, offset: usize) -> String {
+    let mut shifted = String::new();
+    let mut rest = message;
+    while let Some(start) = rest.find("line ") {
+        let (before, after) = rest.split_at(start + "line ".len());
+        shifted.push_str(before);
+        let end = after
+            .find(|c: char| !c.is_ascii_digit())
+            .unwrap_or(after.len());
+        let (digits, after) = after.split_at(end);
+        match digits.parse::<usize>() {
+            Ok(line) if after.starts_with(" column ") => {
+                shifted.push_str(&(line + offset).to_string())
+            }
+            _ => shifted.push_str(digits),
+        }
+        rest = after;
+    }
+    shifted.push_str(rest);
+    shifted
+}
+
+/// The documents of a YAML stream, read one at a time by splitting the
+/// stream at the lines that start with the document marker `---`. Like
+/// serde_yaml, text before the first marker is only a document if it is
+/// more than comments, while every marker starts a document. Each document
+/// comes with the number of lines of the stream before it, so that errors
+/// can refer to the lines of the stream.
+struct Documents<R> {
+    lines: std::io::Lines<R>,
+    /// The number of lines read so far.
+    num_lines: usize,
+    /// The rest of the marker line of the next document, once it is read.
+    next: Option<String>,
+    started: bool,
+}
+
+impl<R: std::io::BufRead> Documents<R> {
+    fn new(reader: R) -> Self {
+        Self {
+            lines: reader.lines(),
+            num_lines: 0,
+            next: None,
+            started: false,
+        }
+    }
+
+    /// Whether another document follows the ones returned so far.
+    fn has_more(&self) -> bool {
+        self.next.is_some()
+    }
+}
+
+impl<R: std::io::BufRead> Iterator for Documents<R> {
+    type Item = std::io::Result<(usize, String)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let first = !self.started;
+            self.started = true;
+            let offset = self.num_lines.saturating_sub(1);
+            let mut document = match self.next.take() {
+                Some(start) => start + "\n",
+                None if first => String::new(),
+                None => return None,
+            };
+            for line in self.lines.by_ref() {
+                self.num_lines += 1;
+                let line = match line {
+                    Ok(line) => line,
+                    Err(e) => return Some(Err(e)),
+                };
+                if let Some(rest) = document_start(&line) {
+                    self.next = Some(rest.to_owned());
+                    break;
+                }
+                if document_start_or_end(&line, "...").is_none() {
+                    document.push_str(&line);
+                    document.push('\n');
+                }
+            }
+            let blank = document.lines().all(|line| {
+                let line = line.trim_start();
+                line.is_empty() || line.starts_with('#') || line.starts_with('%')
+            });
+            if !(first && blank && self.has_more()) {
+                return Some(Ok((offset, document)));
+            }
+        }
+    }
+}
+
+/// The rest of the line if it starts a document.
+fn document_start(line: &str) -> Option<&str> {
+    document_start_or_end(line, "---")
+}
+
+/// The rest of the line if it starts with the given marker, followed by
+/// whitespace or the end of the line.
+fn document_start_or_end<'a>(line: &'a str, marker: &str) -> Option<&'a str> {
+    let rest = line.strip_prefix(marker)?;
+    (rest.is_empty() || rest.starts_with([' ', '\t'])).then_some(rest)
+}
+
+/// The tournaments of a file, labeled by the file name and, for files with
+/// multiple documents, by the name of the tournament or its document index.
+/// The documents are read and parsed one at a time while iterating, so that
+/// only the document that is currently simulated is kept in memory.
+pub fn read_labeled_tournaments(
+    fname: &str,
+) -> Result<impl Iterator<Item = Result<(String, Tournament), TournamentError>>, TournamentError> {
+    let file = std::fs::File::open(fname).map_err(|e| io_error(fname, e))?;
+    let fname = fname.to_owned();
+    let mut documents = Documents::new(std::io::BufReader::new(file));
+    let mut index = 0;
+    Ok(std::iter::from_fn(move || {
+        let (offset, document) = match documents.next()? {
+            Ok(document) => document,
+            Err(e) => return Some(Err(io_error(&fname, e))),
+        };
+        let single_document = index == 0 && !documents.has_more();
+        let labeled = parse_document(&document, offset).map(|tournament| {
+            let label = match (&tournament.name, single_document) {
+                (Some(name), _) => format!("{fname}#{name}"),
+                (None, true) => fname.clone(),
+                (None, false) => format!("{fname}#{index}"),
+            };
+            (label, tournament)
+        });
+        index += 1;
+        Some(labeled)
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Team;
+    use super::TeamIdentifier;
+    use crate::error::TournamentError;
+    use crate::ratings::RatingsTable;
+
+    #[test]
+    fn strong_teams_are_even_against_each_other() {
+        let (strong, regular) = (Team::with_strength(0, true), Team::new(1));
+        assert!(strong.is_strong() && !regular.is_strong());
+        assert_eq!(strong.probability_to_win_against(&strong), 0.5);
+        assert_eq!(regular.probability_to_win_against(&regular), 0.5);
+        let p = strong.probability_to_win_against(&regular);
+        assert!((p - 0.5 - crate::STRONG_TEAM_ADVANTAGE).abs() < 1e-12);
+    }
+
+    #[test]
+    fn multiple_documents() {
+        let tournaments = super::parse_tournaments(
+            "
+name: first
+components:
+  final:
+    type: !BestOf1
+    teams: [0, 1]
+scoring:
+  [winner, final]: 1.0
+---
+components:
+  final:
+    type: !GroupStage {num_games_per_series: 1}
+    teams: [0, 1, 2]
+scoring:
+  [1st, final]: 1.0
+",
+        )
+        .unwrap();
+        assert_eq!(tournaments.len(), 2);
+        assert_eq!(tournaments[0].name.as_deref(), Some("first"));
+        assert_eq!(tournaments[1].name, None);
+        assert_eq!(tournaments[1].num_teams(), 3);
+        assert!(!tournaments[1].skip_sanity_check());
+    }
+
+    #[test]
+    fn errors_refer_to_the_lines_of_the_stream() {
+        let error = super::parse_tournaments(
+            "
+components:
+  final:
+    type: !BestOf1
+    teams: [0, 1]
+scoring:
+  [winner, final]: 1.0
+---
+components: [
+",
+        )
+        .unwrap_err();
+        assert!(error.to_string().contains("line 10 column"), "{error}");
+    }
+
+    #[test]
+    fn team_identifiers_serialize_to_their_shorthand() {
+        for shorthand in ["3", "[winner, final]"] {
+            let team: TeamIdentifier = serde_yaml::from_str(shorthand).unwrap();
+            assert_eq!(
+                serde_yaml::to_value(&team).unwrap(),
+                serde_yaml::from_str::<serde_yaml::Value>(shorthand).unwrap()
+            );
+        }
+    }
+
+    #[test]
+    fn unreadable_tournaments_are_errors() {
+        assert!(matches!(
+            super::read_tournament("does_not_exist.yml"),
+            Err(TournamentError::Io { .. })
+        ));
+        assert!(matches!(
+            super::parse_tournaments("components: [0, 1"),
+            Err(TournamentError::Parse(_))
+        ));
+        assert_eq!(
+            super::parse_tournaments(
+                "
+components:
+  final:
+    type: !BestOf1
+    teams: [0, 1]
+scoring:
+  [3rd, final]: 1.0
+"
+            )
+            .unwrap_err(),
+            TournamentError::InvalidPlacement {
+                component: "final".into(),
+                placement: "3rd".into(),
+            }
+        );
+    }
+
+    #[test]
+    fn sanity_checks_report_invalid_routing() {
+        let tournament = &super::parse_tournaments(
+            "
+components:
+  semi:
+    type: !BestOf1
+    teams: [0, 1]
+  final:
+    type: !BestOf1
+    teams: [[winner, semi], [winner, semi]]
+scoring:
+  [winner, final]: 1.0
+",
+        )
+        .unwrap()[0];
+        assert!(matches!(
+            tournament.check_all_teams_can_win(100, &mut rand::thread_rng()),
+            Err(TournamentError::InvalidRouting(_))
+        ));
+    }
+
+    #[test]
+    fn all_invalid_references_are_reported() {
+        let error = super::parse_tournaments(
+            "
+components:
+  semi:
+    type: !BestOf1
+    teams: [0, 1]
+  final:
+    type: !BestOf1
+    teams: [[winner, semi], [winner, quarter]]
+scoring:
+  [winner, final]: 1.0
+  [3rd, final]: 0.5
+  [3rd, semi]: 0.5
+",
+        )
+        .unwrap_err();
+        assert_eq!(
+            error,
+            TournamentError::Multiple(vec![
+                TournamentError::UnknownComponent("quarter".into()),
+                TournamentError::InvalidPlacement {
+                    component: "final".into(),
+                    placement: "3rd".into(),
+                },
+                TournamentError::InvalidPlacement {
+                    component: "semi".into(),
+                    placement: "3rd".into(),
+                },
+            ])
+        );
+        assert_eq!(
+            error.to_string(),
+            "3 problems:\n  \
+             Unknown component: quarter\n  \
+             Component final has no placement 3rd\n  \
+             Component semi has no placement 3rd"
+        );
+    }
+
+    #[test]
+    fn documents_are_labeled_while_streaming() {
+        let document = "
+components:
+  final:
+    type: !BestOf1
+    teams: [0, 1]
+scoring:
+  [winner, final]: 1.0
+";
+        let dir = std::env::temp_dir();
+        let labels = |name: &str, contents: &str| {
+            let path = dir.join(format!("{}_{}.yml", name, std::process::id()));
+            std::fs::write(&path, contents).unwrap();
+            let fname = path.to_str().unwrap();
+            let labels: Vec<_> = super::read_labeled_tournaments(fname)
+                .unwrap()
+                .map(|labeled| {
+                    let (label, _) = labeled.unwrap();
+                    label.strip_prefix(fname).unwrap().to_owned()
+                })
+                .collect();
+            std::fs::remove_file(&path).unwrap();
+            labels
+        };
+        assert_eq!(labels("single", document), vec![""]);
+        assert_eq!(
+            labels("marked", &format!("# notes\n---{document}")),
+            vec![""]
+        );
+        assert_eq!(
+            labels(
+                "multiple",
+                &format!("{document}---\nname: second{document}")
+            ),
+            vec!["#0", "#second"]
+        );
+    }
+
+    #[test]
+    fn empty_series_is_rejected_at_load() {
+        let tournament: super::Tournament = serde_yaml::from_str(
+            "
+components:
+  final:
+    type: !BestOfN 0
+    teams: [0, 1]
+scoring:
+  [winner, final]: 1.0
+",
+        )
+        .unwrap();
+        assert_eq!(
+            tournament.validate().unwrap_err().to_string(),
+            "Invalid component final: Series must consist of an odd number of games, got 0 in BestOf0"
+        );
+    }
+
+    #[cfg(feature = "schema")]
+    #[test]
+    fn schema() {
+        let schema: serde_json::Value = serde_json::from_str(&super::schema()).unwrap();
+        let properties = &schema["properties"];
+        for property in ["components", "scoring", "noise", "ratings"] {
+            assert!(properties.get(property).is_some(), "{}", property);
+        }
+        assert!(schema["$defs"].get("GroupStage").is_some());
+    }
+
+    #[test]
+    fn sanity_check_with_relegation() {
+        let league = "
+components:
+  league:
+    type: !GroupStage {num_games_per_series: 2}
+    teams: [0, 1, 2, 3]
+scoring:
+  [4th, league]: -1.0
+";
+        let relegation_only = &super::parse_tournaments(league).unwrap()[0];
+        assert!(relegation_only.has_penalties());
+        assert!(relegation_only.has_only_penalties());
+        let mut rng = rand::thread_rng();
+        assert_eq!(
+            relegation_only.check_all_teams_can_win(1000, &mut rng),
+            Ok(vec![])
+        );
+        let with_champion =
+            &super::parse_tournaments(&format!("{league}  [1st, league]: 1.0\n")).unwrap()[0];
+        assert!(!with_champion.has_only_penalties());
+        assert_eq!(
+            with_champion.check_all_teams_can_win(1000, &mut rng),
+            Ok(vec![])
+        );
+    }
+
+    #[test]
+    fn teams_that_cannot_win_are_reported() {
+        let tournament = &super::parse_tournaments(
+            "
+components:
+  league:
+    type: !Placebo
+    teams: [0, 1, 2]
+scoring:
+  [position_1, league]: 1.0
+",
+        )
+        .unwrap()[0];
+        assert_eq!(
+            tournament.check_all_teams_can_win(100, &mut rand::thread_rng()),
+            Ok(vec![1, 2])
+        );
+    }
+
+    #[test]
+    fn all_placements_with_the_highest_score_count_as_winning() {
+        let tournament = &super::parse_tournaments(
+            "
+components:
+  league:
+    type: !Placebo
+    teams: [0, 1, 2]
+scoring:
+  [position_1, league]: 1.0
+  [position_2, league]: 1.0
+",
+        )
+        .unwrap()[0];
+        assert_eq!(
+            tournament.check_all_teams_can_win(100, &mut rand::thread_rng()),
+            Ok(vec![2])
+        );
+    }
+
+    #[test]
+    fn components_must_get_the_number_of_teams_they_require() {
+        let tournament = |r#type: &str, teams: &str| {
+            serde_yaml::from_str::<super::Tournament>(&format!(
+                "
+components:
+  final:
+    type: {}
+    teams: {}
+scoring:
+  [position_1, final]: 1.0
+",
+                r#type, teams
+            ))
+            .unwrap()
+            .validate()
+        };
+        assert_eq!(
+            tournament("!BestOf3", "[0, 1, 2]"),
+            Err(TournamentError::InvalidComponent {
+                component: "final".into(),
+                reason: "BestOf3 requires exactly 2 teams, got 3".into(),
+            })
+        );
+        assert_eq!(
+            tournament(
+                "!LastChanceQualifier {num_games_per_series: 1}",
+                "[0, 1, 2]"
+            ),
+            Err(TournamentError::InvalidComponent {
+                component: "final".into(),
+                reason: "LastChanceQualifier(Bo1 series, 1 slots) requires a power of two of at \
+                         least 2 teams, got 3"
+                    .into(),
+            })
+        );
+        assert!(tournament("!Placebo", "[0, 1, 2]").is_ok());
+    }
+
+    #[test]
+    fn ratings_and_a_rating_distribution_cannot_be_combined() {
+        let tournament = |ratings: &str| {
+            super::parse_tournaments(&format!(
+                "
+rating_distribution: !Normal {{mean: 1500, std_dev: 200}}
+{}
+components:
+  final:
+    type: !BestOf1
+    teams: [0, 1]
+scoring:
+  [winner, final]: 1.0
+",
+                ratings
+            ))
+        };
+        assert!(tournament("").unwrap()[0].has_matchups());
+        assert_eq!(
+            tournament("ratings: {a: 1600, b: 1400}").unwrap_err(),
+            TournamentError::InvalidMatchups(
+                "Ratings and a rating distribution cannot be combined".into()
+            )
+        );
+    }
+
+    #[test]
+    fn invalid_noise_is_rejected() {
+        let tournament = |noise: &str| {
+            super::parse_tournaments(&format!(
+                "
+noise: {}
+components:
+  final:
+    type: !BestOf1
+    teams: [0, 1]
+scoring:
+  [winner, final]: 1.0
+",
+                noise
+            ))
+        };
+        assert!(tournament("!Beta {concentration: 10}").is_ok());
+        assert!(tournament("!Uniform {width: 0}").is_ok());
+        assert_eq!(
+            tournament("!Beta {concentration: 0}").unwrap_err(),
+            TournamentError::InvalidMatchups("Invalid beta noise with concentration 0".into())
+        );
+        assert_eq!(
+            tournament("!Uniform {width: -0.1}").unwrap_err(),
+            TournamentError::InvalidMatchups("Invalid uniform noise with width -0.1".into())
+        );
+        assert!(tournament("!Uniform {width: .nan}").is_err());
+    }
+
+    #[test]
+    fn ratings_table_is_merged() {
+        let tournament = super::parse_tournaments(
+            "
+components:
+  final:
+    type: !BestOf1
+    teams: [0, 1]
+scoring:
+  [winner, final]: 1.0
+",
+        )
+        .unwrap()
+        .remove(0);
+        let table = RatingsTable::parse("0,Alpha,1700\n1,Bravo,1500\n").unwrap();
+        let rated = tournament.clone().with_ratings_table(&table).unwrap();
+        assert_eq!(rated.ratings(), vec![1700.0, 1500.0]);
+        assert_eq!(rated.team_names(), vec!["Alpha", "Bravo"]);
+        assert!(rated.has_matchups());
+        let incomplete = RatingsTable::parse("0,Alpha,1700\n").unwrap();
+        assert!(tournament.with_ratings_table(&incomplete).is_err());
+    }
+
+    #[test]
+    fn sanity_check_can_be_skipped() {
+        let tournament = &super::parse_tournaments(
+            "
+skip_sanity_check: true
+components:
+  final:
+    type: !BestOf1
+    teams: [0, 1]
+scoring:
+  [winner, final]: 1.0
+",
+        )
+        .unwrap()[0];
+        assert!(tournament.skip_sanity_check());
+    }
+
+    #[test]
+    fn matchup_overrides_must_refer_to_teams_of_the_component() {
+        let tournament = |opponent| {
+            serde_yaml::from_str::<super::Tournament>(&format!(
+                "
+components:
+  semi:
+    type: !BestOf1
+    teams: [0, 1]
+  final:
+    type: !BestOf1
+    teams: [[winner, semi], 2]
+    matchups: [{{team: 2, opponent: {opponent}, win_probability: 0.2}}]
+scoring:
+  [winner, final]: 1.0
+"
+            ))
+            .unwrap()
+        };
+        assert!(tournament(0).validate().is_ok());
+        assert_eq!(
+            tournament(3).validate().unwrap_err().to_string(),
+            "Invalid component final: Matchup refers to team 3, which cannot play in it"
+        );
+        let mut direct = tournament(0);
+        direct.components["semi"].matchups = direct.components["final"].matchups.clone();
+        assert!(direct.validate().is_err());
+    }
+
+    #[test]
+    fn num_runs() {
+        let tournament = |num_runs| {
+            serde_yaml::from_str::<super::Tournament>(&format!(
+                "
+num_runs: {num_runs}
+components:
+  final:
+    type: !BestOf1
+    teams: [0, 1]
+scoring:
+  [winner, final]: 1.0
+"
+            ))
+            .unwrap()
+        };
+        assert_eq!(tournament(5000).num_runs(), Some(5000));
+        assert_eq!(
+            tournament(0).validate().unwrap_err(),
+            super::TournamentError::InvalidNumRuns
+        );
+    }
+
+    #[test]
+    fn empty_scoring_is_rejected() {
+        let tournament = super::parse_tournaments(
+            "
+components:
+  final:
+    type: !BestOf1
+    teams: [0, 1]
+scoring: {}
+",
+        );
+        assert_eq!(tournament.unwrap_err(), TournamentError::EmptyScoring);
+    }
+
+    #[test]
+    fn team_names() {
+        let tournament = |team_names| {
+            serde_yaml::from_str::<super::Tournament>(&format!(
+                "
+team_names: {team_names}
+components:
+  final:
+    type: !BestOf1
+    teams: [0, 1]
+scoring:
+  [winner, final]: 1.0
+"
+            ))
+            .unwrap()
+        };
+        let named = tournament("[Alpha, Bravo]");
+        assert!(named.validate().is_ok());
+        assert_eq!(named.team_names(), vec!["Alpha", "Bravo"]);
+        assert_eq!(tournament("[]").team_name(1), "team 1");
+        assert_eq!(
+            tournament("[Alpha]").validate().unwrap_err().to_string(),
+            "Invalid team names: Expected 2 team names, got 1"
+        );
+    }
+
+    #[test]
+    fn display_team_identifier() {
+        let placement = TeamIdentifier::FromPreviousComponent("winner".into(), "SemiFinal".into());
+        assert_eq!(placement.to_string(), "winner of SemiFinal");
+        assert_eq!(TeamIdentifier::Team(3).to_string(), "team 3");
+    }
+
+    #[test]
+    fn component_accessors() {
+        let tournament = &super::parse_tournaments(
+            "
+components:
+  semi:
+    type: !BestOf3
+    teams: [0, 1]
+  final:
+    type: !BestOf3
+    teams: [[winner, semi], 2]
+scoring:
+  [winner, final]: 1.0
+",
+        )
+        .unwrap()[0];
+        assert_eq!(tournament.num_components(), 2);
+        assert_eq!(
+            tournament.component_names().collect::<Vec<_>>(),
+            vec!["semi", "final"]
+        );
+        assert_eq!(tournament.component("final").unwrap().teams.len(), 2);
+        assert!(tournament.component("quarter").is_none());
+    }
+}
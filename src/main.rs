@@ -1,179 +1,614 @@
-mod component;
-mod math;
-mod runner;
+mod args;
 
-use std::collections::HashMap;
 use std::env;
-use std::hash::Hash;
+use std::fmt;
+use std::fs::File;
+use std::io;
+use std::io::BufWriter;
+use std::io::Write;
 
-use component::Component;
-use linked_hash_map::LinkedHashMap;
+use itertools::EitherOrBoth;
+use itertools::Itertools;
 use ordered_float::OrderedFloat;
-use rand::rngs::ThreadRng;
-use rand::seq::IteratorRandom;
 use rand::thread_rng;
 use rand::Rng;
-use rayon::iter::ParallelIterator;
-use rayon::prelude::IntoParallelIterator;
-use serde::Deserialize;
+use rand::SeedableRng;
+use tournament_sim::describe;
+use tournament_sim::diff::structural_diff;
+use tournament_sim::dot;
+use tournament_sim::error::TournamentError;
+use tournament_sim::plot::write_plot_data;
+use tournament_sim::plot::PlotData;
+use tournament_sim::ratings::RatingsTable;
+use tournament_sim::read_labeled_tournaments;
+use tournament_sim::report::AdvantageReport;
+use tournament_sim::report::PayoutReport;
+use tournament_sim::report::RelativeAdvantage;
+use tournament_sim::report::ReplicateSummary;
+use tournament_sim::report::SeedTitleReport;
+use tournament_sim::rng::stream_rng;
+use tournament_sim::rng::SimRng;
+use tournament_sim::rng::SANITY_CHECK_STREAM;
+use tournament_sim::runner::Runner;
+use tournament_sim::simulation::compare_advantages;
+use tournament_sim::simulation::component_contributions;
+use tournament_sim::simulation::component_sensitivities;
+use tournament_sim::simulation::equal_teams_title_probabilities;
+use tournament_sim::simulation::estimate_advantage;
+use tournament_sim::simulation::estimate_conditional_advantage;
+use tournament_sim::simulation::estimate_maximum_advantage;
+use tournament_sim::simulation::get_misseeded_teams;
+use tournament_sim::simulation::get_noisily_seeded_teams;
+use tournament_sim::simulation::get_strong_teams;
+use tournament_sim::simulation::replicate_base_seed;
+use tournament_sim::simulation::seeded_title_probabilities;
+use tournament_sim::simulation::title_probabilities;
+use tournament_sim::simulation::RunLength;
+use tournament_sim::simulation::StopCondition;
+use tournament_sim::simulation::NUM_RUNS;
+use tournament_sim::Tournament;
+use tournament_sim::SANITY_CHECK_RUNS;
 
-use crate::runner::Runner;
+use crate::args::Args;
+use crate::args::Control;
 
-const STRONG_TEAM_ADVANTAGE: f64 = 0.1;
-
-type Score = f64;
-type ComponentName = String;
-type PlacementName = String;
-
-#[derive(Deserialize, Debug, Hash, PartialEq, Eq, Clone)]
-struct Placement {
-    component: usize,
-    position: usize,
+fn exit_with_error(error: impl fmt::Display) -> ! {
+    eprintln!("Error: {}", error);
+    std::process::exit(1)
 }
 
-#[derive(Deserialize, Debug, Hash, PartialEq, Eq, Clone)]
-#[serde(untagged)]
-pub enum TeamIdentifier {
-    Team(usize),
-    FromPreviousComponent(PlacementName, ComponentName),
+fn or_exit<T>(result: Result<T, TournamentError>) -> T {
+    result.unwrap_or_else(|e| exit_with_error(e))
 }
 
-#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
-pub struct Team {
-    index: usize,
-    strong: bool,
+fn create_or_exit(file: &str) -> BufWriter<File> {
+    BufWriter::new(
+        File::create(file)
+            .unwrap_or_else(|e| exit_with_error(format!("Cannot create {}: {}", file, e))),
+    )
 }
 
-impl Team {
-    fn probability_to_win_against(&self, other: &Self) -> f64 {
-        if self.strong {
-            0.5 + STRONG_TEAM_ADVANTAGE
-        } else if other.strong {
-            0.5 - STRONG_TEAM_ADVANTAGE
-        } else {
-            0.5
-        }
-    }
+fn write_or_exit(file: &str, result: io::Result<()>) {
+    result.unwrap_or_else(|e| exit_with_error(format!("Cannot write {}: {}", file, e)))
+}
 
-    fn wins_against(&self, other: &Self, rng: &mut ThreadRng) -> bool {
-        let p = self.probability_to_win_against(other);
-        rng.gen_range(0.0..=1.0) < p
-    }
+/// The tournaments of a file, exiting on the first one that cannot be read.
+/// Errors in a document are prefixed with the file name.
+fn labeled_tournaments(file: &str) -> impl Iterator<Item = (String, Tournament)> + '_ {
+    or_exit(read_labeled_tournaments(file))
+        .map(move |labeled| labeled.unwrap_or_else(|e| exit_with_error(format!("{}: {}", file, e))))
 }
 
-#[derive(Deserialize, Debug, Clone)]
-pub struct Tournament {
-    // Linked hash map is used here to preserve order of the components during
-    // deserialization.
-    components: LinkedHashMap<ComponentName, Component<TeamIdentifier>>,
-    scoring: HashMap<TeamIdentifier, Score>,
+/// The number of runs given on the command line, else in the file.
+fn num_runs(t: &Tournament, args: &Args) -> usize {
+    args.num_runs.or(t.num_runs()).unwrap_or(NUM_RUNS)
 }
 
-impl Tournament {
-    fn get_team_numbers(&self) -> impl Iterator<Item = usize> + '_ {
-        self.components.iter().flat_map(|(_, component)| {
-            component.teams.iter().filter_map(|team| match team {
-                TeamIdentifier::Team(num) => Some(*num),
-                _ => None,
-            })
-        })
+fn run_length(t: &Tournament, args: &Args) -> StopCondition {
+    let length = match args.precision {
+        Some(precision) => RunLength::Precision(precision),
+        None => RunLength::Fixed(num_runs(t, args)),
+    };
+    StopCondition {
+        length,
+        deadline: args.deadline,
     }
+}
 
-    pub fn num_teams(&self) -> usize {
-        self.get_team_numbers().count()
+fn make_runner(t: Tournament, args: &Args) -> Runner {
+    let mut runner = or_exit(Runner::new(t)).with_chaos(args.chaos.unwrap_or_default());
+    if args.low_memory {
+        runner = runner.with_dead_placements_dropped();
     }
-
-    fn sanity_check_any_team_can_win(mut self) {
-        let winner_placement = self
-            .scoring
-            .iter()
-            .max_by_key(|(_, v)| OrderedFloat(**v))
-            .unwrap();
-        self.scoring = [(winner_placement.0.clone(), 1.0f64)].into_iter().collect();
-        let num_teams = self.num_teams();
-        let runner = Runner::new(self);
-        let mut rng = thread_rng();
-        let num_tries = 10000;
-        for strong_team in 0..num_teams {
-            assert!(
-                (0..num_tries).any(|_| {
-                    let teams = (0..num_teams)
-                        .map(|index| Team {
-                            index,
-                            strong: index == strong_team,
-                        })
-                        .collect();
-                    let mut runner = runner.clone();
-                    let result = runner.get_score_result(teams, &mut rng);
-                    result.strong_team > 0.0
-                }),
-                "Invalid tournament format: Team {} cannot win.",
-                strong_team
-            );
-        }
+    match args.edge {
+        Some(edge) => runner.with_strong_team_advantage(edge),
+        None => runner,
     }
 }
 
-#[derive(Default, Debug)]
-pub struct ScoreResult {
-    strong_team: Score,
-    all_teams: Score,
+/// The format that all other formats are compared to.
+struct Baseline {
+    label: String,
+    report: AdvantageReport,
+    runner: Runner,
+    num_teams: usize,
 }
 
-impl std::iter::Sum for ScoreResult {
-    fn sum<I: Iterator<Item = Self>>(iter: I) -> Self {
-        let mut total = Self::default();
-        for item in iter {
-            total.strong_team += item.strong_team;
-            total.all_teams += item.all_teams;
-        }
-        total
+/// Simulates the first tournament of the baseline file, with the same
+/// base seed as all other simulations.
+fn simulate_baseline(file: &str, args: &Args) -> Baseline {
+    let (label, t) = labeled_tournaments(file)
+        .next()
+        .unwrap_or_else(|| exit_with_error(format!("No tournament in baseline file {}", file)));
+    let num_teams = t.num_teams();
+    let length = run_length(&t, args);
+    let runner = make_runner(t, args);
+    let report = estimate_advantage(&runner, num_teams, length, args.base_seed, |rng| {
+        get_strong_teams(num_teams, args.strong_teams, rng)
+    });
+    println!("Baseline {label}: advantage {report}");
+    if report.is_noise_dominated() {
+        eprintln!("Warning: the advantage of the baseline is compatible with zero, so ratios to it are meaningless");
+    }
+    Baseline {
+        label,
+        report,
+        runner,
+        num_teams,
     }
 }
 
-fn read_tournament(fname: &str) -> Tournament {
-    let contents = std::fs::read_to_string(fname).unwrap();
-    serde_yaml::from_str(&contents).unwrap()
+/// The random numbers of the sanity check, which are reproducible if the
+/// base seed is given.
+fn sanity_rng(args: &Args) -> SimRng {
+    match args.base_seed {
+        Some(base_seed) => stream_rng(base_seed, SANITY_CHECK_STREAM),
+        None => SimRng::from_entropy(),
+    }
 }
 
-fn get_teams(num: usize, rng: &mut ThreadRng) -> Vec<Team> {
-    let mut teams: Vec<Team> = (0..num)
-        .map(|index| Team {
-            index,
-            strong: false,
+/// Simulates the tournament, prints and returns the advantage. The results
+/// of sweeps are added to `plots`.
+fn run_tournament(
+    label: &str,
+    t: Tournament,
+    args: &Args,
+    baseline: Option<&Baseline>,
+    plots: &mut Vec<PlotData>,
+) -> AdvantageReport {
+    println!("{label}");
+    let mut runner = make_runner(t.clone(), args);
+    if args.no_sanity || t.skip_sanity_check() {
+        eprintln!("Warning: skipping the check that every team can win");
+    } else {
+        let teams = or_exit(t.check_all_teams_can_win(SANITY_CHECK_RUNS, &mut sanity_rng(args)));
+        if !teams.is_empty() {
+            let teams = teams.iter().map(|team| t.team_name(*team)).join(", ");
+            let goal = if t.has_only_penalties() {
+                "avoid all penalties"
+            } else {
+                "win"
+            };
+            eprintln!("Invalid tournament format: {teams} cannot {goal}.");
+            std::process::exit(1);
+        }
+    }
+    let num_teams = t.num_teams();
+    if args.strong_teams >= num_teams {
+        exit_with_error(format!(
+            "{label} has {num_teams} teams, which is not enough for {} strong teams",
+            args.strong_teams
+        ));
+    }
+    let component_names: Vec<_> = t.component_names().map(|name| name.to_owned()).collect();
+    let component_types: Vec<_> = component_names
+        .iter()
+        .map(|name| t.component(name).unwrap().r#type.to_string())
+        .collect();
+    let num_runs = num_runs(&t, args);
+    let length = run_length(&t, args);
+    let total_score = t.total_score();
+    let has_penalties = t.has_penalties();
+    let currency = t.currency().map(|currency| currency.to_owned());
+    if args.control == Some(Control::NoStrongTeam) {
+        runner = runner.with_strong_team_advantage(0.0);
+    }
+    let strong_teams = |rng: &mut _| get_strong_teams(num_teams, args.strong_teams, rng);
+    let reports: Vec<_> = (0..args.repeat)
+        .map(|replicate| {
+            let base_seed = if args.repeat == 1 {
+                args.base_seed
+            } else {
+                replicate_base_seed(args.base_seed, replicate)
+            };
+            estimate_advantage(&runner, num_teams, length, base_seed, |rng| {
+                match args.control {
+                    Some(Control::FixedTopSeed) => get_misseeded_teams(num_teams, 0.0, rng),
+                    _ => strong_teams(rng),
+                }
+            })
         })
         .collect();
-    teams.iter_mut().choose(rng).unwrap().strong = true;
-    teams
+    let report = reports[0].clone();
+    println!("Advantage: {}", report);
+    if let Some(baseline) = baseline {
+        println!(
+            "Advantage relative to {}: {}",
+            baseline.label,
+            RelativeAdvantage::new(&report, &baseline.report)
+        );
+        if args.paired_test {
+            let comparison = compare_advantages(
+                (&runner, num_teams),
+                (&baseline.runner, baseline.num_teams),
+                args.strong_teams,
+                num_runs,
+                args.base_seed,
+            );
+            println!(
+                "Advantage minus the advantage of {}: {comparison}",
+                baseline.label
+            );
+        }
+    }
+    if args.control == Some(Control::NoStrongTeam) {
+        if report.is_noise_dominated() {
+            println!("Control passed: the advantage without a strong team is compatible with zero");
+        } else {
+            eprintln!("Warning: the advantage without a strong team is not compatible with zero, the measurement is biased");
+        }
+    } else if report.is_noise_dominated() {
+        eprintln!(
+            "Warning: the confidence interval of the advantage contains zero, increase the number of runs"
+        );
+    }
+    if args.repeat > 1 {
+        println!(
+            "Advantage over repeats: {}",
+            ReplicateSummary::from_reports(&reports)
+        );
+    }
+    if args.precision.is_some() {
+        println!("Runs needed: {}", report.num_runs);
+    } else if args.deadline.is_some() && report.num_runs < num_runs as u64 {
+        println!(
+            "Timeout reached after {} of {} runs",
+            report.num_runs, num_runs
+        );
+    }
+    println!(
+        "Tiebreaks needed in {:.1}% of runs, deepest tiebreak: {}",
+        100.0 * report.tiebreak_fraction,
+        report.max_tiebreak_depth
+    );
+    println!(
+        "Strong team score: mean {:.3}, median {:.3}, 90th percentile {:.3}",
+        report.mean_score, report.median_score, report.p90_score
+    );
+    if args.strong_teams > 1 {
+        println!(
+            "Mean score by tier: strong teams {:.3}, other teams {:.3}",
+            report.mean_score, report.mean_other_score
+        );
+    }
+    println!(
+        "Final rank of the strong team: mean {:.2}, standard deviation {:.2}",
+        report.mean_rank, report.rank_std_dev
+    );
+    if let Some(gini) = report.seed_gini {
+        println!("Gini coefficient of the expected scores by seed: {gini:.3}");
+    }
+    if has_penalties {
+        println!(
+            "Strong team penalized (negative score) in {:.1}% of runs",
+            100.0 * report.negative_score_fraction
+        );
+    }
+    println!(
+        "Rounds survived by the strong team: {:.2}",
+        report.rounds_survived
+    );
+    if args.max_advantage {
+        let maximum =
+            estimate_maximum_advantage(&runner, num_teams, length, args.base_seed, strong_teams);
+        println!("Advantage if the edge decides every game: {}", maximum);
+        println!(
+            "Fraction of the maximum advantage reached: {:.1}%",
+            100.0 * report.advantage / maximum.advantage
+        );
+    }
+    if args.payout {
+        println!(
+            "{}",
+            PayoutReport::new(total_score, num_teams, &report).with_currency(currency)
+        );
+    }
+    if args.seed_titles {
+        let probabilities =
+            equal_teams_title_probabilities(&runner, num_teams, num_runs, args.base_seed);
+        let seed_titles = SeedTitleReport {
+            num_runs: num_runs as u64,
+            probabilities,
+        };
+        println!("{seed_titles}");
+    }
+    if args.upsets {
+        println!("Upset rate per component:");
+        for (name, upsets) in component_names.iter().zip(report.upsets.iter()) {
+            match upsets.rate() {
+                Some(rate) => println!(
+                    "  {name}: {rate:.3} ({:.2} matches per run)",
+                    upsets.matches as f64 / report.num_runs as f64
+                ),
+                None => println!("  {name}: no matches"),
+            }
+        }
+    }
+    if let Some(name) = &args.positions {
+        match component_names.iter().position(|other| other == name) {
+            Some(index) => {
+                println!("Position of the strong team in {name}:");
+                for (position, fraction) in report.strong_team_positions[index].iter().enumerate() {
+                    println!("  {}: {:.1}%", position + 1, 100.0 * fraction);
+                }
+            }
+            None => eprintln!("Warning: no component named {name}"),
+        }
+    }
+    if let Some(name) = &args.given {
+        match component_names.iter().position(|other| other == name) {
+            Some(index) => {
+                let conditional = estimate_conditional_advantage(
+                    &runner,
+                    num_teams,
+                    index,
+                    num_runs,
+                    args.base_seed,
+                    strong_teams,
+                );
+                println!("Advantage given the strong team reached {name}: {conditional}");
+            }
+            None => eprintln!("Warning: no component named {name}"),
+        }
+    }
+    if args.explain {
+        let contributions = component_contributions(
+            &runner,
+            num_teams,
+            component_names.len(),
+            num_runs,
+            args.base_seed
+                .expect("The base seed is drawn for --explain"),
+            strong_teams,
+        );
+        println!("Contribution of each component to the advantage:");
+        for ((name, r#type), contribution) in component_names
+            .iter()
+            .zip(component_types.iter())
+            .zip(contributions)
+        {
+            println!(
+                "  The {name} ({type}) contributes {contribution:+.2}",
+                r#type = r#type
+            );
+        }
+        println!(
+            "  (the advantage without an edge in that component only, subtracted from the total)"
+        );
+    }
+    if args.sensitivity {
+        let sensitivities = component_sensitivities(
+            &runner,
+            num_teams,
+            component_names.len(),
+            num_runs,
+            args.base_seed
+                .expect("The base seed is drawn for --sensitivity"),
+            strong_teams,
+        );
+        println!("Components by sensitivity of the advantage to their edge:");
+        for ((name, r#type), sensitivity) in component_names
+            .iter()
+            .zip(component_types.iter())
+            .zip(sensitivities)
+            .sorted_by_key(|(_, sensitivity)| std::cmp::Reverse(OrderedFloat(sensitivity.abs())))
+        {
+            println!("  {name} ({type}): {sensitivity:+.2}", r#type = r#type);
+        }
+        println!(
+            "  (the change of the advantage per relative change of the edge in that component only)"
+        );
+    }
+    if let Some(error_rate) = args.seeding_error {
+        let mut points = vec![];
+        for rate in [0.0, error_rate] {
+            let report = estimate_advantage(&runner, num_teams, length, args.base_seed, |rng| {
+                get_misseeded_teams(num_teams, rate, rng)
+            });
+            println!(
+                "Advantage as top seed with seeding error {:.3}: {}",
+                rate, report
+            );
+            points.push((rate, report.advantage));
+        }
+        plots.push(PlotData {
+            label: label.to_owned(),
+            x_label: "seeding error".into(),
+            y_label: "advantage".into(),
+            points,
+        });
+    }
+    report
 }
 
-fn run_tournament_for_file(file: &str) {
-    println!("{file}");
-    let t = read_tournament(&file);
-    t.clone().sanity_check_any_team_can_win();
+/// Prints the probability of each team to win a tournament with ratings or
+/// win probabilities. The results of sweeps are added to `plots`.
+fn print_title_probabilities(label: &str, t: Tournament, args: &Args, plots: &mut Vec<PlotData>) {
     let num_teams = t.num_teams();
-    let runner = Runner::new(t);
-    let num_runs = 1000000;
-    let score: ScoreResult = (0..num_runs)
-        .into_par_iter()
-        .map(|_| {
-            let mut rng = thread_rng();
-            let mut runner = runner.clone();
-            let teams = get_teams(num_teams, &mut rng);
-            runner.get_score_result(teams, &mut rng)
-        })
-        .sum();
-    let average_score = score.all_teams / num_runs as f64 / num_teams as f64;
-    let strong_team_score_advantage = score.strong_team / num_runs as f64 - average_score;
+    let names = t.team_names();
+    let ratings = t.ratings();
+    let num_runs = num_runs(&t, args);
+    let width = names.iter().map(|name| name.len()).max().unwrap_or(0);
+    let runner = make_runner(t, args);
+    let probabilities = title_probabilities(&runner, num_teams, num_runs, args.base_seed);
+    println!("Title probabilities:");
+    for (name, p) in names.iter().zip(probabilities) {
+        println!("  {name:<width$}  {p:.3}");
+    }
+    if args.seeding_noise.is_empty() {
+        return;
+    }
+    if ratings.is_empty() {
+        eprintln!("Warning: --seeding-noise requires ratings");
+        return;
+    }
+    let favorite = (0..num_teams)
+        .max_by_key(|team| OrderedFloat(ratings[*team]))
+        .unwrap();
     println!(
-        "Advantage: {:.3}",
-        strong_team_score_advantage / STRONG_TEAM_ADVANTAGE
+        "Title probability of {} when seeded by noisy ratings:",
+        names[favorite]
     );
+    let mut points = vec![];
+    for noise in args.seeding_noise.iter() {
+        let probabilities =
+            seeded_title_probabilities(&runner, num_teams, num_runs, args.base_seed, |rng| {
+                get_noisily_seeded_teams(&ratings, *noise, rng)
+            });
+        println!("  noise {noise:>6.1}  {:.3}", probabilities[favorite]);
+        points.push((*noise, probabilities[favorite]));
+    }
+    plots.push(PlotData {
+        label: label.to_owned(),
+        x_label: "seeding noise".into(),
+        y_label: format!("title probability of {}", names[favorite]),
+        points,
+    });
+}
+
+fn print_summary(mut results: Vec<(String, AdvantageReport)>) {
+    results.sort_by_key(|(_, report)| std::cmp::Reverse(OrderedFloat(report.advantage)));
+    let width = results
+        .iter()
+        .map(|(file, _)| file.len())
+        .max()
+        .unwrap_or(0);
+    println!("\nSummary:");
+    for (file, report) in results {
+        println!("{file:<width$}  {report}");
+    }
+}
+
+/// Compares the documents of the two files in order.
+fn print_structural_diff(old: &str, new: &str) {
+    let documents = labeled_tournaments(old).zip_longest(labeled_tournaments(new));
+    for documents in documents {
+        match documents {
+            EitherOrBoth::Both((old_label, old), (new_label, new)) => {
+                println!("{old_label} -> {new_label}:");
+                let lines = structural_diff(&old, &new);
+                if lines.is_empty() {
+                    println!("  no structural changes");
+                }
+                for line in lines {
+                    println!("  {line}");
+                }
+            }
+            EitherOrBoth::Left((label, _)) => println!("{label}: removed"),
+            EitherOrBoth::Right((label, _)) => println!("{label}: added"),
+        }
+    }
 }
 
 fn main() {
-    let files: Vec<String> = env::args().skip(1).collect();
-    for file in files {
-        run_tournament_for_file(&file);
+    let mut args = Args::parse(env::args().skip(1));
+    #[cfg(feature = "schema")]
+    if args.emit_schema {
+        println!("{}", tournament_sim::schema());
+        return;
+    }
+    if let Some(dot_file) = &args.dot {
+        let mut out = create_or_exit(dot_file);
+        for file in args.files.iter() {
+            for (label, tournament) in labeled_tournaments(file) {
+                write_or_exit(dot_file, dot::write_dot(&tournament, &label, &mut out));
+            }
+        }
+        write_or_exit(dot_file, out.flush());
+        return;
+    }
+    if let Some((old, new)) = &args.structural_diff {
+        print_structural_diff(old, new);
+        return;
+    }
+    if args.describe {
+        for file in args.files.iter() {
+            for (label, tournament) in labeled_tournaments(file) {
+                println!("{label}: {tournament}");
+                describe::describe(&tournament);
+            }
+        }
+        return;
+    }
+    if args.normalize {
+        let tournaments = args.files.iter().flat_map(|file| labeled_tournaments(file));
+        for (index, (_, tournament)) in tournaments.enumerate() {
+            if index > 0 {
+                println!("---");
+            }
+            print!("{}", tournament_sim::to_yaml(&tournament));
+        }
+        return;
+    }
+    #[cfg(feature = "sqlite")]
+    let database = args.sqlite.as_ref().map(|path| {
+        tournament_sim::sqlite::open(path)
+            .unwrap_or_else(|e| exit_with_error(format!("Cannot open database {}: {}", path, e)))
+    });
+    if args.baseline.is_some() || args.explain || args.sensitivity {
+        // Common random numbers for the baseline and the compared formats,
+        // and for the variations of a format that --explain and
+        // --sensitivity compare.
+        if args.base_seed.is_none() {
+            let base_seed = args.base_seed.get_or_insert_with(|| thread_rng().gen());
+            println!("Base seed: {base_seed}");
+        }
+    }
+    let baseline = args
+        .baseline
+        .as_ref()
+        .map(|file| simulate_baseline(file, &args));
+    let ratings = args
+        .ratings
+        .as_ref()
+        .map(|file| or_exit(RatingsTable::read(file)));
+    let mut plots = vec![];
+    let results: Vec<_> = args
+        .files
+        .iter()
+        .flat_map(|file| labeled_tournaments(file))
+        .map(|(label, tournament)| match &ratings {
+            Some(ratings) => {
+                let tournament = tournament
+                    .with_ratings_table(ratings)
+                    .unwrap_or_else(|e| exit_with_error(format!("{}: {}", label, e)));
+                (label, tournament)
+            }
+            None => (label, tournament),
+        })
+        .filter_map(|(label, tournament)| {
+            // With fixed win probabilities, the strong teams are not stronger.
+            if tournament.has_matchups() && !tournament.is_rated() {
+                println!("{label}");
+                print_title_probabilities(&label, tournament, &args, &mut plots);
+                return None;
+            }
+            let report = run_tournament(
+                &label,
+                tournament.clone(),
+                &args,
+                baseline.as_ref(),
+                &mut plots,
+            );
+            if tournament.is_rated() {
+                print_title_probabilities(&label, tournament, &args, &mut plots);
+            }
+            #[cfg(feature = "sqlite")]
+            if let Some(database) = &database {
+                tournament_sim::sqlite::insert(database, &label, args.base_seed, None, &report)
+                    .unwrap_or_else(|e| {
+                        exit_with_error(format!("Cannot insert {} into the database: {}", label, e))
+                    });
+            }
+            Some((label, report))
+        })
+        .collect();
+    if let Some(plot_file) = &args.plot_data {
+        let mut out = create_or_exit(plot_file);
+        write_or_exit(plot_file, write_plot_data(&plots, &mut out));
+        write_or_exit(plot_file, out.flush());
+    }
+    if results.len() > 1 {
+        print_summary(results);
     }
 }
@@ -1,9 +1,80 @@
-fn binomial(n: usize, k: usize) -> usize {
-    ((n - k + 1)..=n).product::<usize>() / (1..=k).product::<usize>()
+/// The binomial coefficient, by the multiplicative formula in floating
+/// point, so that it neither overflows for long series nor takes
+/// factorials. Exact as long as the result is below 2^53.
+fn binomial(n: usize, k: usize) -> f64 {
+    let k = k.min(n - k);
+    (0..k).fold(1.0, |result, i| result * (n - i) as f64 / (i + 1) as f64)
 }
 
 pub fn binomial_distribution(p: f64, n: usize, k: usize) -> f64 {
-    binomial(n, k) as f64 * p.powi((n - k) as i32) * (1.0 - p).powi(k as i32)
+    binomial(n, k) * p.powi((n - k) as i32) * (1.0 - p).powi(k as i32)
+}
+
+/// The probability of at most `k` failures in `n` trials that each succeed
+/// with probability `p`, i.e. the sum of [`binomial_distribution`] up to `k`.
+pub fn cumulative_binomial(p: f64, n: usize, k: usize) -> f64 {
+    (0..=k.min(n))
+        .map(|failures| binomial_distribution(p, n, failures))
+        .sum()
+}
+
+/// The probability that a team with Elo rating `rating` wins a game
+/// against a team with rating `other_rating`.
+pub fn elo_win_probability(rating: f64, other_rating: f64) -> f64 {
+    1.0 / (1.0 + 10f64.powf((other_rating - rating) / 400.0))
+}
+
+/// The rating difference at which a team wins a game with probability `p`
+/// under [`elo_win_probability`].
+pub fn elo_rating_difference(p: f64) -> f64 {
+    400.0 * (p / (1.0 - p)).log10()
+}
+
+/// The probability that a team which wins each game with probability `p`
+/// wins a best-of-`num_games` series, i.e. loses at most `(num_games - 1) / 2` games.
+pub fn best_of_n_win_probability(p: f64, num_games: usize) -> f64 {
+    best_of_n_win_probability_with_handicap(p, num_games, 0)
+}
+
+/// Like [`best_of_n_win_probability`], but the team starts the series with
+/// `handicap` wins, so it only has to win `num_games - handicap` games
+/// while still losing at most `(num_games - 1) / 2` of them.
+pub fn best_of_n_win_probability_with_handicap(p: f64, num_games: usize, handicap: usize) -> f64 {
+    cumulative_binomial(p, num_games - handicap, (num_games - 1) / 2)
+}
+
+/// The expected number of decisive games in a race in which one team needs
+/// `wins_needed` wins (winning each game with probability `p`) and the other
+/// team needs `other_wins_needed` wins.
+pub fn expected_num_games(p: f64, wins_needed: usize, other_wins_needed: usize) -> f64 {
+    let finishes = |p: f64, wins_needed: usize, other_wins_needed: usize| -> f64 {
+        (0..other_wins_needed)
+            .map(|losses| {
+                let num_games = wins_needed + losses;
+                num_games as f64
+                    * binomial(num_games - 1, losses)
+                    * p.powi(wins_needed as i32)
+                    * (1.0 - p).powi(losses as i32)
+            })
+            .sum()
+    };
+    finishes(p, wins_needed, other_wins_needed) + finishes(1.0 - p, other_wins_needed, wins_needed)
+}
+
+/// The probability that a standard normal variable is at least `z`, with
+/// an absolute error below 1.5e-7 (Abramowitz and Stegun 7.1.26).
+pub fn normal_upper_tail(z: f64) -> f64 {
+    let x = z.abs() / std::f64::consts::SQRT_2;
+    let t = 1.0 / (1.0 + 0.3275911 * x);
+    let polynomial = t
+        * (0.254829592
+            + t * (-0.284496736 + t * (1.421413741 + t * (-1.453152027 + t * 1.061405429))));
+    let tail = 0.5 * polynomial * (-x * x).exp();
+    if z >= 0.0 {
+        tail
+    } else {
+        1.0 - tail
+    }
 }
 
 #[cfg(test)]
@@ -11,16 +82,27 @@ mod tests {
     #[test]
     fn binomial() {
         use super::binomial;
-        assert_eq!(binomial(0, 0), 1);
-        assert_eq!(binomial(1, 0), 1);
-        assert_eq!(binomial(1, 1), 1);
-        assert_eq!(binomial(2, 0), 1);
-        assert_eq!(binomial(2, 1), 2);
-        assert_eq!(binomial(2, 2), 1);
-        assert_eq!(binomial(3, 0), 1);
-        assert_eq!(binomial(3, 1), 3);
-        assert_eq!(binomial(3, 2), 3);
-        assert_eq!(binomial(3, 3), 1);
+        assert_eq!(binomial(0, 0), 1.0);
+        assert_eq!(binomial(1, 0), 1.0);
+        assert_eq!(binomial(1, 1), 1.0);
+        assert_eq!(binomial(2, 0), 1.0);
+        assert_eq!(binomial(2, 1), 2.0);
+        assert_eq!(binomial(2, 2), 1.0);
+        assert_eq!(binomial(3, 0), 1.0);
+        assert_eq!(binomial(3, 1), 3.0);
+        assert_eq!(binomial(3, 2), 3.0);
+        assert_eq!(binomial(3, 3), 1.0);
+    }
+
+    #[test]
+    fn binomial_of_long_series() {
+        use super::binomial;
+        assert_eq!(binomial(50, 25), 126410606437752.0);
+        assert_eq!(binomial(64, 1), 64.0);
+        let relative_error = |value: f64, expected: f64| (value - expected).abs() / expected;
+        assert!(relative_error(binomial(60, 30), 118264581564861424.0) < 1e-12);
+        assert!(relative_error(binomial(100, 50), 1.0089134454556419e29) < 1e-12);
+        assert!(binomial(1000, 500).is_finite());
     }
 
     #[test]
@@ -31,4 +113,77 @@ mod tests {
             0.5
         )
     }
+
+    #[test]
+    fn cumulative_binomial() {
+        use super::cumulative_binomial;
+        assert_eq!(cumulative_binomial(0.5, 3, 0), 0.125);
+        assert_eq!(cumulative_binomial(0.5, 3, 1), 0.5);
+        assert_eq!(cumulative_binomial(0.5, 3, 3), 1.0);
+        // More failures than trials are certain.
+        assert_eq!(
+            cumulative_binomial(0.3, 2, 5),
+            cumulative_binomial(0.3, 2, 2)
+        );
+        assert!((cumulative_binomial(0.3, 10, 10) - 1.0).abs() < 1e-12);
+        // 0.6^2 + 2 * 0.6 * 0.4
+        assert!((cumulative_binomial(0.6, 2, 1) - 0.84).abs() < 1e-12);
+    }
+
+    #[test]
+    fn elo_win_probability() {
+        use super::elo_win_probability;
+        assert_eq!(elo_win_probability(1500.0, 1500.0), 0.5);
+        let difference = super::elo_rating_difference(0.75);
+        assert!((elo_win_probability(1500.0 + difference, 1500.0) - 0.75).abs() < 1e-12);
+        assert!((elo_win_probability(1900.0, 1500.0) - 10.0 / 11.0).abs() < 1e-12);
+        assert!(
+            (elo_win_probability(1500.0, 1700.0) + elo_win_probability(1700.0, 1500.0) - 1.0).abs()
+                < 1e-12
+        );
+    }
+
+    #[test]
+    fn best_of_n_win_probability() {
+        use super::best_of_n_win_probability;
+        for num_games in [1, 3, 5, 7, 9] {
+            assert!((best_of_n_win_probability(0.5, num_games) - 0.5).abs() < 1e-12);
+            assert_eq!(best_of_n_win_probability(1.0, num_games), 1.0);
+            assert_eq!(best_of_n_win_probability(0.0, num_games), 0.0);
+        }
+        assert!((best_of_n_win_probability(0.5, 101) - 0.5).abs() < 1e-12);
+        assert!((best_of_n_win_probability(0.6, 1) - 0.6).abs() < 1e-12);
+        // 0.6^3 + 3 * 0.6^2 * 0.4
+        assert!((best_of_n_win_probability(0.6, 3) - 0.648).abs() < 1e-12);
+    }
+
+    #[test]
+    fn best_of_n_win_probability_with_handicap() {
+        use super::best_of_n_win_probability_with_handicap;
+        // One more win out of the remaining two games: 1 - 0.4^2
+        assert!((best_of_n_win_probability_with_handicap(0.6, 3, 1) - 0.84).abs() < 1e-12);
+        // At least three out of six fair games: (20 + 15 + 6 + 1) / 64
+        assert!((best_of_n_win_probability_with_handicap(0.5, 7, 1) - 42.0 / 64.0).abs() < 1e-12);
+        assert_eq!(
+            best_of_n_win_probability_with_handicap(0.6, 5, 0),
+            super::best_of_n_win_probability(0.6, 5)
+        );
+    }
+
+    #[test]
+    fn normal_upper_tail() {
+        use super::normal_upper_tail;
+        assert!((normal_upper_tail(0.0) - 0.5).abs() < 1e-7);
+        assert!((normal_upper_tail(1.959963984540054) - 0.025).abs() < 1e-7);
+        assert!((normal_upper_tail(-1.0) - 0.8413447460685429).abs() < 1e-7);
+    }
+
+    #[test]
+    fn expected_num_games() {
+        use super::expected_num_games;
+        assert_eq!(expected_num_games(0.5, 1, 1), 1.0);
+        // Best of three: two games with probability p^2 + (1-p)^2, three otherwise.
+        assert!((expected_num_games(0.6, 2, 2) - (2.0 * 0.52 + 3.0 * 0.48)).abs() < 1e-12);
+        assert!((expected_num_games(1.0, 4, 4) - 4.0).abs() < 1e-12);
+    }
 }
@@ -0,0 +1,621 @@
+use std::cmp::Ordering;
+use std::sync::Arc;
+
+use rand::Rng;
+use rand_distr::Beta;
+use rand_distr::Distribution;
+use rand_distr::Normal;
+use serde::Deserialize;
+use serde::Serialize;
+
+use crate::math::best_of_n_win_probability_with_handicap;
+use crate::math::elo_rating_difference;
+use crate::math::elo_win_probability;
+use crate::strong_team_rating_difference;
+use crate::Team;
+use crate::STRONG_TEAM_ADVANTAGE;
+
+/// Perturbs the win probability of a match before it is played.
+///
+/// The noise is drawn once per match (a series or a pairing within a group),
+/// not once per game: independent per-game noise with mean `p` leaves the
+/// outcome distribution of every game unchanged and would therefore have no
+/// effect at all.
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, Default)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub enum NoiseModel {
+    #[default]
+    None,
+    /// Adds a uniform offset in `[-width, width]`.
+    Uniform { width: f64 },
+    /// Draws the probability from a beta distribution with mean `p`.
+    /// Larger concentrations mean less noise.
+    Beta { concentration: f64 },
+}
+
+impl NoiseModel {
+    pub fn validate(&self) -> Result<(), String> {
+        match *self {
+            NoiseModel::Uniform { width } if !width.is_finite() || width < 0.0 => {
+                Err(format!("Invalid uniform noise with width {}", width))
+            }
+            NoiseModel::Beta { concentration }
+                if !concentration.is_finite() || concentration <= 0.0 =>
+            {
+                Err(format!(
+                    "Invalid beta noise with concentration {}",
+                    concentration
+                ))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    fn perturb(&self, p: f64, rng: &mut impl Rng) -> f64 {
+        match self {
+            NoiseModel::None => p,
+            NoiseModel::Uniform { width } => p + rng.gen_range(-width..=*width),
+            NoiseModel::Beta { concentration } => {
+                if p <= 0.0 || p >= 1.0 {
+                    return p;
+                }
+                Beta::new(p * concentration, (1.0 - p) * concentration)
+                    .unwrap_or_else(|_| {
+                        panic!("Invalid beta noise concentration: {}", concentration)
+                    })
+                    .sample(rng)
+            }
+        }
+    }
+}
+
+/// A distribution of Elo ratings, from which the ratings of all teams are
+/// drawn anew in every run.
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub enum RatingDistribution {
+    Normal { mean: f64, std_dev: f64 },
+}
+
+impl RatingDistribution {
+    pub fn validate(&self) -> Result<(), String> {
+        match *self {
+            RatingDistribution::Normal { mean, std_dev }
+                if !mean.is_finite() || !std_dev.is_finite() || std_dev < 0.0 =>
+            {
+                Err(format!(
+                    "Invalid normal rating distribution with mean {} and standard deviation {}",
+                    mean, std_dev
+                ))
+            }
+            RatingDistribution::Normal { .. } => Ok(()),
+        }
+    }
+
+    /// Ratings for `num_teams` teams, from the best to the worst, so that
+    /// the seeding follows the strength of the teams.
+    pub fn sample(&self, num_teams: usize, rng: &mut impl Rng) -> Vec<f64> {
+        let mut ratings: Vec<f64> = match *self {
+            RatingDistribution::Normal { mean, std_dev } => {
+                let normal = Normal::new(mean, std_dev)
+                    .unwrap_or_else(|_| panic!("Invalid rating standard deviation: {}", std_dev));
+                normal.sample_iter(rng).take(num_teams).collect()
+            }
+        };
+        ratings.sort_by(|a, b| b.total_cmp(a));
+        ratings
+    }
+}
+
+/// What to do with a probability that is not finite or lies outside of `[0, 1]`.
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub enum ProbabilityPolicy {
+    #[default]
+    Error,
+    /// Clamp into `[0, 1]`. NaN cannot be clamped and is always an error.
+    Clamp,
+}
+
+impl ProbabilityPolicy {
+    pub fn apply(&self, p: f64) -> f64 {
+        if (0.0..=1.0).contains(&p) {
+            return p;
+        }
+        match self {
+            _ if p.is_nan() => panic!("Invalid probability: NaN"),
+            ProbabilityPolicy::Error => panic!("Invalid probability: {}", p),
+            ProbabilityPolicy::Clamp => p.clamp(0.0, 1.0),
+        }
+    }
+}
+
+/// The probability that `team` wins a game against `opponent`, by team number.
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct MatchupOverride {
+    pub team: usize,
+    pub opponent: usize,
+    pub win_probability: f64,
+}
+
+/// Decides the outcome of matches between two teams.
+#[derive(Debug, Clone)]
+pub struct MatchModel {
+    noise: NoiseModel,
+    policy: ProbabilityPolicy,
+    /// The strong team wins a game with probability `0.5 + strong_team_advantage`.
+    strong_team_advantage: f64,
+    /// Multiplies the rating that the strong teams add to their own ratings,
+    /// so that a strong team has the strong team advantage over an equal team.
+    rating_scale: f64,
+    /// Pulls every win probability towards 0.5, from not at all for 0 to
+    /// a coin flip for 1, without changing which team is stronger.
+    chaos: f64,
+    /// The probability that team `i` wins a game against team `j` at
+    /// `[i][j]`. Takes precedence over the ratings if not empty.
+    win_probabilities: Vec<Vec<f64>>,
+    /// Take precedence over everything else, in either order of the teams.
+    overrides: Vec<MatchupOverride>,
+    /// Moves the win probability of the weaker team in every match up by
+    /// this amount, or down if negative. Applied after the chaos.
+    underdog_shift: f64,
+    /// Lowers the win probability of the teams whose entry, by team index,
+    /// is set by this amount. Applied after the underdog shift.
+    penalty: f64,
+    penalized: Vec<bool>,
+    /// Series win probabilities computed ahead of the simulation, sorted
+    /// by the number of games, the handicap and the bits of the game win
+    /// probability.
+    series_win_probabilities: Arc<Vec<(SeriesKey, f64)>>,
+}
+
+/// The number of games, the handicap and the bits of the game win
+/// probability of a series.
+type SeriesKey = (usize, usize, u64);
+
+impl Default for MatchModel {
+    fn default() -> Self {
+        Self::new(NoiseModel::default(), ProbabilityPolicy::default())
+    }
+}
+
+impl MatchModel {
+    pub fn new(noise: NoiseModel, policy: ProbabilityPolicy) -> Self {
+        Self {
+            noise,
+            policy,
+            strong_team_advantage: STRONG_TEAM_ADVANTAGE,
+            rating_scale: 1.0,
+            chaos: 0.0,
+            win_probabilities: vec![],
+            overrides: vec![],
+            underdog_shift: 0.0,
+            penalty: 0.0,
+            penalized: vec![],
+            series_win_probabilities: Arc::default(),
+        }
+    }
+
+    pub fn with_strong_team_advantage(self, strong_team_advantage: f64) -> Self {
+        Self {
+            strong_team_advantage,
+            rating_scale: elo_rating_difference(0.5 + strong_team_advantage)
+                / strong_team_rating_difference(),
+            ..self
+        }
+    }
+
+    pub fn strong_team_advantage(&self) -> f64 {
+        self.strong_team_advantage
+    }
+
+    pub fn with_chaos(self, chaos: f64) -> Self {
+        Self { chaos, ..self }
+    }
+
+    pub fn with_win_probabilities(self, win_probabilities: Vec<Vec<f64>>) -> Self {
+        Self {
+            win_probabilities,
+            ..self
+        }
+    }
+
+    pub fn with_overrides(self, overrides: Vec<MatchupOverride>) -> Self {
+        Self { overrides, ..self }
+    }
+
+    pub fn with_underdog_shift(self, underdog_shift: f64) -> Self {
+        Self {
+            underdog_shift,
+            ..self
+        }
+    }
+
+    pub fn with_penalized_teams(self, penalty: f64, penalized: Vec<bool>) -> Self {
+        Self {
+            penalty,
+            penalized,
+            ..self
+        }
+    }
+
+    fn penalty_of(&self, team: &Team) -> f64 {
+        match self.penalized.get(team.index) {
+            Some(true) => self.penalty,
+            _ => 0.0,
+        }
+    }
+
+    /// The override for a pair of teams, if any.
+    fn overridden_probability(&self, team: &Team, other: &Team) -> Option<f64> {
+        self.overrides.iter().find_map(|matchup| {
+            if (matchup.team, matchup.opponent) == (team.index, other.index) {
+                Some(matchup.win_probability)
+            } else if (matchup.opponent, matchup.team) == (team.index, other.index) {
+                Some(1.0 - matchup.win_probability)
+            } else {
+                None
+            }
+        })
+    }
+
+    /// Computes the win probability of each of `series`, given by the
+    /// number of games and the handicap, for every game win probability
+    /// that a match between teams with the given ratings, by team index,
+    /// can have if any of them are strong, so that matches look them up
+    /// instead. Matches with noise, penalties or other ratings still
+    /// compute them.
+    pub fn with_series_precomputed(self, series: &[(usize, usize)], ratings: &[f64]) -> Self {
+        let mut table: Vec<_> = series
+            .iter()
+            .flat_map(|(num_games, handicap)| {
+                self.game_probabilities(ratings).into_iter().map(move |p| {
+                    let series = best_of_n_win_probability_with_handicap(p, *num_games, *handicap);
+                    ((*num_games, *handicap, p.to_bits()), series)
+                })
+            })
+            .collect();
+        table.sort_by_key(|(key, _)| *key);
+        table.dedup_by_key(|(key, _)| *key);
+        Self {
+            series_win_probabilities: Arc::new(table),
+            ..self
+        }
+    }
+
+    /// The distinct game win probabilities of the matches between teams
+    /// with the given ratings, by team index, of which any can be strong,
+    /// or none if matches are noisy.
+    fn game_probabilities(&self, ratings: &[f64]) -> Vec<f64> {
+        if !matches!(self.noise, NoiseModel::None) {
+            return vec![];
+        }
+        let mut teams: Vec<_> = ratings
+            .iter()
+            .enumerate()
+            .flat_map(|(index, rating)| {
+                [false, true].map(|strong| Team::with_strength(index, strong).with_rating(*rating))
+            })
+            .collect();
+        // Without win probabilities by team index, only the ratings matter,
+        // so that teams with the same rating can meet.
+        let by_index = !self.win_probabilities.is_empty() || !self.overrides.is_empty();
+        if !by_index {
+            let key = |team: &Team| (team.rating.to_bits(), team.own_rating.to_bits());
+            teams.sort_by_key(key);
+            teams.dedup_by_key(|team| key(team));
+        }
+        let mut probabilities: Vec<_> = teams
+            .iter()
+            .flat_map(|team| {
+                teams
+                    .iter()
+                    .filter(move |other| !by_index || other.index != team.index)
+                    .map(|other| self.check_probability(self.unperturbed_probability(team, other)))
+            })
+            .collect();
+        probabilities.sort_by(f64::total_cmp);
+        probabilities.dedup();
+        probabilities
+    }
+
+    /// The probability that the first team wins a series of `num_games`
+    /// that it starts with `handicap` wins if it wins each game with
+    /// probability `p`.
+    pub fn series_win_probability(&self, p: f64, num_games: usize, handicap: usize) -> f64 {
+        let key = (num_games, handicap, p.to_bits());
+        match self
+            .series_win_probabilities
+            .binary_search_by_key(&key, |(key, _)| *key)
+        {
+            Ok(index) => self.series_win_probabilities[index].1,
+            Err(_) => best_of_n_win_probability_with_handicap(p, num_games, handicap),
+        }
+    }
+
+    /// The probability that `team` wins a game against `other` in this match.
+    pub fn match_probability(&self, team: &Team, other: &Team, rng: &mut impl Rng) -> f64 {
+        let p = self.check_probability(self.unperturbed_probability(team, other));
+        self.check_probability(self.noise.perturb(p, rng))
+    }
+
+    /// The win probability of a match before the noise. The underdog shift
+    /// and the penalty can move it out of `[0, 1]`, which is left to the
+    /// probability policy.
+    fn unperturbed_probability(&self, team: &Team, other: &Team) -> f64 {
+        let from_matrix = self
+            .win_probabilities
+            .get(team.index)
+            .and_then(|row| row.get(other.index));
+        let p = match (self.overridden_probability(team, other), from_matrix) {
+            (Some(p), _) => p,
+            (None, Some(p)) => *p,
+            (None, None) => self.rating_probability(team, other),
+        };
+        let p = 0.5 + (p - 0.5) * (1.0 - self.chaos);
+        let p = match p.partial_cmp(&0.5) {
+            Some(Ordering::Less) => p + self.underdog_shift,
+            Some(Ordering::Greater) => p - self.underdog_shift,
+            _ => p,
+        };
+        p - self.penalty_of(team) + self.penalty_of(other)
+    }
+
+    /// The Elo win probability of `team` against `other`, with only the
+    /// part of the rating difference that the strong teams add to their own
+    /// ratings multiplied by the rating scale. The own ratings of the teams
+    /// keep their difference, even if the scale is infinite or zero.
+    fn rating_probability(&self, team: &Team, other: &Team) -> f64 {
+        let offset = team.strong_offset() - other.strong_offset();
+        let scaled = if offset == 0.0 {
+            0.0
+        } else {
+            self.rating_scale * offset
+        };
+        let difference = team.own_rating - other.own_rating + scaled;
+        if difference == 0.0 {
+            return 0.5;
+        }
+        elo_win_probability(difference, 0.0)
+    }
+
+    /// Applies the probability policy to a derived probability, such as
+    /// the probability to win a whole series.
+    pub fn check_probability(&self, p: f64) -> f64 {
+        self.policy.apply(p)
+    }
+
+    pub fn wins_game(&self, p: f64, rng: &mut impl Rng) -> bool {
+        debug_assert!((0.0..=1.0).contains(&p));
+        rng.gen_range(0.0..1.0) < p
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::thread_rng;
+
+    use super::MatchModel;
+    use super::MatchupOverride;
+    use super::NoiseModel;
+    use super::ProbabilityPolicy;
+    use super::RatingDistribution;
+    use crate::math::elo_win_probability;
+    use crate::Team;
+
+    #[test]
+    fn game_probabilities_are_those_of_the_possible_matches() {
+        let edge = crate::STRONG_TEAM_ADVANTAGE;
+        let probabilities = MatchModel::default().game_probabilities(&[crate::BASE_RATING; 16]);
+        assert_eq!(probabilities.len(), 3);
+        for (p, expected) in probabilities.into_iter().zip([0.5 - edge, 0.5, 0.5 + edge]) {
+            assert!((p - expected).abs() < 1e-12);
+        }
+        let noisy = MatchModel::new(
+            NoiseModel::Uniform { width: 0.1 },
+            ProbabilityPolicy::default(),
+        );
+        assert!(noisy
+            .game_probabilities(&[crate::BASE_RATING; 16])
+            .is_empty());
+        let rated = MatchModel::default().game_probabilities(&[1500.0, 1600.0, 1700.0]);
+        assert!(rated.contains(&elo_win_probability(1600.0, 1500.0)));
+        assert!(rated.contains(&elo_win_probability(1500.0, 1700.0)));
+    }
+
+    fn mean_perturbed(noise: NoiseModel, p: f64) -> f64 {
+        let mut rng = thread_rng();
+        let num_samples = 100000;
+        (0..num_samples)
+            .map(|_| noise.perturb(p, &mut rng))
+            .sum::<f64>()
+            / num_samples as f64
+    }
+
+    #[test]
+    fn sampled_ratings_are_ordered_from_best_to_worst() {
+        let distribution = RatingDistribution::Normal {
+            mean: 1500.0,
+            std_dev: 200.0,
+        };
+        let ratings = distribution.sample(10000, &mut thread_rng());
+        assert_eq!(ratings.len(), 10000);
+        assert!(ratings.windows(2).all(|pair| pair[0] >= pair[1]));
+        let mean = ratings.iter().sum::<f64>() / ratings.len() as f64;
+        assert!((mean - 1500.0).abs() < 10.0);
+        let invalid = RatingDistribution::Normal {
+            mean: 1500.0,
+            std_dev: -1.0,
+        };
+        assert!(invalid.validate().is_err());
+    }
+
+    #[test]
+    fn no_noise_keeps_probability() {
+        let mut rng = thread_rng();
+        assert_eq!(NoiseModel::None.perturb(0.6, &mut rng), 0.6);
+    }
+
+    #[test]
+    fn beta_noise_preserves_mean() {
+        let mean = mean_perturbed(
+            NoiseModel::Beta {
+                concentration: 10.0,
+            },
+            0.6,
+        );
+        assert!((mean - 0.6).abs() < 0.01);
+    }
+
+    #[test]
+    fn uniform_noise_is_kept_in_unit_interval_by_the_clamp_policy() {
+        let mut rng = thread_rng();
+        let model = MatchModel::new(NoiseModel::Uniform { width: 0.5 }, ProbabilityPolicy::Clamp)
+            .with_win_probabilities(vec![vec![0.5, 0.9], vec![0.1, 0.5]]);
+        let probabilities: Vec<_> = (0..1000)
+            .map(|_| model.match_probability(&Team::new(0), &Team::new(1), &mut rng))
+            .collect();
+        assert!(probabilities.iter().all(|p| (0.0..=1.0).contains(p)));
+        assert!(probabilities.contains(&1.0));
+    }
+
+    #[test]
+    fn ratings_decide_matches() {
+        let model = MatchModel::default();
+        let (team, other) = (Team::new(0), Team::new(1).with_rating(1900.0));
+        let p = model.match_probability(&team, &other, &mut thread_rng());
+        assert!((p - 1.0 / 11.0).abs() < 1e-12);
+        // The edge of the strong team only scales what it adds to its own rating.
+        let model = model.with_strong_team_advantage(0.2);
+        let p = model.match_probability(&Team::with_strength(0, true), &team, &mut thread_rng());
+        assert!((p - 0.7).abs() < 1e-12);
+        let p = model.match_probability(&team, &other, &mut thread_rng());
+        assert!((p - 1.0 / 11.0).abs() < 1e-12);
+        let certain = MatchModel::default().with_strong_team_advantage(0.5);
+        let p =
+            |team: &Team, other: &Team| certain.match_probability(team, other, &mut thread_rng());
+        let strong = Team::with_strength(0, true).with_rating(1900.0);
+        assert_eq!(p(&strong, &other), 1.0);
+        assert_eq!(p(&other, &strong), 0.0);
+        assert!((p(&other, &team) - 10.0 / 11.0).abs() < 1e-12);
+        assert_eq!(p(&other, &other), 0.5);
+    }
+
+    #[test]
+    fn a_negative_edge_keeps_the_ratings() {
+        let model = MatchModel::default().with_strong_team_advantage(-0.1);
+        let (team, other) = (
+            Team::new(0).with_rating(1900.0),
+            Team::new(1).with_rating(1100.0),
+        );
+        let p = model.match_probability(&team, &other, &mut thread_rng());
+        assert!((p - elo_win_probability(1900.0, 1100.0)).abs() < 1e-12);
+        let strong = Team::with_strength(1, true).with_rating(1900.0);
+        let p = model.match_probability(&strong, &team, &mut thread_rng());
+        assert!((p - 0.4).abs() < 1e-12);
+    }
+
+    #[test]
+    fn win_probabilities_override_ratings() {
+        let model =
+            MatchModel::default().with_win_probabilities(vec![vec![0.5, 0.7], vec![0.3, 0.5]]);
+        let p = model.match_probability(
+            &Team::new(0),
+            &Team::new(1).with_rating(1900.0),
+            &mut thread_rng(),
+        );
+        assert_eq!(p, 0.7);
+    }
+
+    #[test]
+    fn chaos_pulls_probabilities_towards_a_coin_flip() {
+        let team = |index| Team::with_strength(index, index == 0);
+        let p = |chaos| {
+            MatchModel::default().with_chaos(chaos).match_probability(
+                &team(0),
+                &team(1),
+                &mut thread_rng(),
+            )
+        };
+        assert!((p(0.0) - 0.6).abs() < 1e-12);
+        assert!((p(0.5) - 0.55).abs() < 1e-12);
+        assert_eq!(p(1.0), 0.5);
+    }
+
+    #[test]
+    fn underdog_shift_moves_both_teams_of_a_match() {
+        let team = |index| Team::with_strength(index, index == 0);
+        let model = MatchModel::default().with_underdog_shift(0.05);
+        let p = |a, b| model.match_probability(&team(a), &team(b), &mut thread_rng());
+        assert!((p(0, 1) - 0.55).abs() < 1e-12);
+        assert!((p(1, 0) - 0.45).abs() < 1e-12);
+        assert_eq!(p(1, 2), 0.5);
+    }
+
+    #[test]
+    fn penalized_teams_lose_probability_against_everyone() {
+        let team = Team::new;
+        let model = MatchModel::default().with_penalized_teams(0.2, vec![true, false]);
+        let p = |a, b| model.match_probability(&team(a), &team(b), &mut thread_rng());
+        assert!((p(0, 1) - 0.3).abs() < 1e-12);
+        assert!((p(1, 0) - 0.7).abs() < 1e-12);
+        // Teams without an entry are not penalized.
+        assert_eq!(p(1, 2), 0.5);
+    }
+
+    fn penalized_favourite(policy: ProbabilityPolicy) -> f64 {
+        MatchModel::new(NoiseModel::None, policy)
+            .with_win_probabilities(vec![vec![0.5, 0.9], vec![0.1, 0.5]])
+            .with_penalized_teams(0.2, vec![false, true])
+            .match_probability(&Team::new(0), &Team::new(1), &mut thread_rng())
+    }
+
+    #[test]
+    fn clamp_policy_clamps_penalties_beyond_certainty() {
+        assert_eq!(penalized_favourite(ProbabilityPolicy::Clamp), 1.0);
+    }
+
+    #[test]
+    #[should_panic]
+    fn error_policy_rejects_penalties_beyond_certainty() {
+        penalized_favourite(ProbabilityPolicy::Error);
+    }
+
+    #[test]
+    fn overrides_take_precedence_in_both_directions() {
+        let model = MatchModel::default()
+            .with_win_probabilities(vec![vec![0.5, 0.7], vec![0.3, 0.5]])
+            .with_overrides(vec![MatchupOverride {
+                team: 1,
+                opponent: 0,
+                win_probability: 0.9,
+            }]);
+        let team = Team::new;
+        let p = |a, b| model.match_probability(&team(a), &team(b), &mut thread_rng());
+        assert_eq!(p(1, 0), 0.9);
+        assert!((p(0, 1) - 0.1).abs() < 1e-12);
+    }
+
+    #[test]
+    fn clamp_policy() {
+        let policy = ProbabilityPolicy::Clamp;
+        assert_eq!(policy.apply(0.3), 0.3);
+        assert_eq!(policy.apply(1.5), 1.0);
+        assert_eq!(policy.apply(-0.5), 0.0);
+        assert_eq!(policy.apply(f64::INFINITY), 1.0);
+        assert_eq!(policy.apply(f64::NEG_INFINITY), 0.0);
+    }
+
+    #[test]
+    #[should_panic]
+    fn error_policy_rejects_out_of_range() {
+        ProbabilityPolicy::Error.apply(1.5);
+    }
+
+    #[test]
+    #[should_panic]
+    fn clamp_policy_rejects_nan() {
+        ProbabilityPolicy::Clamp.apply(f64::NAN);
+    }
+}
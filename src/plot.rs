@@ -0,0 +1,52 @@
+use std::io;
+use std::io::Write;
+
+/// The results of a sweep over one parameter, for plotting.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PlotData {
+    pub label: String,
+    pub x_label: String,
+    pub y_label: String,
+    pub points: Vec<(f64, f64)>,
+}
+
+/// Writes the data sets as whitespace separated columns, with the label and
+/// the axes in comments. Data sets are separated by two blank lines, so
+/// that gnuplot can select them with `index` and `plot 'data' with lines`
+/// works directly for a single data set.
+pub fn write_plot_data(data: &[PlotData], out: &mut impl Write) -> io::Result<()> {
+    for (index, set) in data.iter().enumerate() {
+        if index > 0 {
+            writeln!(out, "\n")?;
+        }
+        writeln!(out, "# {}", set.label)?;
+        writeln!(out, "# {}\t{}", set.x_label, set.y_label)?;
+        for (x, y) in set.points.iter() {
+            writeln!(out, "{}\t{}", x, y)?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::write_plot_data;
+    use super::PlotData;
+
+    #[test]
+    fn data_sets_are_separated_for_gnuplot() {
+        let set = |label: &str| PlotData {
+            label: label.into(),
+            x_label: "seeding noise".into(),
+            y_label: "title probability".into(),
+            points: vec![(0.0, 0.5), (100.0, 0.25)],
+        };
+        let mut out = vec![];
+        write_plot_data(&[set("a.yml"), set("b.yml")], &mut out).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "# a.yml\n# seeding noise\ttitle probability\n0\t0.5\n100\t0.25\n\n\n\
+             # b.yml\n# seeding noise\ttitle probability\n0\t0.5\n100\t0.25\n"
+        );
+    }
+}
@@ -0,0 +1,159 @@
+//! Ratings kept outside of the tournament file, as CSV with one team per
+//! line:
+//!
+//! ```text
+//! team,name,rating
+//! 0,Alpha,1700
+//! 1,Bravo,1500
+//! ```
+//!
+//! The header is optional. Lines may continue with the probabilities that
+//! the team wins a game against each team, in the order of the team
+//! numbers, which then decide the matches instead of the ratings. Fields
+//! are separated by commas and cannot be quoted.
+
+use crate::error::TournamentError;
+
+/// The rows of a ratings file, in the order of their team numbers.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct RatingsTable {
+    pub rows: Vec<RatingsRow>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct RatingsRow {
+    pub team: usize,
+    pub name: String,
+    pub rating: f64,
+    /// The win probabilities against each team, empty if not given.
+    pub win_probabilities: Vec<f64>,
+}
+
+impl RatingsTable {
+    pub fn parse(contents: &str) -> Result<Self, TournamentError> {
+        let mut rows: Vec<RatingsRow> = vec![];
+        for (number, line) in contents.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let fields: Vec<_> = line.split(',').map(|field| field.trim()).collect();
+            let invalid = |reason: &str| {
+                TournamentError::InvalidRatings(format!("line {}: {}", number + 1, reason))
+            };
+            let Ok(team) = fields[0].parse::<usize>() else {
+                if rows.is_empty() {
+                    // The header.
+                    continue;
+                }
+                return Err(invalid("the team must be a team number"));
+            };
+            if fields.len() < 3 {
+                return Err(invalid("expected a team number, a name and a rating"));
+            }
+            let rating = fields[2]
+                .parse::<f64>()
+                .ok()
+                .filter(|rating| rating.is_finite())
+                .ok_or_else(|| invalid("the rating must be a number"))?;
+            let win_probabilities = fields[3..]
+                .iter()
+                .map(|p| p.parse::<f64>())
+                .collect::<Result<_, _>>()
+                .map_err(|_| invalid("the win probabilities must be numbers"))?;
+            if rows.iter().any(|row| row.team == team) {
+                return Err(invalid(&format!("team {} is listed twice", team)));
+            }
+            rows.push(RatingsRow {
+                team,
+                name: fields[1].to_owned(),
+                rating,
+                win_probabilities,
+            });
+        }
+        rows.sort_by_key(|row| row.team);
+        Ok(Self { rows })
+    }
+
+    pub fn read(fname: &str) -> Result<Self, TournamentError> {
+        let contents = std::fs::read_to_string(fname).map_err(|e| TournamentError::Io {
+            file: fname.to_owned(),
+            reason: e.to_string(),
+        })?;
+        Self::parse(&contents)
+    }
+
+    /// Checks that there is exactly one row for each of the teams and that
+    /// either all or no rows have win probabilities.
+    pub fn validate(&self, num_teams: usize) -> Result<(), TournamentError> {
+        let invalid = |reason: String| TournamentError::InvalidRatings(reason);
+        if let Some(missing) =
+            (0..num_teams).find(|team| self.rows.iter().all(|row| row.team != *team))
+        {
+            return Err(invalid(format!("team {} has no rating", missing)));
+        }
+        if let Some(extra) = self.rows.iter().find(|row| row.team >= num_teams) {
+            return Err(invalid(format!(
+                "team {} does not exist in a tournament with {} teams",
+                extra.team, num_teams
+            )));
+        }
+        let with_probabilities = self
+            .rows
+            .iter()
+            .filter(|row| !row.win_probabilities.is_empty())
+            .count();
+        if with_probabilities != 0 && with_probabilities != self.rows.len() {
+            return Err(invalid(
+                "either all or no teams must have win probabilities".into(),
+            ));
+        }
+        Ok(())
+    }
+
+    pub fn win_probabilities(&self) -> Vec<Vec<f64>> {
+        self.rows
+            .iter()
+            .map(|row| row.win_probabilities.clone())
+            .filter(|row| !row.is_empty())
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::RatingsTable;
+    use crate::error::TournamentError;
+
+    #[test]
+    fn ratings_are_parsed_in_team_order() {
+        let table = RatingsTable::parse(
+            "team,name,rating
+1, Bravo, 1500
+# A comment
+0, Alpha, 1700
+",
+        )
+        .unwrap();
+        let names: Vec<_> = table.rows.iter().map(|row| row.name.as_str()).collect();
+        assert_eq!(names, vec!["Alpha", "Bravo"]);
+        assert_eq!(table.rows[0].rating, 1700.0);
+        assert!(table.validate(2).is_ok());
+        assert_eq!(
+            table.validate(3),
+            Err(TournamentError::InvalidRatings(
+                "team 2 has no rating".into()
+            ))
+        );
+    }
+
+    #[test]
+    fn win_probabilities_follow_the_rating() {
+        let table = RatingsTable::parse("0,a,1500,0.5,0.7\n1,b,1500,0.3,0.5\n").unwrap();
+        assert_eq!(
+            table.win_probabilities(),
+            vec![vec![0.5, 0.7], vec![0.3, 0.5]]
+        );
+        assert!(RatingsTable::parse("0,a,1500\n1,b,strong\n").is_err());
+    }
+}
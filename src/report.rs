@@ -0,0 +1,703 @@
+use std::fmt;
+
+use itertools::EitherOrBoth;
+use itertools::Itertools;
+use serde::Serialize;
+
+use crate::math::normal_upper_tail;
+use crate::stats::gini;
+use crate::stats::Histogram;
+use crate::stats::RunningStats;
+use crate::STRONG_TEAM_ADVANTAGE;
+
+/// Two-sided 95% quantile of the standard normal distribution.
+const Z_95: f64 = 1.959963984540054;
+/// The number of decimals of an advantage without a finite confidence interval.
+const DEFAULT_DECIMALS: usize = 3;
+/// More decimals than this are never justified by a realistic number of runs.
+const MAX_DECIMALS: usize = 9;
+
+/// The number of decided matches in a component and how many of them
+/// were won by the lower seed.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize)]
+pub struct UpsetCount {
+    pub matches: u64,
+    pub upsets: u64,
+}
+
+impl UpsetCount {
+    pub fn merge(self, other: Self) -> Self {
+        Self {
+            matches: self.matches + other.matches,
+            upsets: self.upsets + other.upsets,
+        }
+    }
+
+    /// The fraction of matches that were upsets, if there were any matches.
+    pub fn rate(&self) -> Option<f64> {
+        (self.matches > 0).then(|| self.upsets as f64 / self.matches as f64)
+    }
+}
+
+/// Everything that is accumulated over the runs of a simulation.
+#[derive(Clone, Debug, Default)]
+pub struct SimulationStats {
+    /// Per run, the score of the strong team minus the average score of all teams.
+    pub advantage: RunningStats,
+    pub runs_with_tiebreak: u64,
+    pub max_tiebreak_depth: usize,
+    /// Per run, the number of components the strong team survived.
+    pub rounds_survived: RunningStats,
+    /// Per run, the final rank of the strong team by score.
+    pub final_rank: RunningStats,
+    /// The upsets of each component, empty if no runs were simulated.
+    pub upsets: Vec<UpsetCount>,
+    /// The distribution of the score of the strong team.
+    pub strong_team_score: Histogram,
+    /// Per run, the average score of the teams that are not strong.
+    pub other_teams_score: RunningStats,
+    /// Per component, how often the strong team finished in each position.
+    pub strong_team_positions: Vec<Vec<u64>>,
+    /// The score of each seed, summed over all runs.
+    pub seed_scores: Vec<f64>,
+}
+
+/// The results of a single run, as added by [`SimulationStats::push_run`].
+#[derive(Clone, Copy, Debug, Default)]
+pub struct RunResult<'a> {
+    pub strong_team_score: f64,
+    /// The average score of the teams that are not strong.
+    pub other_teams_score: f64,
+    /// The score of the strong team minus the average score of all teams.
+    pub advantage: f64,
+    pub tiebreak_depth: usize,
+    pub rounds_survived: usize,
+    pub final_rank: usize,
+    pub upsets: &'a [UpsetCount],
+    pub strong_team_positions: &'a [Option<usize>],
+    pub seed_scores: &'a [f64],
+}
+
+impl SimulationStats {
+    /// Adds a single run, without allocating once the lists have grown to
+    /// the number of components and teams.
+    pub fn push_run(&mut self, run: &RunResult) {
+        self.advantage.push(run.advantage);
+        self.runs_with_tiebreak += u64::from(run.tiebreak_depth > 0);
+        self.max_tiebreak_depth = self.max_tiebreak_depth.max(run.tiebreak_depth);
+        self.rounds_survived.push(run.rounds_survived as f64);
+        self.final_rank.push(run.final_rank as f64);
+        add_in_place(&mut self.upsets, run.upsets, |sum, count| {
+            *sum = sum.merge(*count)
+        });
+        self.strong_team_score.push(run.strong_team_score);
+        self.other_teams_score.push(run.other_teams_score);
+        if self.strong_team_positions.len() < run.strong_team_positions.len() {
+            self.strong_team_positions
+                .resize(run.strong_team_positions.len(), vec![]);
+        }
+        for (counts, position) in self
+            .strong_team_positions
+            .iter_mut()
+            .zip(run.strong_team_positions)
+        {
+            if let Some(position) = position {
+                if counts.len() <= *position {
+                    counts.resize(position + 1, 0);
+                }
+                counts[*position] += 1;
+            }
+        }
+        add_in_place(&mut self.seed_scores, run.seed_scores, |sum, score| {
+            *sum += score
+        });
+    }
+
+    pub fn merge(self, other: Self) -> Self {
+        Self {
+            advantage: self.advantage.merge(other.advantage),
+            runs_with_tiebreak: self.runs_with_tiebreak + other.runs_with_tiebreak,
+            max_tiebreak_depth: self.max_tiebreak_depth.max(other.max_tiebreak_depth),
+            rounds_survived: self.rounds_survived.merge(other.rounds_survived),
+            final_rank: self.final_rank.merge(other.final_rank),
+            upsets: add_elementwise(self.upsets, other.upsets, UpsetCount::merge),
+            strong_team_score: self.strong_team_score.merge(other.strong_team_score),
+            other_teams_score: self.other_teams_score.merge(other.other_teams_score),
+            strong_team_positions: add_elementwise(
+                self.strong_team_positions,
+                other.strong_team_positions,
+                |a, b| add_elementwise(a, b, |a, b| a + b),
+            ),
+            seed_scores: add_elementwise(self.seed_scores, other.seed_scores, |a, b| a + b),
+        }
+    }
+}
+
+/// Adds `other` to `sum` element by element, where a missing element
+/// counts as zero.
+fn add_in_place<T: Clone>(sum: &mut Vec<T>, other: &[T], add: impl Fn(&mut T, &T)) {
+    for (sum, x) in sum.iter_mut().zip(other) {
+        add(sum, x);
+    }
+    if sum.len() < other.len() {
+        sum.extend_from_slice(&other[sum.len()..]);
+    }
+}
+
+/// Adds two lists element by element, where a missing element counts as zero.
+fn add_elementwise<T>(a: Vec<T>, b: Vec<T>, add: impl Fn(T, T) -> T) -> Vec<T> {
+    a.into_iter()
+        .zip_longest(b)
+        .map(|pair| match pair {
+            EitherOrBoth::Both(a, b) => add(a, b),
+            EitherOrBoth::Left(x) | EitherOrBoth::Right(x) => x,
+        })
+        .collect()
+}
+
+/// The estimated advantage of the strong team together with its 95%
+/// confidence interval, both in units of the edge of the strong team.
+/// With a negative edge, a positive advantage still means that the format
+/// amplifies the edge, in favor of the other teams.
+#[derive(Clone, Debug, Serialize)]
+pub struct AdvantageReport {
+    pub num_runs: u64,
+    /// The edge that the advantage is given in units of. Zero if the strong
+    /// team has no edge, in which case the advantage is the raw difference
+    /// between its score and the average score.
+    pub edge: f64,
+    pub advantage: f64,
+    pub ci_half_width: f64,
+    /// The fraction of runs in which any ties had to be broken.
+    pub tiebreak_fraction: f64,
+    pub max_tiebreak_depth: usize,
+    /// The average number of components in which the strong team played and
+    /// did not finish last.
+    pub rounds_survived: f64,
+    /// The mean and standard deviation of the final rank of the strong
+    /// team, which tell formats with the same advantage but different
+    /// risk apart.
+    pub mean_rank: f64,
+    pub rank_std_dev: f64,
+    /// The upsets of each component, summed over all runs.
+    pub upsets: Vec<UpsetCount>,
+    /// The mean, median and 90th percentile of the score of the strong team.
+    pub mean_score: f64,
+    pub median_score: f64,
+    pub p90_score: f64,
+    /// The fraction of runs in which the strong team ended up with a
+    /// negative score, e.g. by being relegated.
+    pub negative_score_fraction: f64,
+    /// The mean score of the teams that are not strong. With several strong
+    /// teams, this and `mean_score` are the averages of the two tiers.
+    pub mean_other_score: f64,
+    /// Per component, the fraction of runs in which the strong team
+    /// finished in each position, starting from the first.
+    pub strong_team_positions: Vec<Vec<f64>>,
+    /// The expected score of each seed.
+    pub seed_scores: Vec<f64>,
+    /// The Gini coefficient of the expected scores of the seeds: 0 if every
+    /// seed can expect the same, larger the more the value is concentrated
+    /// on few seeds. `None` if the expected scores do not have a positive sum.
+    pub seed_gini: Option<f64>,
+}
+
+impl AdvantageReport {
+    pub fn from_stats(stats: &SimulationStats) -> Self {
+        Self::from_stats_with_edge(stats, STRONG_TEAM_ADVANTAGE)
+    }
+
+    /// The report of a simulation in which the strong team won each game
+    /// with probability `0.5 + edge`.
+    pub fn from_stats_with_edge(stats: &SimulationStats, edge: f64) -> Self {
+        let unit = if edge == 0.0 { 1.0 } else { edge };
+        let advantage = &stats.advantage;
+        let num_runs = advantage.count();
+        let seed_scores: Vec<_> = stats
+            .seed_scores
+            .iter()
+            .map(|score| score / num_runs.max(1) as f64)
+            .collect();
+        Self {
+            num_runs,
+            edge,
+            advantage: advantage.mean() / unit,
+            ci_half_width: if num_runs < 2 {
+                f64::INFINITY
+            } else {
+                Z_95 * advantage.std_error() / unit.abs()
+            },
+            tiebreak_fraction: stats.runs_with_tiebreak as f64 / num_runs.max(1) as f64,
+            max_tiebreak_depth: stats.max_tiebreak_depth,
+            rounds_survived: stats.rounds_survived.mean(),
+            mean_rank: stats.final_rank.mean(),
+            rank_std_dev: stats.final_rank.variance().sqrt(),
+            upsets: stats.upsets.clone(),
+            mean_score: stats.strong_team_score.mean(),
+            median_score: stats.strong_team_score.quantile(0.5),
+            p90_score: stats.strong_team_score.quantile(0.9),
+            negative_score_fraction: stats.strong_team_score.fraction_below(0.0),
+            mean_other_score: stats.other_teams_score.mean(),
+            strong_team_positions: stats
+                .strong_team_positions
+                .iter()
+                .map(|counts| {
+                    counts
+                        .iter()
+                        .map(|count| *count as f64 / num_runs.max(1) as f64)
+                        .collect()
+                })
+                .collect(),
+            seed_scores: seed_scores.clone(),
+            seed_gini: gini(&seed_scores),
+        }
+    }
+
+    /// The number of decimals justified by the confidence interval, i.e.
+    /// the position of the first significant digit of its half width.
+    pub fn decimals(&self) -> usize {
+        if !self.ci_half_width.is_finite() || self.ci_half_width <= 0.0 {
+            return DEFAULT_DECIMALS;
+        }
+        let exponent = self.ci_half_width.log10().floor();
+        if exponent >= 0.0 {
+            0
+        } else {
+            (-exponent as usize).min(MAX_DECIMALS)
+        }
+    }
+
+    /// Whether the confidence interval contains zero, so that the sign of
+    /// the advantage is not resolved by the simulation.
+    pub fn is_noise_dominated(&self) -> bool {
+        self.ci_half_width >= self.advantage.abs()
+    }
+}
+
+impl fmt::Display for AdvantageReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let decimals = self.decimals();
+        write!(
+            f,
+            "{:.*} ± {:.*}",
+            decimals, self.advantage, decimals, self.ci_half_width
+        )?;
+        if self.edge == 0.0 {
+            write!(f, " (score difference, no edge)")?;
+        }
+        Ok(())
+    }
+}
+
+/// The advantage within the runs in which the strong team reached a
+/// component, whose number is the number of runs of `advantage`.
+#[derive(Clone, Debug, Serialize)]
+pub struct ConditionalAdvantageReport {
+    /// The number of all runs.
+    pub num_runs: u64,
+    /// The fraction of all runs in which the strong team reached the component.
+    pub probability: f64,
+    pub advantage: AdvantageReport,
+}
+
+impl ConditionalAdvantageReport {
+    pub fn new(num_runs: u64, advantage: AdvantageReport) -> Self {
+        Self {
+            num_runs,
+            probability: advantage.num_runs as f64 / num_runs.max(1) as f64,
+            advantage,
+        }
+    }
+
+    /// The half width of the 95% confidence interval of the probability
+    /// to reach the component.
+    pub fn probability_ci_half_width(&self) -> f64 {
+        let p = self.probability;
+        Z_95 * (p * (1.0 - p) / self.num_runs.max(1) as f64).sqrt()
+    }
+}
+
+impl fmt::Display for ConditionalAdvantageReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} (reached in {:.1}% ± {:.1}% of runs)",
+            self.advantage,
+            100.0 * self.probability,
+            100.0 * self.probability_ci_half_width()
+        )
+    }
+}
+
+/// A paired test of whether one format amplifies the edge of the strong
+/// team more than another, from the difference between the advantages of
+/// the two formats in runs with the same random numbers.
+#[derive(Clone, Copy, Debug, Serialize)]
+pub struct PairedComparison {
+    pub num_runs: u64,
+    /// The mean of the differences, in units of the edge.
+    pub mean_difference: f64,
+    pub std_error: f64,
+    /// The one-sided p-value of the hypothesis that the first format does
+    /// not amplify the edge more than the second. With many runs, the
+    /// t-distribution of the mean is approximated by a normal distribution.
+    pub p_value: f64,
+}
+
+impl PairedComparison {
+    pub fn from_differences(differences: &RunningStats) -> Self {
+        let mean_difference = differences.mean();
+        let std_error = differences.std_error();
+        let p_value = if std_error > 0.0 {
+            normal_upper_tail(mean_difference / std_error)
+        } else if mean_difference > 0.0 {
+            0.0
+        } else {
+            1.0
+        };
+        Self {
+            num_runs: differences.count(),
+            mean_difference,
+            std_error,
+            p_value,
+        }
+    }
+}
+
+impl fmt::Display for PairedComparison {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{:+.3} ± {:.3} (standard error over {} paired runs), p = {:.4}",
+            self.mean_difference, self.std_error, self.num_runs, self.p_value
+        )
+    }
+}
+
+/// The spread of the advantage between independent repetitions of a simulation.
+#[derive(Clone, Copy, Debug, Serialize)]
+pub struct ReplicateSummary {
+    pub num_replicates: u64,
+    pub mean: f64,
+    pub std_dev: f64,
+    pub min: f64,
+    pub max: f64,
+}
+
+impl ReplicateSummary {
+    pub fn from_reports(reports: &[AdvantageReport]) -> Self {
+        let advantages = || reports.iter().map(|report| report.advantage);
+        let stats: RunningStats = advantages().sum();
+        Self {
+            num_replicates: stats.count(),
+            mean: stats.mean(),
+            std_dev: stats.variance().sqrt(),
+            min: advantages().fold(f64::INFINITY, f64::min),
+            max: advantages().fold(f64::NEG_INFINITY, f64::max),
+        }
+    }
+}
+
+impl fmt::Display for ReplicateSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{:.3} ± {:.3} (standard deviation over {} repeats, range {:.3} to {:.3})",
+            self.mean, self.std_dev, self.num_replicates, self.min, self.max
+        )
+    }
+}
+
+/// The advantage of a format as a multiple of the advantage of a baseline
+/// format, e.g. a single game.
+#[derive(Clone, Copy, Debug, Serialize)]
+pub struct RelativeAdvantage {
+    pub ratio: f64,
+    /// The 95% confidence interval of the ratio, propagated from the
+    /// intervals of both advantages as if they were independent. With
+    /// common random numbers the two are positively correlated, so this
+    /// overestimates the uncertainty.
+    pub ci_half_width: f64,
+}
+
+impl RelativeAdvantage {
+    pub fn new(report: &AdvantageReport, baseline: &AdvantageReport) -> Self {
+        let ratio = report.advantage / baseline.advantage;
+        let relative_error = |report: &AdvantageReport| report.ci_half_width / report.advantage;
+        Self {
+            ratio,
+            ci_half_width: ratio.abs() * relative_error(report).hypot(relative_error(baseline)),
+        }
+    }
+}
+
+impl fmt::Display for RelativeAdvantage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:.2}× ± {:.2}", self.ratio, self.ci_half_width)
+    }
+}
+
+/// How often each seed wins the title if all teams are equal.
+#[derive(Clone, Debug, Serialize)]
+pub struct SeedTitleReport {
+    pub num_runs: u64,
+    pub probabilities: Vec<f64>,
+}
+
+impl SeedTitleReport {
+    /// The half width of the 95% confidence interval of the title
+    /// probability of a seed.
+    pub fn ci_half_width(&self, seed: usize) -> f64 {
+        let p = self.probabilities[seed];
+        Z_95 * (p * (1.0 - p) / self.num_runs as f64).sqrt()
+    }
+
+    /// The seeds whose title probability is not compatible with a fair
+    /// share of the titles.
+    pub fn biased_seeds(&self) -> Vec<usize> {
+        let fair = 1.0 / self.probabilities.len() as f64;
+        (0..self.probabilities.len())
+            .filter(|seed| (self.probabilities[*seed] - fair).abs() > self.ci_half_width(*seed))
+            .collect()
+    }
+}
+
+impl fmt::Display for SeedTitleReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let biased = self.biased_seeds();
+        write!(
+            f,
+            "Title probability by seed if all teams are equal (fair share {:.3}):",
+            1.0 / self.probabilities.len() as f64
+        )?;
+        for (seed, p) in self.probabilities.iter().enumerate() {
+            write!(
+                f,
+                "\n  seed {}: {:.3} ± {:.3}",
+                seed,
+                p,
+                self.ci_half_width(seed)
+            )?;
+            if biased.contains(&seed) {
+                write!(f, " *")?;
+            }
+        }
+        if !biased.is_empty() {
+            write!(f, "\n  (* not compatible with a fair share)")?;
+        }
+        Ok(())
+    }
+}
+
+/// The expected payout when the scores of the placements are prize money.
+#[derive(Clone, Debug, Serialize)]
+pub struct PayoutReport {
+    /// The sum of the payouts of all placements.
+    pub total: f64,
+    pub strong_team: f64,
+    pub average_team: f64,
+    pub currency: Option<String>,
+}
+
+impl PayoutReport {
+    pub fn new(total: f64, num_teams: usize, report: &AdvantageReport) -> Self {
+        Self {
+            total,
+            strong_team: report.mean_score,
+            average_team: total / num_teams as f64,
+            currency: None,
+        }
+    }
+
+    pub fn with_currency(self, currency: Option<String>) -> Self {
+        Self { currency, ..self }
+    }
+
+    /// How many times the payout of an average team the strong team earns.
+    pub fn ratio(&self) -> f64 {
+        self.strong_team / self.average_team
+    }
+
+    fn money(&self, amount: f64) -> String {
+        let cents = (amount * 100.0).round() as i64;
+        let digits = (cents.abs() / 100).to_string();
+        let mut grouped = String::new();
+        for (i, digit) in digits.chars().enumerate() {
+            if i > 0 && (digits.len() - i).is_multiple_of(3) {
+                grouped.push(',');
+            }
+            grouped.push(digit);
+        }
+        let sign = if cents < 0 { "-" } else { "" };
+        let amount = format!("{}{}.{:02}", sign, grouped, cents.abs() % 100);
+        match &self.currency {
+            Some(currency) => format!("{} {}", amount, currency),
+            None => amount,
+        }
+    }
+}
+
+impl fmt::Display for PayoutReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Total payout: {}", self.money(self.total))?;
+        writeln!(
+            f,
+            "Expected payout to the strong team: {}",
+            self.money(self.strong_team)
+        )?;
+        writeln!(
+            f,
+            "Expected payout to an average team: {}",
+            self.money(self.average_team)
+        )?;
+        write!(f, "Ratio: {:.3}", self.ratio())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::AdvantageReport;
+    use super::PayoutReport;
+    use super::RelativeAdvantage;
+    use super::ReplicateSummary;
+    use super::RunResult;
+    use super::SeedTitleReport;
+    use super::SimulationStats;
+    use super::UpsetCount;
+
+    #[test]
+    fn upsets_are_merged_per_component() {
+        let count = |matches, upsets| UpsetCount { matches, upsets };
+        let run = |upsets: &[UpsetCount]| {
+            let mut stats = SimulationStats::default();
+            stats.push_run(&RunResult {
+                upsets,
+                ..Default::default()
+            });
+            stats
+        };
+        let first = run(&[count(1, 0), count(3, 1)]);
+        let second = run(&[count(1, 1), count(3, 2)]);
+        let merged = SimulationStats::default()
+            .merge(first.clone())
+            .merge(second);
+        assert_eq!(merged.upsets, vec![count(2, 1), count(6, 3)]);
+        let mut pushed = first;
+        pushed.push_run(&RunResult {
+            upsets: &[count(1, 1), count(3, 2)],
+            ..Default::default()
+        });
+        assert_eq!(pushed.upsets, merged.upsets);
+        assert_eq!(merged.upsets[0].rate(), Some(0.5));
+        assert_eq!(UpsetCount::default().rate(), None);
+    }
+
+    #[test]
+    fn strong_team_positions_are_counted_per_component() {
+        let mut merged = SimulationStats::default();
+        for positions in [
+            [Some(0), Some(2)],
+            [Some(1), None],
+            [Some(0), Some(0)],
+            [Some(0), None],
+        ] {
+            merged.push_run(&RunResult {
+                strong_team_positions: &positions,
+                ..Default::default()
+            });
+        }
+        assert_eq!(
+            merged.strong_team_positions,
+            vec![vec![3, 1], vec![1, 0, 1]]
+        );
+        let report = AdvantageReport::from_stats(&merged);
+        assert_eq!(
+            report.strong_team_positions,
+            vec![vec![0.75, 0.25], vec![0.25, 0.0, 0.25]]
+        );
+    }
+
+    #[test]
+    fn payout_formatting() {
+        let payout = PayoutReport {
+            total: 1234567.891,
+            strong_team: 500.0,
+            average_team: 250.0,
+            currency: Some("USD".into()),
+        };
+        assert_eq!(payout.money(payout.total), "1,234,567.89 USD");
+        assert_eq!(payout.money(-999.5), "-999.50 USD");
+        assert_eq!(payout.ratio(), 2.0);
+        assert_eq!(payout.with_currency(None).money(12.0), "12.00");
+    }
+
+    #[test]
+    fn advantage_is_rounded_to_the_confidence_interval() {
+        let report = |advantage, ci_half_width| AdvantageReport {
+            advantage,
+            ci_half_width,
+            ..AdvantageReport::from_stats(&SimulationStats::default())
+        };
+        assert_eq!(report(1.23456, 0.0345).to_string(), "1.23 ± 0.03");
+        assert_eq!(report(1.23456, 0.00345).to_string(), "1.235 ± 0.003");
+        assert_eq!(report(12.3456, 2.5).to_string(), "12 ± 2");
+        assert_eq!(report(1.23456, f64::INFINITY).to_string(), "1.235 ± inf");
+        assert!(report(0.01, 0.02).is_noise_dominated());
+        assert!(!report(1.0, 0.02).is_noise_dominated());
+    }
+
+    #[test]
+    fn without_an_edge_the_raw_advantage_is_reported() {
+        let mut stats = SimulationStats::default();
+        for advantage in [0.25, 0.75] {
+            stats.push_run(&RunResult {
+                advantage,
+                ..Default::default()
+            });
+        }
+        let report = AdvantageReport::from_stats_with_edge(&stats, 0.0);
+        assert_eq!(report.advantage, 0.5);
+        assert!(report.ci_half_width.is_finite());
+        assert!(report.to_string().ends_with("(score difference, no edge)"));
+    }
+
+    #[test]
+    fn biased_seeds_are_marked() {
+        let report = SeedTitleReport {
+            num_runs: 10000,
+            probabilities: vec![0.3, 0.25, 0.25, 0.2],
+        };
+        assert_eq!(report.biased_seeds(), vec![0, 3]);
+        assert!(report.to_string().contains("seed 0: 0.300 ± 0.009 *"));
+        assert!(report.to_string().contains("seed 1: 0.250 ± 0.008\n"));
+    }
+
+    #[test]
+    fn replicate_summary() {
+        let reports: Vec<_> = [1.0, 2.0, 3.0]
+            .into_iter()
+            .map(|advantage| AdvantageReport {
+                advantage,
+                ..AdvantageReport::from_stats(&SimulationStats::default())
+            })
+            .collect();
+        let summary = ReplicateSummary::from_reports(&reports);
+        assert_eq!(summary.num_replicates, 3);
+        assert_eq!((summary.mean, summary.std_dev), (2.0, 1.0));
+        assert_eq!((summary.min, summary.max), (1.0, 3.0));
+    }
+
+    #[test]
+    fn advantage_relative_to_a_baseline() {
+        let report = |advantage, ci_half_width| AdvantageReport {
+            advantage,
+            ci_half_width,
+            ..AdvantageReport::from_stats(&SimulationStats::default())
+        };
+        // Relative errors of 3% and 4% add up to 5%.
+        let relative = RelativeAdvantage::new(&report(2.0, 0.06), &report(1.0, 0.04));
+        assert!((relative.ratio - 2.0).abs() < 1e-12);
+        assert!((relative.ci_half_width - 0.1).abs() < 1e-12);
+        assert_eq!(relative.to_string(), "2.00× ± 0.10");
+    }
+}
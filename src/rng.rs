@@ -0,0 +1,52 @@
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+
+pub type SimRng = StdRng;
+
+/// The stream used to pick the strong team of a run. Component streams are
+/// derived from the component names, see [`name_stream`].
+pub const TEAMS_STREAM: u64 = 0;
+/// The stream used to draw the ratings of a run from a rating distribution.
+pub const RATINGS_STREAM: u64 = 1;
+/// The stream of the check that every team can win.
+pub const SANITY_CHECK_STREAM: u64 = 2;
+
+/// Derives the seed of an independent substream from the seed of a run
+/// (SplitMix64 finalizer).
+pub fn derive_seed(seed: u64, stream: u64) -> u64 {
+    let mut z = seed.wrapping_add(stream.wrapping_add(1).wrapping_mul(0x9E3779B97F4A7C15));
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58476D1CE4E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D049BB133111EB);
+    z ^ (z >> 31)
+}
+
+pub fn stream_rng(seed: u64, stream: u64) -> SimRng {
+    SimRng::seed_from_u64(derive_seed(seed, stream))
+}
+
+/// A stream identifier derived from a name (FNV-1a), so that the stream of a
+/// component does not depend on its position in the tournament. Adding a
+/// component to a tournament therefore leaves the random numbers drawn by all
+/// other components untouched.
+pub fn name_stream(name: &str) -> u64 {
+    name.bytes().fold(0xcbf29ce484222325, |hash, byte| {
+        (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::Rng;
+
+    use super::name_stream;
+    use super::stream_rng;
+
+    #[test]
+    fn streams_are_reproducible_and_distinct() {
+        let draw = |seed, stream| stream_rng(seed, stream).gen::<u64>();
+        assert_eq!(draw(1, 2), draw(1, 2));
+        assert_ne!(draw(1, 2), draw(1, 3));
+        assert_ne!(draw(1, 2), draw(2, 2));
+        assert_ne!(name_stream("final"), name_stream("semifinal"));
+    }
+}
@@ -1,107 +1,987 @@
-use rand::rngs::ThreadRng;
+use std::collections::HashSet;
 
+use itertools::Itertools;
+use rand::seq::SliceRandom;
+
+use crate::component::is_loser_placement;
 use crate::component::Component;
+use crate::component::MatchObserver;
+use crate::error::TournamentError;
+use crate::model::MatchModel;
+use crate::model::RatingDistribution;
+use crate::report::UpsetCount;
+use crate::rng::name_stream;
+use crate::rng::stream_rng;
+use crate::rng::RATINGS_STREAM;
+use crate::ComponentName;
 use crate::Placement;
 use crate::Score;
 use crate::ScoreResult;
 use crate::Team;
 use crate::TeamIdentifier;
 use crate::Tournament;
+use crate::BASE_RATING;
+
+/// Is called with the name of the component, the two teams of each decided
+/// match in the order in which they were paired, and the winner.
+pub type TournamentObserver<'a> = dyn FnMut(&str, Team, Team, Team) + 'a;
 
 #[derive(Debug, Clone)]
 pub struct Runner {
     placements: Vec<Vec<Team>>,
     components: Vec<Component<Placement>>,
     scoring: Vec<(Placement, Score)>,
+    /// The sum of the scores of all placements, which every run hands out
+    /// in full, so that it does not have to be summed per run.
+    total_score: Score,
+    model: MatchModel,
+    /// The name of each component.
+    names: Vec<ComponentName>,
+    /// The random number stream of each component, see [`name_stream`].
+    streams: Vec<u64>,
+    /// The deepest tiebreak needed by any component in the last run.
+    tiebreak_depth: usize,
+    /// The number of components in the last run in which the strong team
+    /// played and did not finish last.
+    rounds_survived: usize,
+    /// The upsets in each component in the last run.
+    upsets: Vec<UpsetCount>,
+    /// The position of the strong team in each component in the last run,
+    /// if it played in it.
+    strong_team_positions: Vec<Option<usize>>,
+    /// A component in which the edge of the strong team differs from the
+    /// other components, together with the model used for it.
+    component_model: Option<(usize, MatchModel)>,
+    /// For each component, the placements that no later component and no
+    /// score refers to once it has run.
+    dead_after: Vec<Vec<usize>>,
+    /// Free the dead placements during a run instead of keeping all of them
+    /// until the next run.
+    drop_dead_placements: bool,
+    /// For each component, whether each of its incoming teams comes from a
+    /// loser placement.
+    loser_routes: Vec<Vec<bool>>,
+    /// Whether any component penalizes the teams that took a loser route,
+    /// which are only tracked if so.
+    penalizes_loser_routes: bool,
+    /// By team index, whether the team took a loser route so far in the
+    /// current run.
+    took_loser_route: Vec<bool>,
+    /// Draws the ratings of the teams at the start of every run.
+    rating_distribution: Option<RatingDistribution>,
+    /// The Elo ratings of the teams by index, fixed by the tournament or
+    /// drawn for the current run, on top of which the strong teams are
+    /// strong. Empty if the teams keep their own ratings.
+    ratings: Vec<f64>,
 }
 
 impl Runner {
-    fn named_placement_to_placement(tournament: &Tournament, team: &TeamIdentifier) -> Placement {
-        let (component_index, placement_index) = match team {
-            TeamIdentifier::Team(num) => (0, *num),
-            TeamIdentifier::FromPreviousComponent(placement, component) => {
-                let (index, component) = tournament
-                    .components
-                    .iter()
-                    .enumerate()
-                    .find(|(_, (comp_name, _))| &component == comp_name)
-                    .map(|(i, (_, component))| (i, component))
-                    .unwrap_or_else(|| panic!("Component not found: {}", component));
-                let placement_index = component.get_placement_index_from_placement_name(placement);
-                // The 0-index component is at position 1 in the placements vec because the first entry
-                // is the incoming teams.
-                (index + 1, placement_index)
+    fn named_placement_to_placement(
+        tournament: &Tournament,
+        team: &TeamIdentifier,
+    ) -> Result<Placement, TournamentError> {
+        // The 0-index component is at position 1 in the placements vec because the first entry
+        // is the incoming teams.
+        let (component, position) = tournament.resolve_team(team)?;
+        Ok(Placement {
+            position,
+            component,
+        })
+    }
+
+    /// Makes sure that every placement is consumed by at most one component
+    /// and that components only use placements of components that precede them.
+    fn validate_routing(
+        components: &[Component<Placement>],
+        names: &[&ComponentName],
+    ) -> Result<(), TournamentError> {
+        let mut used = HashSet::new();
+        for (index, (component, name)) in components.iter().zip(names).enumerate() {
+            for placement in component.teams.iter() {
+                if placement.component > index {
+                    return Err(TournamentError::InvalidRouting(format!(
+                        "Component {} uses a placement of a later component: {}",
+                        name,
+                        names[placement.component - 1]
+                    )));
+                }
+                if !used.insert(placement.clone()) {
+                    return Err(TournamentError::InvalidRouting(format!(
+                        "Placement {} used more than once (again in component {})",
+                        Self::describe_placement(placement, names),
+                        name
+                    )));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn describe_placement(placement: &Placement, names: &[&ComponentName]) -> String {
+        if placement.component == 0 {
+            format!("team {}", placement.position)
+        } else {
+            format!(
+                "position {} of {}",
+                placement.position,
+                names[placement.component - 1]
+            )
+        }
+    }
+
+    /// The placements, by index into `placements`, that are dead after each
+    /// component has run. Placements that are scored and the initial teams,
+    /// which determine the seed scores, are never dead.
+    fn dead_placements(
+        components: &[Component<Placement>],
+        scoring: &[(Placement, Score)],
+    ) -> Vec<Vec<usize>> {
+        let mut live: HashSet<_> = scoring
+            .iter()
+            .map(|(placement, _)| placement.component)
+            .collect();
+        live.insert(0);
+        let mut dead_after = vec![vec![]; components.len()];
+        // Going backwards, the first component seen to use a placement is its last use.
+        for (index, component) in components.iter().enumerate().rev() {
+            for placement in component.teams.iter() {
+                if live.insert(placement.component) {
+                    dead_after[index].push(placement.component);
+                }
+            }
+            if live.insert(index + 1) {
+                dead_after[index].push(index + 1);
             }
-        };
-        Placement {
-            position: placement_index,
-            component: component_index,
         }
+        dead_after
     }
 
-    pub fn new(tournament: Tournament) -> Self {
-        let components = tournament
+    /// Checks the tournament, including that every placement is consumed
+    /// at most once and only after its component has run.
+    pub fn new(tournament: Tournament) -> Result<Self, TournamentError> {
+        tournament.validate()?;
+        let (components, loser_routes): (Vec<_>, Vec<_>) = tournament
             .components
             .iter()
             .map(|(_, comp)| {
-                let teams = comp
+                let mut teams = comp
                     .teams
                     .iter()
-                    .map(|team| Self::named_placement_to_placement(&tournament, team))
-                    .collect();
-                Component {
-                    r#type: comp.r#type,
-                    teams,
+                    .map(|team| {
+                        let loser_route = matches!(
+                            team,
+                            TeamIdentifier::FromPreviousComponent(placement, _)
+                                if is_loser_placement(placement)
+                        );
+                        Ok((
+                            Self::named_placement_to_placement(&tournament, team)?,
+                            loser_route,
+                        ))
+                    })
+                    .collect::<Result<Vec<_>, TournamentError>>()?;
+                if comp.reseed {
+                    // Stable, so teams with the same position keep their listed order.
+                    teams.sort_by_key(|(placement, _)| placement.position);
                 }
+                let (teams, loser_routes) = teams.into_iter().unzip();
+                let component = Component {
+                    r#type: comp.r#type.clone(),
+                    teams,
+                    reseed: comp.reseed,
+                    matchups: comp.matchups.clone(),
+                    shared_upset_shift: comp.shared_upset_shift,
+                    loser_route_penalty: comp.loser_route_penalty,
+                };
+                Ok((component, loser_routes))
             })
-            .collect();
+            .collect::<Result<Vec<_>, TournamentError>>()?
+            .into_iter()
+            .unzip();
+        let names: Vec<_> = tournament.components.keys().collect();
+        Self::validate_routing(&components, &names)?;
 
         let scoring = tournament
             .scoring
             .iter()
             .map(|(team, score)| {
-                (
-                    Self::named_placement_to_placement(&tournament, team),
+                Ok((
+                    Self::named_placement_to_placement(&tournament, team)?,
                     *score,
-                )
+                ))
             })
-            .collect();
-        Self {
+            .collect::<Result<Vec<_>, TournamentError>>()?;
+        Ok(Self {
             placements: vec![],
+            penalizes_loser_routes: components
+                .iter()
+                .any(|component| component.loser_route_penalty > 0.0),
+            loser_routes,
+            took_loser_route: vec![],
+            rating_distribution: tournament.rating_distribution,
+            ratings: tournament.ratings.values().copied().collect(),
+            dead_after: Self::dead_placements(&components, &scoring),
+            drop_dead_placements: false,
             components,
+            total_score: tournament.total_score(),
             scoring,
+            model: MatchModel::new(tournament.noise, tournament.probability_policy)
+                .with_win_probabilities(tournament.win_probabilities.clone()),
+            streams: names.iter().map(|name| name_stream(name)).collect(),
+            names: names.into_iter().cloned().collect(),
+            tiebreak_depth: 0,
+            rounds_survived: 0,
+            upsets: vec![],
+            strong_team_positions: vec![],
+            component_model: None,
         }
+        .with_series_precomputed())
+    }
+
+    /// Runs each component with its own random number generator derived
+    /// from `seed` and the component's stream. Teams that a component
+    /// reports as tied are passed on in random order, so that whichever
+    /// position a downstream component pulls from a tie is a random one of
+    /// the tied teams.
+    fn run(&mut self, teams: Vec<Team>, seed: u64) {
+        self.run_observed(teams, seed, None)
     }
 
-    fn run(&mut self, teams: Vec<Team>, rng: &mut ThreadRng) {
+    fn run_observed(
+        &mut self,
+        mut teams: Vec<Team>,
+        seed: u64,
+        mut observer: Option<&mut TournamentObserver>,
+    ) {
+        self.placements.clear();
+        if let Some(distribution) = self.rating_distribution {
+            self.ratings = distribution.sample(teams.len(), &mut stream_rng(seed, RATINGS_STREAM));
+        }
+        for team in teams.iter_mut() {
+            if let Some(rating) = self.ratings.get(team.index) {
+                *team = team.with_rating(*rating);
+            }
+        }
+        if self.penalizes_loser_routes {
+            self.took_loser_route.clear();
+            self.took_loser_route.resize(teams.len(), false);
+        }
         self.placements.push(teams);
-        for component in self.components.iter() {
+        self.tiebreak_depth = 0;
+        self.rounds_survived = 0;
+        self.upsets.clear();
+        self.strong_team_positions.clear();
+        for (index, ((component, stream), name)) in self
+            .components
+            .iter()
+            .zip(self.streams.iter())
+            .zip(self.names.iter())
+            .enumerate()
+        {
             let mut teams_this_component: Vec<_> = component
                 .teams
                 .iter()
                 .map(|team| self.placements[team.component][team.position])
                 .collect();
-            component.run(&mut teams_this_component, rng);
+            let mut rng = stream_rng(seed, *stream);
+            let model = match &self.component_model {
+                Some((scaled, model)) if *scaled == index => model,
+                _ => &self.model,
+            };
+            if self.penalizes_loser_routes {
+                for (team, loser_route) in
+                    teams_this_component.iter().zip(&self.loser_routes[index])
+                {
+                    self.took_loser_route[team.index] |= *loser_route;
+                }
+            }
+            let penalized;
+            let model = if component.loser_route_penalty > 0.0 {
+                penalized = model.clone().with_penalized_teams(
+                    component.loser_route_penalty,
+                    self.took_loser_route.clone(),
+                );
+                &penalized
+            } else {
+                model
+            };
+            let mut observer = observer
+                .as_deref_mut()
+                .map(|observer| move |a, b, winner| observer(name, a, b, winner));
+            let outcome = component.run_observed(
+                &mut teams_this_component,
+                model,
+                &mut rng,
+                observer
+                    .as_mut()
+                    .map(|observer| observer as &mut MatchObserver),
+            );
+            for tie in outcome.ties {
+                teams_this_component[tie].shuffle(&mut rng);
+            }
+            self.tiebreak_depth = self.tiebreak_depth.max(outcome.tiebreak_depth);
+            self.upsets.push(UpsetCount {
+                matches: outcome.matches as u64,
+                upsets: outcome.upsets as u64,
+            });
+            self.strong_team_positions.push(
+                teams_this_component
+                    .iter()
+                    .position(|team| team.is_strong()),
+            );
+            if let Some((_, ahead)) = teams_this_component.split_last() {
+                if ahead.iter().any(|team| team.is_strong()) {
+                    self.rounds_survived += 1;
+                }
+            }
             self.placements.push(teams_this_component);
+            if self.drop_dead_placements {
+                for dead in self.dead_after[index].iter() {
+                    self.placements[*dead] = Vec::new();
+                }
+            }
+        }
+    }
+
+    /// Frees the placements of each component as soon as neither a later
+    /// component nor the scoring refers to them, which lowers the peak
+    /// memory of large tournaments with many stages. The results are the
+    /// same, but the placements of these components are not available after
+    /// the run.
+    pub fn with_dead_placements_dropped(self) -> Self {
+        Self {
+            drop_dead_placements: true,
+            ..self
+        }
+    }
+
+    /// Lets the strong team win each game with probability `0.5 + advantage`
+    /// instead of `0.5 + STRONG_TEAM_ADVANTAGE`, by scaling the rating that the
+    /// strong teams add to their own ratings.
+    pub fn with_strong_team_advantage(self, advantage: f64) -> Self {
+        Self {
+            model: self.model.with_strong_team_advantage(advantage),
+            ..self
+        }
+        .with_series_precomputed()
+    }
+
+    pub fn strong_team_advantage(&self) -> f64 {
+        self.model.strong_team_advantage()
+    }
+
+    /// Pulls every win probability towards 0.5 by the factor `1 - chaos`.
+    pub fn with_chaos(self, chaos: f64) -> Self {
+        Self {
+            model: self.model.with_chaos(chaos),
+            ..self
         }
+        .with_series_precomputed()
+    }
+
+    /// Gives the strong team no edge in the component with the given index,
+    /// counted in the order in which the components are run.
+    pub fn with_neutral_component(self, component: usize) -> Self {
+        self.with_scaled_component_edge(component, 0.0)
     }
 
-    pub fn get_score_result(&mut self, teams: Vec<Team>, rng: &mut ThreadRng) -> ScoreResult {
-        self.run(teams, rng);
-        self.scoring
+    /// Multiplies the edge of the strong team in the component with the
+    /// given index by `factor`.
+    pub fn with_scaled_component_edge(self, component: usize, factor: f64) -> Self {
+        let advantage = self.model.strong_team_advantage() * factor;
+        let model = self.model.clone().with_strong_team_advantage(advantage);
+        Self {
+            component_model: Some((component, model)),
+            ..self
+        }
+        .with_series_precomputed()
+    }
+
+    /// Computes the series win probabilities of the best-of series of all
+    /// components for the game win probabilities of the models, once for
+    /// all runs. Ratings that are drawn for every run give each run
+    /// different probabilities, so then nothing is computed in advance.
+    fn with_series_precomputed(mut self) -> Self {
+        if self.rating_distribution.is_some() {
+            return self;
+        }
+        let series: Vec<_> = self
+            .components
             .iter()
-            .map(|(placement, score)| {
-                let strong_team_score =
-                    if self.placements[placement.component][placement.position].strong {
-                        *score
-                    } else {
-                        0.0
-                    };
-                ScoreResult {
-                    all_teams: *score,
-                    strong_team: strong_team_score,
-                }
-            })
-            .sum()
+            .flat_map(|component| component.r#type.best_of_n_series())
+            .sorted()
+            .dedup()
+            .collect();
+        let ratings = if self.ratings.is_empty() {
+            let num_teams = self
+                .components
+                .iter()
+                .flat_map(|component| component.teams.iter())
+                .filter(|placement| placement.component == 0)
+                .map(|placement| placement.position + 1)
+                .max()
+                .unwrap_or_default();
+            vec![BASE_RATING; num_teams]
+        } else {
+            self.ratings.clone()
+        };
+        self.model = self.model.with_series_precomputed(&series, &ratings);
+        if let Some((component, model)) = self.component_model.take() {
+            self.component_model =
+                Some((component, model.with_series_precomputed(&series, &ratings)));
+        }
+        self
+    }
+
+    pub fn tiebreak_depth(&self) -> usize {
+        self.tiebreak_depth
+    }
+
+    pub fn rounds_survived(&self) -> usize {
+        self.rounds_survived
+    }
+
+    pub fn upsets(&self) -> &[UpsetCount] {
+        &self.upsets
+    }
+
+    pub fn strong_team_positions(&self) -> &[Option<usize>] {
+        &self.strong_team_positions
+    }
+
+    /// Runs the tournament and returns the team in the placement with the
+    /// highest score.
+    pub fn get_champion(&mut self, teams: Vec<Team>, seed: u64) -> Team {
+        self.run(teams, seed);
+        let (placement, _) = self
+            .scoring
+            .iter()
+            .max_by(|(_, score), (_, other)| score.total_cmp(other))
+            .expect("Validated tournaments score at least one placement");
+        self.placements[placement.component][placement.position]
+    }
+
+    pub fn get_score_result(&mut self, teams: Vec<Team>, seed: u64) -> ScoreResult {
+        self.run(teams, seed);
+        self.score_result()
+    }
+
+    /// Like [`Runner::get_score_result`], but calls `observer` on every
+    /// decided match of every component.
+    pub fn get_observed_score_result(
+        &mut self,
+        teams: Vec<Team>,
+        seed: u64,
+        observer: &mut TournamentObserver,
+    ) -> ScoreResult {
+        self.run_observed(teams, seed, Some(observer));
+        self.score_result()
+    }
+
+    fn score_result(&self) -> ScoreResult {
+        let num_teams = self.placements[0].len();
+        let num_strong = self.placements[0]
+            .iter()
+            .filter(|team| team.is_strong())
+            .count();
+        let mut scores = vec![0.0; num_teams];
+        for (placement, score) in self.scoring.iter() {
+            scores[self.placements[placement.component][placement.position].index] += score;
+        }
+        let strong_scores = || {
+            self.placements[0]
+                .iter()
+                .filter(|team| team.is_strong())
+                .map(|team| scores[team.index])
+        };
+        let strong_total: Score = strong_scores().sum();
+        let best_strong = strong_scores().fold(Score::NEG_INFINITY, Score::max);
+        ScoreResult {
+            strong_team: strong_total / num_strong.max(1) as Score,
+            other_teams: (self.total_score - strong_total)
+                / num_teams.saturating_sub(num_strong).max(1) as Score,
+            all_teams: self.total_score,
+            strong_team_rank: 1 + scores.iter().filter(|score| **score > best_strong).count(),
+            seed_scores: self.placements[0]
+                .iter()
+                .map(|team| scores[team.index])
+                .collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use rand::thread_rng;
+
+    use super::Runner;
+    use crate::component::is_loser_placement;
+    use crate::error::TournamentError;
+    use crate::simulation::get_teams;
+    use crate::teams;
+    use crate::STRONG_TEAM_ADVANTAGE;
+
+    fn final_placements(yaml: &str, seed: u64) -> Vec<usize> {
+        let mut runner = Runner::new(serde_yaml::from_str(yaml).unwrap()).unwrap();
+        let teams = teams(4, &[0]);
+        runner.run(teams, seed);
+        runner
+            .placements
+            .last()
+            .unwrap()
+            .iter()
+            .map(|team| team.index)
+            .collect()
+    }
+
+    #[test]
+    fn kept_ties_are_passed_on_in_random_order() {
+        let yaml = "
+components:
+  group:
+    type: !GroupStage {num_games_per_series: 1, draw_prob: 1.0, keep_ties: true}
+    teams: [0, 1, 2, 3]
+scoring:
+  [1st, group]: 1.0
+";
+        let winners: HashSet<_> = (0..100)
+            .map(|seed| final_placements(yaml, seed)[0])
+            .collect();
+        assert_eq!(winners.len(), 4);
+    }
+
+    #[test]
+    fn strong_teams_are_strong_on_top_of_their_ratings() {
+        let mean_score = |ratings: &str, advantage: f64| {
+            let runner = Runner::new(
+                serde_yaml::from_str(&format!(
+                    "
+ratings: {ratings}
+components:
+  final:
+    type: !BestOf1
+    teams: [0, 1]
+scoring:
+  [winner, final]: 1.0
+"
+                ))
+                .unwrap(),
+            )
+            .unwrap()
+            .with_strong_team_advantage(advantage);
+            (0..4000)
+                .map(|seed| {
+                    runner
+                        .clone()
+                        .get_score_result(teams(2, &[0]), seed)
+                        .strong_team
+                })
+                .sum::<f64>()
+                / 4000.0
+        };
+        assert!((mean_score("{a: 1700, b: 1700}", STRONG_TEAM_ADVANTAGE) - 0.6).abs() < 0.03);
+        assert!(mean_score("{a: 1000, b: 2000}", STRONG_TEAM_ADVANTAGE) < 0.03);
+        // A negative edge weakens the strong team without flipping its rating.
+        assert!((mean_score("{a: 1700, b: 1700}", -0.1) - 0.4).abs() < 0.03);
+        assert!(mean_score("{a: 1900, b: 1100}", -0.1) > 0.9);
+    }
+
+    #[test]
+    fn placements_are_routed_between_components() {
+        let yaml = "
+components:
+  first:
+    type: !Placebo
+    teams: [0, 1, 2]
+  second:
+    type: !Placebo
+    teams: [[position_3, first], 3, [position_1, first]]
+scoring:
+  [position_1, second]: 1.0
+";
+        assert_eq!(final_placements(yaml, 0), vec![2, 3, 0]);
+    }
+
+    #[test]
+    fn last_chance_qualifiers_are_routed_into_the_main_event() {
+        // The better seed always wins, so teams 0 and 1 qualify.
+        let yaml = "
+win_probabilities:
+  - [0.5, 1.0, 1.0, 1.0]
+  - [0.0, 0.5, 1.0, 1.0]
+  - [0.0, 0.0, 0.5, 1.0]
+  - [0.0, 0.0, 0.0, 0.5]
+components:
+  qualifier:
+    type: !LastChanceQualifier {num_games_per_series: 1, slots: 2}
+    teams: [0, 1, 2, 3]
+  main:
+    type: !Placebo
+    teams: [[qualifier_2, qualifier], [round_1_loser_1, qualifier], [qualifier_1, qualifier]]
+scoring:
+  [position_1, main]: 1.0
+";
+        assert_eq!(final_placements(yaml, 0), vec![1, 3, 0]);
+    }
+
+    #[test]
+    fn loser_routes_are_penalized_in_later_components() {
+        // All games are coin flips, except that the penalty decides the
+        // final for a team that lost the opener and then won the decider.
+        let mut runner = Runner::new(
+            serde_yaml::from_str(
+                "
+win_probabilities:
+  - [0.5, 0.5, 0.5, 0.5]
+  - [0.5, 0.5, 0.5, 0.5]
+  - [0.5, 0.5, 0.5, 0.5]
+  - [0.5, 0.5, 0.5, 0.5]
+components:
+  opener:
+    type: !BestOf1
+    teams: [0, 1]
+  decider:
+    type: !BestOf1
+    teams: [[loser, opener], 2]
+  final:
+    type: !BestOf1
+    teams: [[winner, decider], 3]
+    loser_route_penalty: 0.5
+scoring:
+  [winner, final]: 1.0
+",
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        let mut finals_of_the_opener_loser = 0;
+        for seed in 0..200 {
+            runner.run(get_teams(4, &mut thread_rng()), seed);
+            let opener_loser = runner.placements[1][1];
+            let finalist = runner.placements[2][0];
+            let winner = runner.placements[3][0];
+            if finalist == opener_loser {
+                finals_of_the_opener_loser += 1;
+                assert_eq!(winner.index, 3);
+            }
+        }
+        assert!(finals_of_the_opener_loser > 0);
+        assert!(is_loser_placement("round_2_loser_1"));
+        assert!(!is_loser_placement("winner"));
+    }
+
+    #[test]
+    fn invalid_routing_is_an_error() {
+        let error = |teams: &str| {
+            let yaml = format!(
+                "
+components:
+  first:
+    type: !Placebo
+    teams: [0, 1]
+  second:
+    type: !Placebo
+    teams: {teams}
+scoring:
+  [position_1, second]: 1.0
+"
+            );
+            Runner::new(serde_yaml::from_str(&yaml).unwrap()).unwrap_err()
+        };
+        assert!(matches!(
+            error("[[position_1, first], [position_1, first]]"),
+            TournamentError::InvalidRouting(_)
+        ));
+        assert!(matches!(
+            error("[[position_1, second], [position_2, first]]"),
+            TournamentError::InvalidRouting(_)
+        ));
+        assert_eq!(
+            error("[[position_1, third], [position_2, first]]"),
+            TournamentError::UnknownComponent("third".into())
+        );
+        assert_eq!(
+            error("[[position_3, first], [position_2, first]]"),
+            TournamentError::InvalidPlacement {
+                component: "first".into(),
+                placement: "position_3".into(),
+            }
+        );
+    }
+
+    #[test]
+    fn scores_of_all_teams_add_up_to_the_total() {
+        let runner = Runner::new(
+            serde_yaml::from_str(
+                "
+components:
+  group:
+    type: !GroupStage {num_games_per_series: 1}
+    teams: [0, 1, 2, 3]
+scoring:
+  [1st, group]: 3.0
+  [2nd, group]: 1.0
+  [4th, group]: -1.0
+",
+            )
+            .unwrap(),
+        )
+        .unwrap()
+        .with_strong_team_advantage(0.0);
+        for seed in 0..10 {
+            let results: Vec<_> = (0..4)
+                .map(|strong_team| {
+                    let teams = teams(4, &[strong_team]);
+                    runner.clone().get_score_result(teams, seed)
+                })
+                .collect();
+            // Without an edge, the outcome does not depend on the strong team.
+            let strong_teams: f64 = results.iter().map(|result| result.strong_team).sum();
+            assert_eq!(strong_teams, 3.0);
+            assert!(results.iter().all(|result| result.all_teams == 3.0));
+            let mut ranks: Vec<_> = results
+                .iter()
+                .map(|result| result.strong_team_rank)
+                .collect();
+            ranks.sort();
+            assert_eq!(ranks, vec![1, 2, 3, 4]);
+        }
+    }
+
+    #[test]
+    fn several_strong_teams_are_averaged_per_tier() {
+        let mut runner = Runner::new(
+            serde_yaml::from_str(
+                "
+components:
+  table:
+    type: !Placebo
+    teams: [0, 1, 2, 3]
+scoring:
+  [position_1, table]: 1.0
+  [position_2, table]: 0.5
+",
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        let teams = teams(4, &[0, 3]);
+        let result = runner.get_score_result(teams, 0);
+        assert_eq!(result.strong_team, 0.5);
+        assert_eq!(result.other_teams, 0.25);
+        assert_eq!(result.all_teams, 1.5);
+        assert_eq!(result.strong_team_rank, 1);
+    }
+
+    #[test]
+    fn rounds_survived() {
+        let runner = Runner::new(
+            serde_yaml::from_str(
+                "
+components:
+  semi:
+    type: !BestOf1
+    teams: [0, 1]
+  consolation:
+    type: !BestOf1
+    teams: [[loser, semi], 2]
+  final:
+    type: !BestOf1
+    teams: [[winner, semi], 3]
+scoring:
+  [winner, final]: 1.0
+",
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        for seed in 0..20 {
+            let teams = teams(4, &[0]);
+            let mut runner = runner.clone();
+            runner.run(teams, seed);
+            let won_semi = runner.placements[1][0].is_strong();
+            let won_final = runner.placements[3][0].is_strong();
+            let won_consolation = runner.placements[2][0].is_strong();
+            let expected = won_semi as usize + won_final as usize + won_consolation as usize;
+            assert_eq!(runner.rounds_survived(), expected);
+        }
+    }
+
+    #[test]
+    fn observer_sees_every_match() {
+        let mut runner = Runner::new(
+            serde_yaml::from_str(
+                "
+components:
+  semi:
+    type: !BestOf3
+    teams: [0, 1]
+  final:
+    type: !BestOf1
+    teams: [2, [winner, semi]]
+scoring:
+  [winner, final]: 1.0
+",
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        let teams: Vec<_> = teams(3, &[0]);
+        let mut matches = vec![];
+        let result =
+            runner.get_observed_score_result(teams.clone(), 0, &mut |component, a, b, winner| {
+                matches.push((component.to_owned(), a, b, winner))
+            });
+        assert_eq!(matches.len(), 2);
+        let (semi, final_) = (&matches[0], &matches[1]);
+        assert_eq!(
+            (semi.0.as_str(), semi.1, semi.2),
+            ("semi", teams[0], teams[1])
+        );
+        assert_eq!(
+            (final_.0.as_str(), final_.1, final_.2),
+            ("final", teams[2], semi.3)
+        );
+        assert_eq!(
+            result.strong_team,
+            if final_.3.is_strong() { 1.0 } else { 0.0 }
+        );
+        // Observing does not change the outcome.
+        assert_eq!(runner.get_champion(teams, 0), final_.3);
+    }
+
+    #[test]
+    fn reused_runner_matches_fresh_runner() {
+        let mut reused = Runner::new(
+            serde_yaml::from_str(
+                "
+components:
+  semi:
+    type: !BestOf1
+    teams: [0, 1]
+  final:
+    type: !BestOf1
+    teams: [[winner, semi], 2]
+scoring:
+  [winner, final]: 1.0
+",
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        let fresh = reused.clone();
+        for seed in 0..20 {
+            let teams: Vec<_> = teams(3, &[0]);
+            assert_eq!(
+                reused.get_champion(teams.clone(), seed),
+                fresh.clone().get_champion(teams, seed)
+            );
+        }
+    }
+
+    #[test]
+    fn dropping_dead_placements_keeps_the_results() {
+        let runner = Runner::new(
+            serde_yaml::from_str(
+                "
+components:
+  group:
+    type: !GroupStage {num_games_per_series: 1}
+    teams: [0, 1, 2, 3, 4, 5]
+  semi_1:
+    type: !BestOf3
+    teams: [[1st, group], [4th, group]]
+  semi_2:
+    type: !BestOf3
+    teams: [[2nd, group], [3rd, group]]
+  unused:
+    type: !BestOf1
+    teams: [[5th, group], [6th, group]]
+  final:
+    type: !BestOf5
+    teams: [[winner, semi_1], [winner, semi_2]]
+scoring:
+  [winner, final]: 3.0
+  [loser, final]: 1.0
+  [1st, group]: 0.5
+",
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        assert_eq!(
+            runner.dead_after,
+            vec![vec![], vec![], vec![], vec![4], vec![2, 3]]
+        );
+        let mut dropping = runner.clone().with_dead_placements_dropped();
+        for seed in 0..50 {
+            let teams: Vec<_> = teams(6, &[seed as usize % 6]);
+            assert_eq!(
+                runner.clone().get_score_result(teams.clone(), seed),
+                dropping.get_score_result(teams, seed)
+            );
+            assert!(dropping.placements[2].is_empty() && dropping.placements[4].is_empty());
+        }
+    }
+
+    #[test]
+    fn reseeding_orders_by_previous_position() {
+        let runner = Runner::new(
+            serde_yaml::from_str(
+                "
+components:
+  group_a:
+    type: !GroupStage {num_games_per_series: 1}
+    teams: [0, 1]
+  group_b:
+    type: !GroupStage {num_games_per_series: 1}
+    teams: [2, 3]
+  final:
+    type: !SingleElimination {num_games_per_series: 3}
+    teams: [[2nd, group_a], [1st, group_b], [1st, group_a], [2nd, group_b]]
+    reseed: true
+scoring:
+  [winner, final]: 1.0
+",
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        let positions: Vec<_> = runner.components[2]
+            .teams
+            .iter()
+            .map(|placement| (placement.component, placement.position))
+            .collect();
+        assert_eq!(positions, vec![(2, 0), (1, 0), (1, 1), (2, 1)]);
+    }
+
+    #[test]
+    fn adding_a_component_does_not_change_other_streams() {
+        let without = "
+components:
+  final:
+    type: !BestOfN 3
+    teams: [0, 1]
+scoring:
+  [winner, final]: 1.0
+";
+        let with = "
+components:
+  other:
+    type: !BestOfN 5
+    teams: [2, 3]
+  final:
+    type: !BestOfN 3
+    teams: [0, 1]
+scoring:
+  [winner, final]: 1.0
+";
+        for seed in 0..100 {
+            assert_eq!(
+                final_placements(without, seed),
+                final_placements(with, seed)
+            );
+        }
     }
 }
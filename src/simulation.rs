@@ -0,0 +1,841 @@
+use std::ops::Range;
+use std::time::Instant;
+
+use itertools::Itertools;
+use ordered_float::OrderedFloat;
+use rand::seq::IteratorRandom;
+use rand::Rng;
+use rand_distr::Distribution;
+use rand_distr::Normal;
+#[cfg(feature = "parallel")]
+use rayon::iter::ParallelIterator;
+#[cfg(feature = "parallel")]
+use rayon::prelude::IntoParallelIterator;
+
+use crate::report::AdvantageReport;
+use crate::report::ConditionalAdvantageReport;
+use crate::report::PairedComparison;
+use crate::report::RunResult;
+use crate::report::SimulationStats;
+use crate::rng::derive_seed;
+use crate::rng::stream_rng;
+use crate::rng::SimRng;
+use crate::rng::TEAMS_STREAM;
+use crate::runner::Runner;
+use crate::stats::RunningStats;
+use crate::ScoreResult;
+use crate::Team;
+
+pub const NUM_RUNS: usize = 1000000;
+/// Number of runs between two convergence checks in [`RunLength::Precision`]
+/// and between two checks of the deadline of a [`StopCondition`].
+const CHUNK_SIZE: usize = 10000;
+const MAX_RUNS_FOR_PRECISION: usize = 100000000;
+/// The number of consecutive runs that [`fold_runs`] accumulates in order
+/// on a single thread.
+const BATCH_SIZE: usize = 1000;
+/// The advantage of a strong team that wins every game.
+const CERTAIN_WIN_ADVANTAGE: f64 = 0.5;
+/// The relative change of the edge of the strong team in
+/// [`component_sensitivities`]. Large, so that the difference is not
+/// swamped by noise.
+pub const SENSITIVITY_STEP: f64 = 0.5;
+/// Without the `parallel` feature and without a given base seed, the seed
+/// of each run is derived from this seed, so that results are reproducible.
+#[cfg(not(feature = "parallel"))]
+const SEQUENTIAL_BASE_SEED: u64 = 0;
+
+/// How many runs to simulate.
+#[derive(Clone, Copy, Debug)]
+pub enum RunLength {
+    Fixed(usize),
+    /// Simulate until the half width of the confidence interval of the
+    /// advantage is below this value.
+    Precision(f64),
+}
+
+/// When to stop simulating: after the run length, or once the deadline has
+/// passed, whichever comes first. The deadline is only checked between
+/// chunks of runs and at least one chunk is always simulated, so that the
+/// result is an honest estimate from every run that was started.
+#[derive(Clone, Copy, Debug)]
+pub struct StopCondition {
+    pub length: RunLength,
+    pub deadline: Option<Instant>,
+}
+
+impl From<RunLength> for StopCondition {
+    fn from(length: RunLength) -> Self {
+        Self {
+            length,
+            deadline: None,
+        }
+    }
+}
+
+/// Teams with the base rating, except for one strong team at a random
+/// seed.
+pub fn get_teams(num: usize, rng: &mut impl Rng) -> Vec<Team> {
+    let mut teams: Vec<Team> = (0..num).map(Team::new).collect();
+    let team = teams.iter_mut().choose(rng).unwrap();
+    *team = Team::with_strength(team.index, true);
+    teams
+}
+
+/// Like [`get_teams`], but `num_strong` teams at distinct random seeds are
+/// strong.
+pub fn get_strong_teams(num: usize, num_strong: usize, rng: &mut impl Rng) -> Vec<Team> {
+    // Keeps the random numbers of simulations with a single strong team.
+    if num_strong == 1 {
+        return get_teams(num, rng);
+    }
+    let mut teams: Vec<Team> = (0..num).map(Team::new).collect();
+    for index in rand::seq::index::sample(rng, num, num_strong) {
+        teams[index] = Team::with_strength(index, true);
+    }
+    teams
+}
+
+/// The strong team is the top seed and each pair of adjacent seeds is
+/// swapped with probability `error_rate`, going from the top seed down,
+/// so that a team can drop by more than one position. Since the seeding has
+/// no effect when the strong team is placed at random, correct seeding means
+/// that the strong team is seed 0.
+pub fn get_misseeded_teams(num: usize, error_rate: f64, rng: &mut impl Rng) -> Vec<Team> {
+    let mut teams: Vec<Team> = (0..num)
+        .map(|index| Team::with_strength(index, index == 0))
+        .collect();
+    for i in 0..num.saturating_sub(1) {
+        if rng.gen_bool(error_rate) {
+            teams.swap(i, i + 1);
+        }
+    }
+    teams
+}
+
+/// The teams seeded by their rating plus normally distributed noise with
+/// standard deviation `noise`, as a committee with an imperfect estimate
+/// of their strength would seed them. The matches are still decided by
+/// the true ratings, since the teams keep their index.
+pub fn get_noisily_seeded_teams(ratings: &[f64], noise: f64, rng: &mut impl Rng) -> Vec<Team> {
+    let normal =
+        Normal::new(0.0, noise).unwrap_or_else(|_| panic!("Invalid seeding noise: {}", noise));
+    let estimated: Vec<_> = ratings
+        .iter()
+        .map(|rating| OrderedFloat(rating + normal.sample(rng)))
+        .collect();
+    (0..ratings.len())
+        .sorted_by_key(|index| std::cmp::Reverse(estimated[*index]))
+        .map(Team::new)
+        .collect()
+}
+
+/// The seed of run `run`. With a base seed, run `i` always uses the same
+/// randomness, so that two formats simulated with the same base seed are
+/// compared on correlated random draws. Components with the same name then
+/// see the same random numbers, see [`crate::rng::name_stream`].
+fn run_seed(base_seed: Option<u64>, run: usize) -> u64 {
+    match base_seed {
+        Some(base_seed) => derive_seed(base_seed, run as u64),
+        #[cfg(feature = "parallel")]
+        None => rand::thread_rng().gen(),
+        #[cfg(not(feature = "parallel"))]
+        None => derive_seed(SEQUENTIAL_BASE_SEED, run as u64),
+    }
+}
+
+/// The base seed of replicate `replicate` of a simulation that is repeated
+/// with independent seeds.
+pub fn replicate_base_seed(base_seed: Option<u64>, replicate: usize) -> Option<u64> {
+    match base_seed {
+        Some(base_seed) => Some(derive_seed(base_seed, replicate as u64)),
+        #[cfg(feature = "parallel")]
+        None => None,
+        #[cfg(not(feature = "parallel"))]
+        None => Some(derive_seed(SEQUENTIAL_BASE_SEED, replicate as u64)),
+    }
+}
+
+/// Accumulates all runs, in parallel if the `parallel` feature is enabled.
+/// The runs are split into batches of [`BATCH_SIZE`] consecutive runs, each
+/// of which reuses one clone of the runner and adds every run to one
+/// accumulator in place. The batches are merged in order, so that
+/// the result is the same for every number of threads and with or without
+/// the `parallel` feature.
+fn fold_runs<T: Send>(
+    runs: Range<usize>,
+    runner: &Runner,
+    accumulate: impl Fn(&mut T, &mut Runner, usize) + Sync + Send,
+    identity: impl Fn() -> T + Sync + Send,
+    merge: impl Fn(T, T) -> T + Sync + Send,
+) -> T {
+    let end = runs.end;
+    let batch = |start: usize| {
+        let mut runner = runner.clone();
+        let mut acc = identity();
+        for run in start..(start + BATCH_SIZE).min(end) {
+            accumulate(&mut acc, &mut runner, run);
+        }
+        acc
+    };
+    let starts: Vec<_> = runs.step_by(BATCH_SIZE).collect();
+    #[cfg(feature = "parallel")]
+    let batches: Vec<T> = starts.into_par_iter().map(batch).collect();
+    #[cfg(not(feature = "parallel"))]
+    let batches: Vec<T> = starts.into_iter().map(batch).collect();
+    batches.into_iter().fold(identity(), merge)
+}
+
+pub fn simulate(
+    runner: &Runner,
+    num_teams: usize,
+    runs: Range<usize>,
+    base_seed: Option<u64>,
+    get_teams: &(impl Fn(&mut SimRng) -> Vec<Team> + Sync),
+) -> SimulationStats {
+    let accumulate = |stats: &mut SimulationStats, runner: &mut Runner, run| {
+        let score = simulate_run(runner, run_seed(base_seed, run), get_teams);
+        add_run(stats, runner, &score, num_teams);
+    };
+    fold_runs(
+        runs,
+        runner,
+        accumulate,
+        SimulationStats::default,
+        SimulationStats::merge,
+    )
+}
+
+/// Plays the run with the given seed.
+fn simulate_run(
+    runner: &mut Runner,
+    seed: u64,
+    get_teams: &impl Fn(&mut SimRng) -> Vec<Team>,
+) -> ScoreResult {
+    let teams = get_teams(&mut stream_rng(seed, TEAMS_STREAM));
+    runner.get_score_result(teams, seed)
+}
+
+/// Adds the run that `runner` just played, with the given score, to `stats`.
+fn add_run(stats: &mut SimulationStats, runner: &Runner, score: &ScoreResult, num_teams: usize) {
+    stats.push_run(&RunResult {
+        strong_team_score: score.strong_team,
+        other_teams_score: score.other_teams,
+        advantage: score.strong_team - score.all_teams / num_teams as f64,
+        tiebreak_depth: runner.tiebreak_depth(),
+        rounds_survived: runner.rounds_survived(),
+        final_rank: score.strong_team_rank,
+        upsets: runner.upsets(),
+        strong_team_positions: runner.strong_team_positions(),
+        seed_scores: &score.seed_scores,
+    });
+}
+
+/// Simulates both formats, each given by its runner and number of teams,
+/// with the same seed in every run and compares the per-run advantages in
+/// units of the edge in the first runner. Both get their teams with
+/// [`get_strong_teams`].
+pub fn compare_advantages(
+    (runner, num_teams): (&Runner, usize),
+    (other, other_num_teams): (&Runner, usize),
+    num_strong: usize,
+    num_runs: usize,
+    base_seed: Option<u64>,
+) -> PairedComparison {
+    let edge = runner.strong_team_advantage();
+    let unit = if edge == 0.0 { 1.0 } else { edge };
+    let advantage = |runner: &mut Runner, num_teams: usize, seed: u64| {
+        let teams = get_strong_teams(num_teams, num_strong, &mut stream_rng(seed, TEAMS_STREAM));
+        let score = runner.get_score_result(teams, seed);
+        (score.strong_team - score.all_teams / num_teams as f64) / unit
+    };
+    // The runner of the other format is cloned once per batch along with
+    // the accumulator.
+    let accumulate =
+        |(differences, other): &mut (RunningStats, Runner), runner: &mut Runner, run| {
+            let seed = run_seed(base_seed, run);
+            differences
+                .push(advantage(runner, num_teams, seed) - advantage(other, other_num_teams, seed));
+        };
+    let (differences, _) = fold_runs(
+        0..num_runs,
+        runner,
+        accumulate,
+        || (RunningStats::default(), other.clone()),
+        |(a, other), (b, _)| (a.merge(b), other),
+    );
+    PairedComparison::from_differences(&differences)
+}
+
+/// The advantage within the runs in which the strong team played in the
+/// component with the given index, which isolates the effect of that
+/// component and the later ones from the filtering by the earlier ones.
+pub fn estimate_conditional_advantage(
+    runner: &Runner,
+    num_teams: usize,
+    component: usize,
+    num_runs: usize,
+    base_seed: Option<u64>,
+    get_teams: impl Fn(&mut SimRng) -> Vec<Team> + Sync,
+) -> ConditionalAdvantageReport {
+    let accumulate = |stats: &mut SimulationStats, runner: &mut Runner, run| {
+        let score = simulate_run(runner, run_seed(base_seed, run), &get_teams);
+        if runner.strong_team_positions()[component].is_some() {
+            add_run(stats, runner, &score, num_teams);
+        }
+    };
+    let stats = fold_runs(
+        0..num_runs,
+        runner,
+        accumulate,
+        SimulationStats::default,
+        SimulationStats::merge,
+    );
+    ConditionalAdvantageReport::new(
+        num_runs as u64,
+        AdvantageReport::from_stats_with_edge(&stats, runner.strong_team_advantage()),
+    )
+}
+
+/// The probability of each team, in the order of the team numbers, to end
+/// up in the placement with the highest score.
+pub fn title_probabilities(
+    runner: &Runner,
+    num_teams: usize,
+    num_runs: usize,
+    base_seed: Option<u64>,
+) -> Vec<f64> {
+    let teams: Vec<_> = (0..num_teams).map(Team::new).collect();
+    seeded_title_probabilities(runner, num_teams, num_runs, base_seed, |_| teams.clone())
+}
+
+/// The probability of each seed to end up in the placement with the
+/// highest score if every game is a coin flip, which is the same for all
+/// seeds in a format without a structural bias.
+pub fn equal_teams_title_probabilities(
+    runner: &Runner,
+    num_teams: usize,
+    num_runs: usize,
+    base_seed: Option<u64>,
+) -> Vec<f64> {
+    let runner = runner.clone().with_chaos(1.0);
+    title_probabilities(&runner, num_teams, num_runs, base_seed)
+}
+
+/// Like [`title_probabilities`], but the teams are put into the seeds in
+/// the order returned by `get_teams`.
+pub fn seeded_title_probabilities(
+    runner: &Runner,
+    num_teams: usize,
+    num_runs: usize,
+    base_seed: Option<u64>,
+    get_teams: impl Fn(&mut SimRng) -> Vec<Team> + Sync,
+) -> Vec<f64> {
+    let accumulate = |titles: &mut Vec<u64>, runner: &mut Runner, run| {
+        let seed = run_seed(base_seed, run);
+        let teams = get_teams(&mut stream_rng(seed, TEAMS_STREAM));
+        titles[runner.get_champion(teams, seed).index] += 1;
+    };
+    let titles = fold_runs(
+        0..num_runs,
+        runner,
+        accumulate,
+        || vec![0; num_teams],
+        |a, b| a.iter().zip(b).map(|(a, b)| a + b).collect(),
+    );
+    titles
+        .into_iter()
+        .map(|num_titles| num_titles as f64 / num_runs as f64)
+        .collect()
+}
+
+/// Simulates in chunks until the run length is reached or the deadline has
+/// passed. With a precision, the run length is reached once the half width
+/// of the confidence interval of the advantage drops below it.
+pub fn simulate_in_chunks(
+    runner: &Runner,
+    num_teams: usize,
+    stop: StopCondition,
+    base_seed: Option<u64>,
+    get_teams: &(impl Fn(&mut SimRng) -> Vec<Team> + Sync),
+) -> SimulationStats {
+    let max_runs = match stop.length {
+        RunLength::Fixed(num_runs) => num_runs,
+        RunLength::Precision(_) => MAX_RUNS_FOR_PRECISION,
+    };
+    let mut stats = SimulationStats::default();
+    while (stats.advantage.count() as usize) < max_runs {
+        let first_run = stats.advantage.count() as usize;
+        let runs = first_run..(first_run + CHUNK_SIZE).min(max_runs);
+        let chunk = simulate(runner, num_teams, runs, base_seed, get_teams);
+        stats = stats.merge(chunk);
+        if let RunLength::Precision(precision) = stop.length {
+            let report =
+                AdvantageReport::from_stats_with_edge(&stats, runner.strong_team_advantage());
+            if report.ci_half_width < precision {
+                return stats;
+            }
+        }
+        if stop
+            .deadline
+            .is_some_and(|deadline| Instant::now() >= deadline)
+        {
+            return stats;
+        }
+    }
+    if let RunLength::Precision(precision) = stop.length {
+        eprintln!(
+            "Warning: precision {} not reached after {} runs",
+            precision, MAX_RUNS_FOR_PRECISION
+        );
+    }
+    stats
+}
+
+/// The advantage in units of the edge of the strong team in the runner.
+pub fn estimate_advantage(
+    runner: &Runner,
+    num_teams: usize,
+    stop: impl Into<StopCondition>,
+    base_seed: Option<u64>,
+    get_teams: impl Fn(&mut SimRng) -> Vec<Team> + Sync,
+) -> AdvantageReport {
+    let edge = runner.strong_team_advantage();
+    estimate_advantage_in_units_of(runner, num_teams, stop.into(), base_seed, get_teams, edge)
+}
+
+fn estimate_advantage_in_units_of(
+    runner: &Runner,
+    num_teams: usize,
+    stop: StopCondition,
+    base_seed: Option<u64>,
+    get_teams: impl Fn(&mut SimRng) -> Vec<Team> + Sync,
+    edge: f64,
+) -> AdvantageReport {
+    let stats = match stop {
+        StopCondition {
+            length: RunLength::Fixed(num_runs),
+            deadline: None,
+        } => simulate(runner, num_teams, 0..num_runs, base_seed, &get_teams),
+        stop => simulate_in_chunks(runner, num_teams, stop, base_seed, &get_teams),
+    };
+    AdvantageReport::from_stats_with_edge(&stats, edge)
+}
+
+/// The advantage of a strong team that wins every game, or loses every
+/// game if its edge is negative, which is the largest advantage the format
+/// can give to any team. It is reported in the same units as
+/// [`estimate_advantage`], so the two can be compared.
+pub fn estimate_maximum_advantage(
+    runner: &Runner,
+    num_teams: usize,
+    stop: impl Into<StopCondition>,
+    base_seed: Option<u64>,
+    get_teams: impl Fn(&mut SimRng) -> Vec<Team> + Sync,
+) -> AdvantageReport {
+    let edge = runner.strong_team_advantage();
+    let runner = runner
+        .clone()
+        .with_strong_team_advantage(CERTAIN_WIN_ADVANTAGE.copysign(edge));
+    estimate_advantage_in_units_of(&runner, num_teams, stop.into(), base_seed, get_teams, edge)
+}
+
+/// The advantage over a fixed number of runs with a given base seed, so
+/// that simulations of variations of a format share their random numbers.
+fn seeded_advantage(
+    runner: &Runner,
+    num_teams: usize,
+    num_runs: usize,
+    base_seed: u64,
+    get_teams: &(impl Fn(&mut SimRng) -> Vec<Team> + Sync),
+) -> f64 {
+    estimate_advantage(
+        runner,
+        num_teams,
+        RunLength::Fixed(num_runs),
+        Some(base_seed),
+        get_teams,
+    )
+    .advantage
+}
+
+/// The contribution of each component to the advantage: the advantage
+/// minus the advantage when the strong team has no edge in that component
+/// only. All simulations use the same base seed, so that the differences
+/// are not dominated by noise.
+pub fn component_contributions(
+    runner: &Runner,
+    num_teams: usize,
+    num_components: usize,
+    num_runs: usize,
+    base_seed: u64,
+    get_teams: impl Fn(&mut SimRng) -> Vec<Team> + Sync,
+) -> Vec<f64> {
+    let advantage =
+        |runner: &Runner| seeded_advantage(runner, num_teams, num_runs, base_seed, &get_teams);
+    let full = advantage(runner);
+    (0..num_components)
+        .map(|component| full - advantage(&runner.clone().with_neutral_component(component)))
+        .collect()
+}
+
+/// The sensitivity of the advantage to the edge of the strong team in each
+/// component: the change of the advantage per relative change of the edge
+/// in that component only, estimated by a central difference with a step
+/// of [`SENSITIVITY_STEP`]. Like [`component_contributions`], all
+/// simulations use the same base seed.
+pub fn component_sensitivities(
+    runner: &Runner,
+    num_teams: usize,
+    num_components: usize,
+    num_runs: usize,
+    base_seed: u64,
+    get_teams: impl Fn(&mut SimRng) -> Vec<Team> + Sync,
+) -> Vec<f64> {
+    let advantage = |component, factor| {
+        let runner = runner.clone().with_scaled_component_edge(component, factor);
+        seeded_advantage(&runner, num_teams, num_runs, base_seed, &get_teams)
+    };
+    (0..num_components)
+        .map(|component| {
+            let stronger = advantage(component, 1.0 + SENSITIVITY_STEP);
+            let weaker = advantage(component, 1.0 - SENSITIVITY_STEP);
+            (stronger - weaker) / (2.0 * SENSITIVITY_STEP)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+    use std::time::Instant;
+
+    use rand::thread_rng;
+
+    use super::compare_advantages;
+    use super::component_contributions;
+    use super::component_sensitivities;
+    use super::equal_teams_title_probabilities;
+    use super::estimate_advantage;
+    use super::estimate_conditional_advantage;
+    use super::estimate_maximum_advantage;
+    use super::get_misseeded_teams;
+    use super::get_noisily_seeded_teams;
+    use super::get_strong_teams;
+    use super::get_teams;
+    use super::title_probabilities;
+    use super::RunLength;
+    use super::StopCondition;
+    use super::CHUNK_SIZE;
+    use crate::runner::Runner;
+
+    fn final_runner() -> Runner {
+        Runner::new(
+            serde_yaml::from_str(
+                "
+components:
+  final:
+    type: !BestOf1
+    teams: [0, 1]
+scoring:
+  [winner, final]: 1.0
+",
+            )
+            .unwrap(),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn conditional_advantage_only_counts_runs_that_reach_the_component() {
+        let runner = Runner::new(
+            serde_yaml::from_str(
+                "
+components:
+  qualifier:
+    type: !BestOf1
+    teams: [0, 1]
+  final:
+    type: !BestOf1
+    teams: [[winner, qualifier], 2]
+scoring:
+  [winner, final]: 1.0
+",
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        let report =
+            estimate_conditional_advantage(&runner, 3, 1, 40000, Some(0), |rng| get_teams(3, rng));
+        // The strong team is seeded into the final or wins the qualifier.
+        assert!((report.probability - (1.0 / 3.0 + 2.0 / 3.0 * 0.6)).abs() < 0.01);
+        assert_eq!(
+            report.advantage.num_runs,
+            (report.probability * 40000.0).round() as u64
+        );
+        // Within these runs, it wins the final with probability 0.6.
+        let expected = (0.6 - 1.0 / 3.0) / crate::STRONG_TEAM_ADVANTAGE;
+        assert!((report.advantage.advantage - expected).abs() < 0.1);
+        assert!(report.advantage.ci_half_width < 0.1);
+        // Team 2 skips the qualifier.
+        let qualifier =
+            estimate_conditional_advantage(&runner, 3, 0, 10000, Some(0), |rng| get_teams(3, rng));
+        assert!((qualifier.probability - 2.0 / 3.0).abs() < 0.02);
+    }
+
+    #[test]
+    fn strong_teams_are_distinct() {
+        let mut rng = thread_rng();
+        for _ in 0..100 {
+            let teams = get_strong_teams(8, 3, &mut rng);
+            assert_eq!(teams.iter().filter(|team| team.is_strong()).count(), 3);
+        }
+    }
+
+    #[test]
+    fn paired_comparison_detects_the_longer_series() {
+        let bo1 = final_runner();
+        let itself = compare_advantages((&bo1, 2), (&bo1, 2), 1, 10000, Some(0));
+        assert_eq!(itself.mean_difference, 0.0);
+        assert_eq!(itself.p_value, 1.0);
+        let bo5 = Runner::new(
+            serde_yaml::from_str(
+                "
+components:
+  final:
+    type: !BestOf5
+    teams: [0, 1]
+scoring:
+  [winner, final]: 1.0
+",
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        let comparison = compare_advantages((&bo5, 2), (&bo1, 2), 1, 10000, Some(0));
+        assert!(comparison.mean_difference > 0.0);
+        assert!(comparison.p_value < 0.01);
+    }
+
+    #[test]
+    fn same_base_seed_gives_same_report() {
+        let runner = final_runner();
+        let report = |base_seed| {
+            estimate_advantage(
+                &runner,
+                2,
+                RunLength::Fixed(10000),
+                Some(base_seed),
+                |rng| get_teams(2, rng),
+            )
+        };
+        let (first, second) = (report(7), report(7));
+        assert_eq!(first.advantage, second.advantage);
+        assert_eq!(first.ci_half_width, second.ci_half_width);
+        assert_eq!(first.mean_score, second.mean_score);
+        assert_ne!(first.mean_score, report(8).mean_score);
+    }
+
+    #[test]
+    fn deadline_stops_after_the_first_chunk() {
+        let advantage = |deadline| {
+            let stop = StopCondition {
+                length: RunLength::Fixed(3 * CHUNK_SIZE),
+                deadline,
+            };
+            estimate_advantage(&final_runner(), 2, stop, Some(0), |rng| get_teams(2, rng))
+        };
+        let passed = advantage(Some(Instant::now()));
+        assert_eq!(passed.num_runs, CHUNK_SIZE as u64);
+        assert!(passed.ci_half_width.is_finite());
+        let far = advantage(Some(Instant::now() + Duration::from_secs(3600)));
+        let unbounded = advantage(None);
+        assert_eq!(far.num_runs, 3 * CHUNK_SIZE as u64);
+        assert!((far.advantage - unbounded.advantage).abs() < 1e-12);
+    }
+
+    #[test]
+    fn advantage_of_a_single_game() {
+        // The strong team wins with probability 0.5 + STRONG_TEAM_ADVANTAGE
+        // against an average score of 0.5, so the advantage is exactly one.
+        let report =
+            estimate_advantage(&final_runner(), 2, RunLength::Fixed(100000), None, |rng| {
+                get_teams(2, rng)
+            });
+        assert!((report.advantage - 1.0).abs() < 2.0 * report.ci_half_width);
+    }
+
+    #[test]
+    fn the_only_component_contributes_the_whole_advantage() {
+        let runner = final_runner();
+        let contributions =
+            component_contributions(&runner, 2, 1, 10000, 0, |rng| get_teams(2, rng));
+        let report = estimate_advantage(&runner, 2, RunLength::Fixed(10000), Some(0), |rng| {
+            get_teams(2, rng)
+        });
+        // Without an edge in the final, the advantage vanishes up to noise.
+        assert_eq!(contributions.len(), 1);
+        assert!((contributions[0] - report.advantage).abs() < report.ci_half_width);
+    }
+
+    #[test]
+    fn sensitivity_of_a_single_game() {
+        // The advantage of a single game is proportional to the edge.
+        let sensitivities =
+            component_sensitivities(&final_runner(), 2, 1, 10000, 0, |rng| get_teams(2, rng));
+        assert_eq!(sensitivities.len(), 1);
+        assert!((sensitivities[0] - 1.0).abs() < 0.05, "{:?}", sensitivities);
+    }
+
+    #[test]
+    fn no_advantage_without_an_edge() {
+        let runner = Runner::new(
+            serde_yaml::from_str(
+                "
+components:
+  group:
+    type: !GroupStage {num_games_per_series: 1}
+    teams: [0, 1, 2, 3]
+scoring:
+  [1st, group]: 3.0
+  [2nd, group]: 1.0
+",
+            )
+            .unwrap(),
+        )
+        .unwrap()
+        .with_strong_team_advantage(0.0);
+        let report = estimate_advantage(&runner, 4, RunLength::Fixed(20000), Some(0), |rng| {
+            get_teams(4, rng)
+        });
+        assert!(report.is_noise_dominated());
+        assert_eq!(report.edge, 0.0);
+        assert!(report.advantage.is_finite() && report.ci_half_width.is_finite());
+    }
+
+    #[test]
+    fn negative_edge_is_amplified_like_a_positive_one() {
+        let runner = final_runner().with_strong_team_advantage(-0.1);
+        let report = estimate_advantage(&runner, 2, RunLength::Fixed(100000), Some(0), |rng| {
+            get_teams(2, rng)
+        });
+        assert!((report.advantage - 1.0).abs() < 2.0 * report.ci_half_width);
+        let maximum = estimate_maximum_advantage(&runner, 2, RunLength::Fixed(1000), None, |rng| {
+            get_teams(2, rng)
+        });
+        assert!((maximum.advantage - 5.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn title_probabilities_of_equal_teams_by_seed() {
+        let probabilities = |r#type: &str| {
+            let runner = Runner::new(
+                serde_yaml::from_str(&format!(
+                    "
+ratings: {{a: 1800, b: 1600, c: 1400, d: 1200}}
+components:
+  playoff:
+    type: {}
+    teams: [0, 1, 2, 3]
+scoring:
+  [winner, playoff]: 1.0
+",
+                    r#type
+                ))
+                .unwrap(),
+            )
+            .unwrap();
+            equal_teams_title_probabilities(&runner, 4, 40000, Some(0))
+        };
+        // Despite the ratings, a bracket gives every seed the same chance.
+        for p in probabilities("!SingleElimination {num_games_per_series: 1}") {
+            assert!((p - 0.25).abs() < 0.01);
+        }
+        // The holder of a gauntlet has to win all three series.
+        let gauntlet = probabilities("!Gauntlet {num_games_per_series: 1}");
+        assert!((gauntlet[0] - 0.125).abs() < 0.01);
+    }
+
+    #[test]
+    fn sampled_ratings_favor_the_top_seeds() {
+        let runner = Runner::new(
+            serde_yaml::from_str(
+                "
+rating_distribution: !Normal {mean: 1500, std_dev: 300}
+components:
+  playoff:
+    type: !SingleElimination {num_games_per_series: 1}
+    teams: [0, 1, 2, 3]
+scoring:
+  [winner, playoff]: 1.0
+",
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        let probabilities = title_probabilities(&runner, 4, 20000, Some(0));
+        assert!((probabilities.iter().sum::<f64>() - 1.0).abs() < 1e-9);
+        assert!(probabilities.windows(2).all(|pair| pair[0] > pair[1]));
+        // The same seed draws the same ratings.
+        assert_eq!(
+            probabilities,
+            title_probabilities(&runner, 4, 20000, Some(0))
+        );
+    }
+
+    #[test]
+    fn maximum_advantage_of_a_single_game() {
+        // The strong team always wins, scoring 1 against an average of 0.5.
+        let report =
+            estimate_maximum_advantage(&final_runner(), 2, RunLength::Fixed(1000), None, |rng| {
+                get_teams(2, rng)
+            });
+        assert!((report.advantage - 0.5 / crate::STRONG_TEAM_ADVANTAGE).abs() < 1e-9);
+        // With both teams strong, the strong teams score the average.
+        let report =
+            estimate_maximum_advantage(&final_runner(), 2, RunLength::Fixed(1000), None, |rng| {
+                get_strong_teams(2, 2, rng)
+            });
+        assert!(report.advantage.abs() < 1e-9);
+    }
+
+    #[cfg(not(feature = "parallel"))]
+    #[test]
+    fn sequential_simulation_is_reproducible() {
+        let runner = final_runner();
+        let run = || super::simulate(&runner, 2, 0..1000, None, &|rng| get_teams(2, rng)).advantage;
+        let (first, second) = (run(), run());
+        assert_eq!(first.mean(), second.mean());
+        assert_eq!(first.variance(), second.variance());
+    }
+
+    #[test]
+    fn noisy_seeding() {
+        let mut rng = thread_rng();
+        let ratings = [1500.0, 1700.0, 1600.0];
+        let order = |noise, rng: &mut _| -> Vec<_> {
+            get_noisily_seeded_teams(&ratings, noise, rng)
+                .iter()
+                .map(|team| team.index)
+                .collect()
+        };
+        assert_eq!(order(0.0, &mut rng), vec![1, 2, 0]);
+        let num_misseeded = (0..1000)
+            .filter(|_| order(1000.0, &mut rng) != vec![1, 2, 0])
+            .count();
+        assert!(num_misseeded > 500);
+    }
+
+    #[test]
+    fn misseeding() {
+        let mut rng = thread_rng();
+        let mut strong_position = |rate| {
+            get_misseeded_teams(4, rate, &mut rng)
+                .iter()
+                .position(|team| team.is_strong())
+                .unwrap()
+        };
+        assert_eq!(strong_position(0.0), 0);
+        assert_eq!(strong_position(1.0), 3);
+    }
+}
@@ -0,0 +1,84 @@
+use rusqlite::params;
+use rusqlite::Connection;
+
+use crate::report::AdvantageReport;
+
+/// Opens the database and creates the results table if it does not exist.
+pub fn open(path: &str) -> rusqlite::Result<Connection> {
+    let connection = Connection::open(path)?;
+    create_schema(&connection)?;
+    Ok(connection)
+}
+
+fn create_schema(connection: &Connection) -> rusqlite::Result<()> {
+    connection.execute(
+        "CREATE TABLE IF NOT EXISTS results (
+            file TEXT NOT NULL,
+            seed INTEGER,
+            num_runs INTEGER NOT NULL,
+            advantage REAL NOT NULL,
+            ci_half_width REAL NOT NULL,
+            parameter REAL
+        )",
+        [],
+    )?;
+    Ok(())
+}
+
+/// Inserts one row for a simulated tournament. `seed` and the sweep
+/// `parameter` are stored as NULL if not given.
+pub fn insert(
+    connection: &Connection,
+    file: &str,
+    seed: Option<u64>,
+    parameter: Option<f64>,
+    report: &AdvantageReport,
+) -> rusqlite::Result<()> {
+    connection.execute(
+        "INSERT INTO results (file, seed, num_runs, advantage, ci_half_width, parameter)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+        params![
+            file,
+            seed.map(|seed| seed as i64),
+            report.num_runs as i64,
+            report.advantage,
+            report.ci_half_width,
+            parameter
+        ],
+    )?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use rusqlite::Connection;
+
+    use crate::report::AdvantageReport;
+    use crate::report::SimulationStats;
+
+    #[test]
+    fn rows_are_inserted() {
+        let connection = Connection::open_in_memory().unwrap();
+        super::create_schema(&connection).unwrap();
+        // Creating the schema twice is fine.
+        super::create_schema(&connection).unwrap();
+        let report = AdvantageReport {
+            num_runs: 100,
+            advantage: 1.5,
+            ci_half_width: 0.25,
+            ..AdvantageReport::from_stats(&SimulationStats::default())
+        };
+        super::insert(&connection, "a.yml", None, Some(0.1), &report).unwrap();
+        let (file, num_runs, advantage, parameter): (String, i64, f64, Option<f64>) = connection
+            .query_row(
+                "SELECT file, num_runs, advantage, parameter FROM results",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?)),
+            )
+            .unwrap();
+        assert_eq!(
+            (file.as_str(), num_runs, advantage, parameter),
+            ("a.yml", 100, 1.5, Some(0.1))
+        );
+    }
+}
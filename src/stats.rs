@@ -0,0 +1,219 @@
+use std::collections::BTreeMap;
+
+use ordered_float::OrderedFloat;
+
+/// The Gini coefficient of non-negative values, the mean absolute difference
+/// between all pairs divided by twice the mean. `None` unless the values
+/// have a positive mean.
+pub fn gini(values: &[f64]) -> Option<f64> {
+    let n = values.len() as f64;
+    let mean = values.iter().sum::<f64>() / n;
+    if mean.is_nan() || mean <= 0.0 {
+        return None;
+    }
+    let total_difference: f64 = values
+        .iter()
+        .flat_map(|x| values.iter().map(move |y| (x - y).abs()))
+        .sum();
+    Some(total_difference / (2.0 * n * n * mean))
+}
+
+/// Mean and variance of a stream of samples (Welford's online algorithm).
+/// Partial results of a parallel computation are combined with
+/// [`RunningStats::merge`] (Chan et al.), so no samples are ever stored.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct RunningStats {
+    count: u64,
+    mean: f64,
+    m2: f64,
+}
+
+impl RunningStats {
+    pub fn push(&mut self, x: f64) {
+        self.count += 1;
+        let delta = x - self.mean;
+        self.mean += delta / self.count as f64;
+        self.m2 += delta * (x - self.mean);
+    }
+
+    pub fn merge(self, other: Self) -> Self {
+        if self.count == 0 {
+            return other;
+        }
+        if other.count == 0 {
+            return self;
+        }
+        let count = self.count + other.count;
+        let delta = other.mean - self.mean;
+        Self {
+            count,
+            mean: self.mean + delta * other.count as f64 / count as f64,
+            m2: self.m2
+                + other.m2
+                + delta * delta * self.count as f64 * other.count as f64 / count as f64,
+        }
+    }
+
+    pub fn count(&self) -> u64 {
+        self.count
+    }
+
+    pub fn mean(&self) -> f64 {
+        self.mean
+    }
+
+    /// The unbiased sample variance.
+    pub fn variance(&self) -> f64 {
+        if self.count < 2 {
+            0.0
+        } else {
+            self.m2 / (self.count - 1) as f64
+        }
+    }
+
+    /// The standard error of the mean.
+    pub fn std_error(&self) -> f64 {
+        if self.count == 0 {
+            0.0
+        } else {
+            (self.variance() / self.count as f64).sqrt()
+        }
+    }
+}
+
+impl std::iter::Sum<f64> for RunningStats {
+    fn sum<I: Iterator<Item = f64>>(iter: I) -> Self {
+        let mut stats = Self::default();
+        for x in iter {
+            stats.push(x);
+        }
+        stats
+    }
+}
+
+impl std::iter::Sum for RunningStats {
+    fn sum<I: Iterator<Item = Self>>(iter: I) -> Self {
+        iter.fold(Self::default(), Self::merge)
+    }
+}
+
+/// Counts how often each value occurred. Scores only take the few values
+/// that sums of the scoring entries can take, so the counts stay small and
+/// quantiles are exact.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Histogram {
+    counts: BTreeMap<OrderedFloat<f64>, u64>,
+}
+
+impl Histogram {
+    pub fn push(&mut self, x: f64) {
+        *self.counts.entry(OrderedFloat(x)).or_default() += 1;
+    }
+
+    pub fn merge(mut self, other: Self) -> Self {
+        for (x, count) in other.counts {
+            *self.counts.entry(x).or_default() += count;
+        }
+        self
+    }
+
+    pub fn mean(&self) -> f64 {
+        let total: u64 = self.counts.values().sum();
+        self.counts
+            .iter()
+            .map(|(x, count)| x.0 * *count as f64)
+            .sum::<f64>()
+            / total as f64
+    }
+
+    /// The fraction of samples that are less than `x`, or NaN if there are no samples.
+    pub fn fraction_below(&self, x: f64) -> f64 {
+        let total: u64 = self.counts.values().sum();
+        let below: u64 = self
+            .counts
+            .range(..OrderedFloat(x))
+            .map(|(_, count)| count)
+            .sum();
+        below as f64 / total as f64
+    }
+
+    /// The smallest value such that at least a fraction `q` of the samples
+    /// are less than or equal to it, or NaN if there are no samples.
+    pub fn quantile(&self, q: f64) -> f64 {
+        let total: u64 = self.counts.values().sum();
+        let mut cumulative = 0;
+        for (x, count) in self.counts.iter() {
+            cumulative += count;
+            if cumulative as f64 >= q * total as f64 {
+                return x.0;
+            }
+        }
+        f64::NAN
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::gini;
+    use super::Histogram;
+    use super::RunningStats;
+
+    #[test]
+    fn gini_coefficient() {
+        assert_eq!(gini(&[1.0, 1.0, 1.0, 1.0]), Some(0.0));
+        // All 6 ordered pairs with the winner differ by 1, divided by 2 * 16 * 0.25.
+        assert_eq!(gini(&[0.0, 0.0, 0.0, 1.0]), Some(0.75));
+        // The ordered pairs differ by 1, 2, 1 in both directions, divided by 2 * 9 * 2.
+        assert!((gini(&[1.0, 2.0, 3.0]).unwrap() - 8.0 / 36.0).abs() < 1e-12);
+        assert_eq!(gini(&[0.0, 0.0]), None);
+        assert_eq!(gini(&[]), None);
+    }
+
+    fn brute_force_variance(xs: &[f64]) -> f64 {
+        let mean = xs.iter().sum::<f64>() / xs.len() as f64;
+        xs.iter().map(|x| (x - mean).powi(2)).sum::<f64>() / (xs.len() - 1) as f64
+    }
+
+    #[test]
+    fn matches_brute_force() {
+        let xs: Vec<f64> = (0..1000).map(|i| ((i * 37) % 101) as f64 / 7.0).collect();
+        let stats: RunningStats = xs.iter().copied().sum();
+        assert_eq!(stats.count(), 1000);
+        assert!((stats.mean() - xs.iter().sum::<f64>() / 1000.0).abs() < 1e-10);
+        assert!((stats.variance() - brute_force_variance(&xs)).abs() < 1e-10);
+    }
+
+    #[test]
+    fn merging_matches_single_pass() {
+        let xs: Vec<f64> = (0..1000).map(|i| ((i * 13) % 17) as f64).collect();
+        let single: RunningStats = xs.iter().copied().sum();
+        let merged: RunningStats = xs
+            .chunks(71)
+            .map(|chunk| chunk.iter().copied().sum::<RunningStats>())
+            .sum();
+        assert_eq!(merged.count(), single.count());
+        assert!((merged.mean() - single.mean()).abs() < 1e-10);
+        assert!((merged.variance() - single.variance()).abs() < 1e-10);
+        assert_eq!(RunningStats::default().merge(single), single);
+    }
+
+    #[test]
+    fn histogram_quantiles() {
+        let histogram = [0.0, 0.0, 0.0, 1.0, 1.0, 2.0, 3.0, 3.0, 3.0, 10.0]
+            .chunks(3)
+            .map(|chunk| {
+                let mut histogram = Histogram::default();
+                chunk.iter().for_each(|x| histogram.push(*x));
+                histogram
+            })
+            .fold(Histogram::default(), Histogram::merge);
+        assert_eq!(histogram.quantile(0.3), 0.0);
+        assert_eq!(histogram.quantile(0.5), 1.0);
+        assert_eq!(histogram.quantile(0.9), 3.0);
+        assert_eq!(histogram.quantile(1.0), 10.0);
+        assert_eq!(histogram.mean(), 2.3);
+        assert_eq!(histogram.fraction_below(1.0), 0.3);
+        assert_eq!(histogram.fraction_below(0.0), 0.0);
+        assert!(Histogram::default().quantile(0.5).is_nan());
+    }
+}
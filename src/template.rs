@@ -0,0 +1,133 @@
+//! Reusable component types. A document may define named component types
+//! under `templates`, which components refer to with `type: !Template name`:
+//!
+//! ```yaml
+//! templates:
+//!   series: !HandicapBestOfN { num_games: 5, handicap: 1 }
+//! components:
+//!   final:
+//!     type: !Template series
+//!     teams: [0, 1]
+//! ```
+//!
+//! The references are replaced by the templates before the document is
+//! deserialized into a [`crate::Tournament`].
+
+use serde_yaml::Mapping;
+use serde_yaml::Value;
+
+use crate::error::TournamentError;
+
+const TEMPLATES_KEY: &str = "templates";
+const TEMPLATE_TAG: &str = "Template";
+
+/// Replaces every template reference in `document` by the template it
+/// names and removes the template definitions.
+pub fn expand_templates(mut document: Value) -> Result<Value, TournamentError> {
+    let Some(mapping) = document.as_mapping_mut() else {
+        return Ok(document);
+    };
+    let templates = match mapping.remove(TEMPLATES_KEY) {
+        Some(Value::Mapping(templates)) => templates,
+        Some(Value::Null) | None => Mapping::new(),
+        Some(_) => {
+            return Err(TournamentError::InvalidTemplate(
+                "templates must map names to component types".into(),
+            ))
+        }
+    };
+    let Some(Value::Mapping(components)) = mapping.get_mut("components") else {
+        return Ok(document);
+    };
+    for (name, component) in components.iter_mut() {
+        let Some(r#type) = component.get_mut("type") else {
+            continue;
+        };
+        let Value::Tagged(tagged) = r#type else {
+            continue;
+        };
+        if tagged.tag != TEMPLATE_TAG {
+            continue;
+        }
+        let template = tagged.value.as_str().ok_or_else(|| {
+            TournamentError::InvalidTemplate(format!(
+                "the template reference of component {} must be a name",
+                component_name(name)
+            ))
+        })?;
+        let expanded = templates
+            .get(template)
+            .ok_or_else(|| TournamentError::UnknownTemplate {
+                component: component_name(name),
+                template: template.to_owned(),
+            })?
+            .clone();
+        *r#type = expanded;
+    }
+    Ok(document)
+}
+
+fn component_name(name: &Value) -> String {
+    match name {
+        Value::String(name) => name.clone(),
+        other => serde_yaml::to_string(other)
+            .unwrap_or_default()
+            .trim()
+            .to_owned(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::expand_templates;
+    use crate::error::TournamentError;
+
+    #[test]
+    fn templates_are_expanded() {
+        let tournaments = crate::parse_tournaments(
+            "
+templates:
+  series: !HandicapBestOfN { num_games: 5, handicap: 1 }
+components:
+  semi:
+    type: !Template series
+    teams: [0, 1]
+  final:
+    type: !Template series
+    teams: [[winner, semi], 2]
+scoring:
+  [winner, final]: 1.0
+",
+        )
+        .unwrap();
+        let tournament = &tournaments[0];
+        for name in ["semi", "final"] {
+            assert_eq!(
+                tournament.component(name).unwrap().r#type.to_string(),
+                "BestOf5(handicap 1)"
+            );
+        }
+    }
+
+    #[test]
+    fn unknown_templates_are_rejected() {
+        let document = serde_yaml::from_str(
+            "
+templates:
+  series: !BestOf3
+components:
+  final:
+    type: !Template seires
+    teams: [0, 1]
+",
+        )
+        .unwrap();
+        assert_eq!(
+            expand_templates(document).unwrap_err(),
+            TournamentError::UnknownTemplate {
+                component: "final".into(),
+                template: "seires".into(),
+            }
+        );
+    }
+}
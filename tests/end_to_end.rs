@@ -0,0 +1,103 @@
+use std::process::Command;
+
+use tournament_sim::math::best_of_n_win_probability;
+use tournament_sim::read_tournament;
+use tournament_sim::report::AdvantageReport;
+use tournament_sim::runner::Runner;
+use tournament_sim::simulation::estimate_advantage;
+use tournament_sim::simulation::get_teams;
+use tournament_sim::simulation::RunLength;
+use tournament_sim::STRONG_TEAM_ADVANTAGE;
+
+const NUM_RUNS: usize = 200000;
+
+fn fixture_advantage(fixture: &str, base_seed: u64) -> AdvantageReport {
+    let file = format!("{}/tests/fixtures/{}", env!("CARGO_MANIFEST_DIR"), fixture);
+    let tournament = read_tournament(&file).unwrap().remove(0);
+    let num_teams = tournament.num_teams();
+    estimate_advantage(
+        &Runner::new(tournament).unwrap(),
+        num_teams,
+        RunLength::Fixed(NUM_RUNS),
+        Some(base_seed),
+        |rng| get_teams(num_teams, rng),
+    )
+}
+
+/// The advantage of a strong team that wins the title with probability
+/// `p` against the average of `num_teams` teams, in units of the edge.
+fn expected_advantage(p: f64, num_teams: usize) -> f64 {
+    (p - 1.0 / num_teams as f64) / STRONG_TEAM_ADVANTAGE
+}
+
+fn assert_advantage(report: &AdvantageReport, expected: f64) {
+    assert!(
+        (report.advantage - expected).abs() < 2.0 * report.ci_half_width,
+        "{} vs {}",
+        report,
+        expected
+    );
+}
+
+#[test]
+fn single_game_final_does_not_amplify_the_edge() {
+    let report = fixture_advantage("bo1_final.yml", 0);
+    assert_advantage(&report, 1.0);
+}
+
+#[test]
+fn best_of_seven_final_amplifies_the_edge() {
+    let p = best_of_n_win_probability(0.5 + STRONG_TEAM_ADVANTAGE, 7);
+    let bo7 = fixture_advantage("bo7_final.yml", 0);
+    assert_advantage(&bo7, expected_advantage(p, 2));
+    let bo1 = fixture_advantage("bo1_final.yml", 0);
+    assert!(bo7.advantage - bo7.ci_half_width > bo1.advantage + bo1.ci_half_width);
+}
+
+#[test]
+fn bracket_winner_wins_every_round() {
+    let p = 0.5 + STRONG_TEAM_ADVANTAGE;
+    let report = fixture_advantage("four_team_bracket.yml", 0);
+    assert_advantage(&report, expected_advantage(p * p, 4));
+}
+
+#[test]
+fn same_base_seed_reproduces_the_advantage() {
+    let first = fixture_advantage("four_team_bracket.yml", 1);
+    let second = fixture_advantage("four_team_bracket.yml", 1);
+    assert_eq!(first.num_runs, second.num_runs);
+    assert_eq!(first.advantage, second.advantage);
+    assert_eq!(first.ci_half_width, second.ci_half_width);
+}
+
+#[test]
+fn same_base_seed_reproduces_the_output() {
+    let run = || {
+        let output = Command::new(env!("CARGO_BIN_EXE_tournament_sim"))
+            .args(["--base-seed", "3", "--num-runs", "20000"])
+            .arg(format!(
+                "{}/tests/fixtures/four_team_bracket.yml",
+                env!("CARGO_MANIFEST_DIR")
+            ))
+            .output()
+            .unwrap();
+        assert!(output.status.success());
+        output.stdout
+    };
+    assert_eq!(run(), run());
+}
+
+#[test]
+fn malformed_files_are_reported_with_their_name() {
+    let file = std::env::temp_dir().join("tournament_sim_malformed.yml");
+    std::fs::write(&file, "components: [\n").unwrap();
+    let output = Command::new(env!("CARGO_BIN_EXE_tournament_sim"))
+        .arg(&file)
+        .output()
+        .unwrap();
+    std::fs::remove_file(&file).unwrap();
+    assert!(!output.status.success());
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.starts_with(&format!("Error: {}: Invalid tournament: ", file.display())));
+    assert!(stderr.contains("line 2"));
+}
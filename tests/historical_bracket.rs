@@ -0,0 +1,39 @@
+use tournament_sim::math::elo_win_probability;
+use tournament_sim::read_tournament;
+use tournament_sim::runner::Runner;
+use tournament_sim::simulation::title_probabilities;
+
+#[test]
+fn title_probabilities_of_a_rated_bracket() {
+    let file = format!("{}/historical_bracket.yml", env!("CARGO_MANIFEST_DIR"));
+    let tournament = read_tournament(&file).unwrap().remove(0);
+    assert!(tournament.has_ratings());
+    assert_eq!(
+        tournament.team_names(),
+        vec!["Alpha", "Bravo", "Charlie", "Delta"]
+    );
+    let num_teams = tournament.num_teams();
+    let probabilities = title_probabilities(
+        &Runner::new(tournament).unwrap(),
+        num_teams,
+        200000,
+        Some(0),
+    );
+
+    let p = elo_win_probability;
+    // Charlie and Delta are rated equally, so each wins semifinal_2 half the time.
+    let alpha = p(1600.0, 1400.0) * p(1600.0, 1500.0);
+    let bravo = p(1400.0, 1600.0) * p(1400.0, 1500.0);
+    let charlie =
+        0.5 * (p(1600.0, 1400.0) * p(1500.0, 1600.0) + p(1400.0, 1600.0) * p(1500.0, 1400.0));
+    let expected = [alpha, bravo, charlie, charlie];
+    assert!((expected.iter().sum::<f64>() - 1.0).abs() < 1e-12);
+    for (probability, expected) in probabilities.iter().zip(expected) {
+        assert!(
+            (probability - expected).abs() < 0.01,
+            "{:?} vs {:?}",
+            probabilities,
+            expected
+        );
+    }
+}
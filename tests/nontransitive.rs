@@ -0,0 +1,44 @@
+use tournament_sim::parse_tournaments;
+use tournament_sim::runner::Runner;
+use tournament_sim::simulation::title_probabilities;
+
+/// Alpha beats Bravo beats Charlie beats Alpha, each in a single game.
+const CYCLE: &str = "
+win_probabilities:
+  - [0.5, 0.9, 0.4]
+  - [0.1, 0.5, 0.7]
+  - [0.6, 0.3, 0.5]
+components:
+  group:
+    type: !GroupStage {num_games_per_series: 1}
+    teams: [0, 1, 2]
+scoring:
+  [1st, group]: 1.0
+";
+
+#[test]
+fn group_stage_with_cyclic_dominance() {
+    let tournament = parse_tournaments(CYCLE).unwrap().remove(0);
+    assert!(tournament.has_matchups());
+    let probabilities = title_probabilities(&Runner::new(tournament).unwrap(), 3, 200000, Some(0));
+    // A team finishes first outright by winning both of its games. Otherwise
+    // all three teams won one game each and the group is replayed.
+    let outright = [0.9 * 0.4, 0.1 * 0.7, 0.6 * 0.3];
+    let replayed = 1.0 - outright.iter().sum::<f64>();
+    for (probability, outright) in probabilities.iter().zip(outright) {
+        let expected = outright / (1.0 - replayed);
+        assert!(
+            (probability - expected).abs() < 0.01,
+            "{:?} vs {}",
+            probabilities,
+            expected
+        );
+    }
+}
+
+#[test]
+fn inconsistent_win_probabilities_are_rejected() {
+    let error =
+        parse_tournaments(&CYCLE.replace("[0.1, 0.5, 0.7]", "[0.2, 0.5, 0.7]")).unwrap_err();
+    assert!(error.to_string().contains("add up to 1"));
+}
@@ -0,0 +1,33 @@
+use std::fs;
+use std::path::Path;
+
+use tournament_sim::parse_tournaments;
+use tournament_sim::read_tournament;
+use tournament_sim::to_yaml;
+
+fn tournament_files(dir: &Path) -> Vec<String> {
+    fs::read_dir(dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().is_some_and(|extension| extension == "yml"))
+        .map(|path| path.to_str().unwrap().to_owned())
+        .collect()
+}
+
+#[test]
+fn normalized_tournaments_parse_back_unchanged() {
+    let root = Path::new(env!("CARGO_MANIFEST_DIR"));
+    let files: Vec<_> = tournament_files(root)
+        .into_iter()
+        .chain(tournament_files(&root.join("tests/fixtures")))
+        .collect();
+    assert!(!files.is_empty());
+    for file in files {
+        for tournament in read_tournament(&file).unwrap() {
+            let normalized = to_yaml(&tournament);
+            let reparsed = parse_tournaments(&normalized).unwrap();
+            assert_eq!(reparsed.len(), 1, "{}", file);
+            assert_eq!(to_yaml(&reparsed[0]), normalized, "{}", file);
+        }
+    }
+}